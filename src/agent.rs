@@ -1,15 +1,64 @@
+use crate::credits::{self, CreditStyle};
 use crate::error::Result;
+use crate::feedback::{self, FeedbackStore};
 use crate::llm::LLMClient;
-use crate::metadata::TrackMetadata;
-use crate::suggestions::{MetadataSuggestion, SuggestionsReport};
+use crate::metadata::encoding::{self, SourceEncoding};
+use crate::metadata::formats::AudioFormat;
+use crate::metadata::{join_values, split_values, TrackMetadata, MULTI_VALUE_FIELDS};
+use crate::prompts::{PromptTemplates, TemplateInfo};
+use crate::suggestions::{
+    MetadataSuggestion, SuggestionAction, SuggestionStatus, SuggestionsReport,
+};
+use std::path::Path;
 
 pub struct MusicAgent {
     llm: Box<dyn LLMClient>,
+    templates: PromptTemplates,
+    feedback: Option<FeedbackStore>,
+    few_shot_limit: usize,
+    credit_style: Option<CreditStyle>,
 }
 
 impl MusicAgent {
     pub fn new(llm: Box<dyn LLMClient>) -> Self {
-        Self { llm }
+        Self {
+            llm,
+            templates: PromptTemplates::default(),
+            feedback: None,
+            few_shot_limit: 0,
+            credit_style: None,
+        }
+    }
+
+    pub fn with_templates(mut self, templates: PromptTemplates) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Inject up to `limit` past reviewer decisions as few-shot examples
+    pub fn with_feedback(mut self, store: FeedbackStore, limit: usize) -> Self {
+        self.feedback = Some(store);
+        self.few_shot_limit = limit;
+        self
+    }
+
+    /// Normalize featured-artist and producer credits in suggestions to this style
+    pub fn with_credit_style(mut self, style: CreditStyle) -> Self {
+        self.credit_style = Some(style);
+        self
+    }
+
+    pub fn provider_name(&self) -> &str {
+        self.llm.provider_name()
+    }
+
+    pub fn model_name(&self) -> &str {
+        self.llm.model_name()
+    }
+
+    /// Template used for structured suggestions
+    pub fn suggestions_template(&self) -> TemplateInfo {
+        self.templates.suggestions.info()
     }
 
     /// Main agent workflow: Observe → Think → Report
@@ -34,16 +83,7 @@ impl MusicAgent {
 
     /// Observe: Prepare metadata for LLM analysis
     fn observe(&self, metadata: &TrackMetadata) -> String {
-        let system_prompt = r#"You are a music metadata expert. Analyze the provided MP3 file metadata and provide:
-
-1. **Assessment**: Evaluate the quality and completeness of the metadata
-2. **Issues**: Identify any missing, incorrect, or suspicious data
-3. **Suggestions**: Recommend specific corrections or improvements
-4. **Confidence**: Rate your confidence in the current metadata (Low/Medium/High)
-
-Be concise but thorough. Focus on actionable insights."#;
-
-        format!("{}\n\n{}", system_prompt, metadata.to_prompt_format())
+        self.templates.analysis.render(metadata)
     }
 
     /// Think: Send observation to LLM for reasoning
@@ -52,72 +92,99 @@ Be concise but thorough. Focus on actionable insights."#;
     }
 
     /// Analyze track and generate structured suggestions
-    pub async fn analyze_with_suggestions(&self, metadata: &TrackMetadata) -> Result<SuggestionsReport> {
+    pub async fn analyze_with_suggestions(
+        &self,
+        metadata: &TrackMetadata,
+    ) -> Result<SuggestionsReport> {
         println!("🔍 Analyzing track with {}...", self.llm.provider_name());
-        
+
+        // Pick past reviewer decisions to teach the house style; a template without
+        // {{examples}} never shows them, so none are recorded as injected
+        let examples = match self.feedback {
+            Some(ref store) if self.templates.suggestions.has_placeholder("examples") => {
+                store.select_examples(metadata, self.few_shot_limit)
+            }
+            _ => Vec::new(),
+        };
+        if !examples.is_empty() {
+            println!(
+                "📚 Including {} few-shot example(s) from past reviews",
+                examples.len()
+            );
+        }
+
         // Build a more structured prompt for suggestions
-        let observation = self.observe_for_suggestions(metadata);
+        let observation =
+            self.observe_for_suggestions(metadata, &feedback::format_examples(&examples));
         let llm_response = self.think(&observation).await?;
-        
+
         // Parse LLM response to extract suggestions
         let suggestions = self.parse_suggestions(&llm_response, metadata);
-        
-        let report = SuggestionsReport::new(
+
+        let mut report = SuggestionsReport::new(
             metadata.file_path.clone(),
             metadata.clone(),
             suggestions,
             llm_response,
-        );
-        
-        Ok(report)
-    }
-
-    /// Build a structured prompt that asks for specific suggestions
-    fn observe_for_suggestions(&self, metadata: &TrackMetadata) -> String {
-        let system_prompt = r#"You are a music metadata expert. Analyze the MP3 metadata and provide structured suggestions.
+        )
+        .with_prompt_template(self.suggestions_template())
+        .with_few_shot_examples(examples);
 
-For each field that needs correction, respond in this EXACT format:
-
-SUGGESTION: [field_name]
-CURRENT: [current value or "None"]
-SUGGESTED: [your suggested value]
-CONFIDENCE: [High/Medium/Low]
-REASON: [brief explanation]
----
-
-Available fields: artist, title, album, year, genre, album_artist, track_number
+        // Mojibake the LLM left in place is repaired before credits are split out of it
+        let repaired = report.merge(encoding_repairs(&report.apply_suggestions()));
+        if repaired > 0 {
+            println!("🔤 Repaired text encoding in {} field(s)", repaired);
+        }
 
-Only suggest changes for fields that are missing, incorrect, or could be improved.
-If metadata is complete and accurate, respond with: "NO_SUGGESTIONS_NEEDED"
+        if let Some(style) = self.credit_style {
+            let stores_credits = AudioFormat::from_path(Path::new(&metadata.file_path))
+                .is_some_and(|format| format.stores_credits());
+            let changed = credits::merge_into(&mut report, style, stores_credits);
+            if changed > 0 {
+                println!(
+                    "🎚️  Normalized credits in {} field(s) ({} style)",
+                    changed, style
+                );
+            }
+        }
 
-After all suggestions, provide a brief OVERALL_ASSESSMENT."#;
+        Ok(report)
+    }
 
-        format!("{}\n\n{}", system_prompt, metadata.to_prompt_format())
+    /// Build a structured prompt that asks for specific suggestions
+    fn observe_for_suggestions(&self, metadata: &TrackMetadata, examples: &str) -> String {
+        self.templates
+            .suggestions
+            .render_with_examples(metadata, examples)
     }
 
     /// Parse LLM response into structured suggestions
-    fn parse_suggestions(&self, response: &str, _metadata: &TrackMetadata) -> Vec<MetadataSuggestion> {
+    fn parse_suggestions(
+        &self,
+        response: &str,
+        _metadata: &TrackMetadata,
+    ) -> Vec<MetadataSuggestion> {
         let mut suggestions = Vec::new();
-        
+
         if response.contains("NO_SUGGESTIONS_NEEDED") {
             return suggestions;
         }
-        
+
         // Split by suggestion blocks
         let blocks: Vec<&str> = response.split("---").collect();
-        
+
         for block in blocks {
             if block.trim().is_empty() || !block.contains("SUGGESTION:") {
                 continue;
             }
-            
+
             let lines: Vec<&str> = block.lines().collect();
             let mut field = String::new();
             let mut current = None;
             let mut suggested = String::new();
             let mut confidence = String::from("Medium");
             let mut reason = String::new();
-            
+
             for line in lines {
                 let line = line.trim();
                 if line.starts_with("SUGGESTION:") {
@@ -135,22 +202,62 @@ After all suggestions, provide a brief OVERALL_ASSESSMENT."#;
                     reason = line.replace("REASON:", "").trim().to_string();
                 }
             }
-            
+
             if !field.is_empty() && !suggested.is_empty() {
+                let action = if suggested == MetadataSuggestion::REMOVE_MARKER {
+                    suggested.clear();
+                    SuggestionAction::Remove
+                } else {
+                    SuggestionAction::Set
+                };
+                // Even out list separators ("MF DOOM;Madvillain" -> "MF DOOM; Madvillain")
+                if MULTI_VALUE_FIELDS.contains(&field.as_str()) {
+                    if let Some(values) = join_values(&split_values(&suggested)) {
+                        suggested = values;
+                    }
+                }
                 suggestions.push(MetadataSuggestion {
                     field,
                     current_value: current,
                     suggested_value: suggested,
                     confidence,
                     reason,
+                    action,
+                    status: SuggestionStatus::Pending,
+                    edited_from: None,
                 });
             }
         }
-        
+
         suggestions
     }
 }
 
+/// Suggestions replacing mis-encoded text with what it was meant to say
+fn encoding_repairs(metadata: &TrackMetadata) -> Vec<MetadataSuggestion> {
+    encoding::find_issues(metadata)
+        .into_iter()
+        .map(|issue| MetadataSuggestion {
+            field: issue.field,
+            current_value: Some(issue.value),
+            suggested_value: issue.repaired,
+            // A strict UTF-8 decode is near-certain; the legacy code pages are a guess
+            confidence: match issue.source {
+                SourceEncoding::Utf8 => "High",
+                _ => "Medium",
+            }
+            .to_string(),
+            reason: format!(
+                "Text was written as {} but stored as ISO-8859-1",
+                issue.source
+            ),
+            action: SuggestionAction::Set,
+            status: SuggestionStatus::Pending,
+            edited_from: None,
+        })
+        .collect()
+}
+
 /// Structured analysis report from the agent
 #[derive(Debug)]
 pub struct AnalysisReport {
@@ -0,0 +1,453 @@
+use crate::metadata::{TrackMetadata, PEOPLE_PREFIX};
+use crate::suggestions::{
+    MetadataSuggestion, SuggestionAction, SuggestionStatus, SuggestionsReport,
+};
+use std::fmt;
+
+/// Role featured artists are credited under in the involved-people list
+pub const FEATURED_ROLE: &str = "featuring";
+/// Role producers are credited under in the involved-people list
+pub const PRODUCER_ROLE: &str = "producer";
+
+/// Lower-case keywords that introduce a credit; longer spellings come first
+const MARKERS: &[(&str, Credit)] = &[
+    ("featuring ", Credit::Featured),
+    ("feat. ", Credit::Featured),
+    ("feat ", Credit::Featured),
+    ("ft. ", Credit::Featured),
+    ("ft ", Credit::Featured),
+    ("produced by ", Credit::Producer),
+    ("prod. by ", Credit::Producer),
+    ("prod by ", Credit::Producer),
+    ("prod. ", Credit::Producer),
+    ("prod ", Credit::Producer),
+];
+
+/// "with" is only a credit in brackets or artist names, not in "Dancing with Myself"
+const WITH_MARKER: (&str, Credit) = ("with ", Credit::Featured);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Credit {
+    Featured,
+    Producer,
+}
+
+/// Where featured-artist and producer credits belong
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CreditStyle {
+    /// "Title (feat. B) (prod. by C)", with only the main artists in the artist field
+    Title,
+    /// Featured artists join the artist list; producers go to the involved-people list
+    #[default]
+    Artist,
+    /// Featured artists and producers both go to the involved-people list (TMCL/TIPL)
+    People,
+}
+
+impl CreditStyle {
+    fn rule(&self) -> &'static str {
+        match self {
+            Self::Title => "credits are kept in the title as \"(feat. …)\" and \"(prod. by …)\"",
+            Self::Artist => {
+                "featured artists belong in the artist list and producers in the credits"
+            }
+            Self::People => "featured artists and producers belong in the credits (TMCL/TIPL)",
+        }
+    }
+}
+
+impl fmt::Display for CreditStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Title => "title",
+            Self::Artist => "artist",
+            Self::People => "people",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Credits split off a track's title and artist names
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CreditSplit {
+    /// Title without its credits
+    pub title: Option<String>,
+    /// Artists without their credits
+    pub artists: Vec<String>,
+    pub featured: Vec<String>,
+    pub producers: Vec<String>,
+    /// Fields the credits came from, with their values, e.g. ("title", "X (Prod By Y)")
+    pub sources: Vec<(String, String)>,
+    /// True if a credit was only inferred from "with", or names were split on a
+    /// separator that can be part of a name ("Simon & Garfunkel")
+    pub uncertain: bool,
+}
+
+impl CreditSplit {
+    /// Split the credits out of `metadata`; None if there are none
+    pub fn from_metadata(metadata: &TrackMetadata) -> Option<Self> {
+        let mut split = Self::default();
+
+        if let Some(ref title) = metadata.title {
+            let bare = split.take(title, false);
+            if split.credit_count() > 0 {
+                split.sources.push(("title".to_string(), title.clone()));
+            }
+            split.title = Some(bare).filter(|t| !t.is_empty());
+        }
+
+        for artist in &metadata.artists {
+            let before = split.credit_count();
+            let bare = split.take(artist, true);
+            if split.credit_count() > before {
+                split.sources.push(("artist".to_string(), artist.clone()));
+            }
+            if !bare.is_empty() && !contains(&split.artists, &bare) {
+                split.artists.push(bare);
+            }
+        }
+
+        (split.credit_count() > 0).then_some(split)
+    }
+
+    fn credit_count(&self) -> usize {
+        self.featured.len() + self.producers.len()
+    }
+
+    /// Remove the credits from `text` and return what is left
+    fn take(&mut self, text: &str, bare_with: bool) -> String {
+        let text = self.take_bracketed(text);
+        self.take_trailing(&text, bare_with)
+    }
+
+    /// Credits in "(feat. B)" / "[Prod By C]" groups anywhere in the text
+    fn take_bracketed(&mut self, text: &str) -> String {
+        let mut kept = String::new();
+        let mut rest = text;
+        while let Some(open) = rest.find(['(', '[']) {
+            let close = if rest.as_bytes()[open] == b'(' {
+                ')'
+            } else {
+                ']'
+            };
+            let Some(len) = rest[open + 1..].find(close) else {
+                break;
+            };
+            let end = open + 1 + len;
+            match credit_marker(&rest[open + 1..end]) {
+                Some((marker, credit, names)) => {
+                    self.uncertain |= marker == WITH_MARKER.0;
+                    self.add(credit, names);
+                    kept.push_str(&rest[..open]);
+                }
+                None => kept.push_str(&rest[..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        kept.push_str(rest);
+        kept.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// An unbracketed credit running to the end of the text ("X ft. B prod. by C")
+    fn take_trailing(&mut self, text: &str, bare_with: bool) -> String {
+        let lower = text.to_ascii_lowercase();
+        let found = MARKERS
+            .iter()
+            .chain(bare_with.then_some(&WITH_MARKER))
+            .filter_map(|(marker, credit)| {
+                let at = lower.find(&format!(" {}", marker))?;
+                Some((at, marker, *credit))
+            })
+            .min_by_key(|(at, ..)| *at);
+        let Some((at, marker, credit)) = found else {
+            return text.trim().to_string();
+        };
+
+        self.uncertain |= *marker == WITH_MARKER.0;
+        let names = self.take_trailing(&text[at + 1 + marker.len()..], bare_with);
+        self.add(credit, &names);
+        text[..at].trim().to_string()
+    }
+
+    fn add(&mut self, credit: Credit, names: &str) {
+        let list = match credit {
+            Credit::Featured => &mut self.featured,
+            Credit::Producer => &mut self.producers,
+        };
+        let (names, ambiguous) = split_names(names);
+        self.uncertain |= ambiguous;
+        for name in names {
+            if !contains(list, &name) {
+                list.push(name);
+            }
+        }
+    }
+
+    /// Where the credits were found, for suggestion reasons
+    fn describe_sources(&self) -> String {
+        self.sources
+            .iter()
+            .map(|(field, value)| format!("{} \"{}\"", field, value))
+            .collect::<Vec<_>>()
+            .join(" and ")
+    }
+}
+
+/// The keyword and credit a bracket's contents open with, and the names after it
+fn credit_marker(text: &str) -> Option<(&'static str, Credit, &str)> {
+    let text = text.trim();
+    let lower = text.to_ascii_lowercase();
+    MARKERS
+        .iter()
+        .chain([&WITH_MARKER])
+        .find(|(marker, _)| lower.starts_with(marker))
+        .map(|(marker, credit)| (*marker, *credit, text[marker.len()..].trim()))
+}
+
+/// "A; B" / "A, B & C" / "A and B" -> ["A", "B", "C"], and whether the split was a guess
+///
+/// Only ";" and a comma list ending in "&" clearly separate names; a lone "&", "and"
+/// or comma may belong to one ("Simon & Garfunkel", "Tyler, the Creator").
+fn split_names(names: &str) -> (Vec<String>, bool) {
+    let mut split = Vec::new();
+    let mut ambiguous = false;
+    for group in names.split(';') {
+        let lower = group.to_ascii_lowercase();
+        let mut parts = Vec::new();
+        let mut start = 0;
+        for (at, separator) in lower.match_indices(" and ") {
+            parts.push(&group[start..at]);
+            start = at + separator.len();
+        }
+        parts.push(&group[start..]);
+
+        let names: Vec<String> = parts
+            .iter()
+            .flat_map(|part| part.split([',', '&']))
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let listed = parts.len() == 1
+            && group.matches('&').count() == 1
+            && matches!((group.rfind(','), group.find('&')), (Some(comma), Some(amp)) if comma < amp);
+        ambiguous |= names.len() > 1 && !listed;
+        split.extend(names);
+    }
+    (split, ambiguous)
+}
+
+/// "A", "A & B", "A, B & C"
+fn join_names(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => name.clone(),
+        [init @ .., last] => format!("{} & {}", init.join(", "), last),
+    }
+}
+
+fn contains(list: &[String], name: &str) -> bool {
+    list.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+fn merge(list: &mut Vec<String>, names: &[String]) {
+    for name in names {
+        if !contains(list, name) {
+            list.push(name.clone());
+        }
+    }
+}
+
+/// Suggestions that move the credits in `metadata` to where `style` puts them
+///
+/// `stores_credits` says whether the file can hold an involved-people list; when it
+/// can't, featured artists go to the artist field and producers stay in the title.
+pub fn suggest(
+    metadata: &TrackMetadata,
+    style: CreditStyle,
+    stores_credits: bool,
+) -> Vec<MetadataSuggestion> {
+    let Some(split) = CreditSplit::from_metadata(metadata) else {
+        return Vec::new();
+    };
+    let style = match style {
+        CreditStyle::People if !stores_credits => CreditStyle::Artist,
+        style => style,
+    };
+
+    let mut target = metadata.clone();
+    target.title = split.title.clone();
+    target.artists = split.artists.clone();
+    let mut title_credits = Vec::new();
+    let mut people = Vec::new();
+    match style {
+        CreditStyle::Title if split.title.is_some() && !split.featured.is_empty() => {
+            title_credits.push(format!("(feat. {})", join_names(&split.featured)));
+        }
+        CreditStyle::People => people.push((FEATURED_ROLE, &split.featured)),
+        _ => merge(&mut target.artists, &split.featured),
+    }
+    if style == CreditStyle::Title || !stores_credits {
+        if !split.producers.is_empty() {
+            title_credits.push(format!("(prod. by {})", join_names(&split.producers)));
+        }
+    } else {
+        people.push((PRODUCER_ROLE, &split.producers));
+    }
+
+    for (role, names) in people.into_iter().filter(|(_, names)| !names.is_empty()) {
+        merge(target.people.entry(role.to_string()).or_default(), names);
+    }
+    if let Some(ref mut title) = target.title {
+        for credit in title_credits {
+            title.push(' ');
+            title.push_str(&credit);
+        }
+    }
+
+    let reason = format!(
+        "Credit found in {}; {}",
+        split.describe_sources(),
+        style.rule()
+    );
+    let confidence = if split.uncertain { "Low" } else { "High" };
+    let fields = [
+        "title".to_string(),
+        "artist".to_string(),
+        format!("{}{}", PEOPLE_PREFIX, FEATURED_ROLE),
+        format!("{}{}", PEOPLE_PREFIX, PRODUCER_ROLE),
+    ];
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let suggested = target.field_value(&field)?;
+            let current = metadata.field_value(&field);
+            (current.as_ref() != Some(&suggested)).then(|| MetadataSuggestion {
+                field,
+                current_value: current,
+                suggested_value: suggested,
+                confidence: confidence.to_string(),
+                reason: reason.clone(),
+                action: SuggestionAction::Set,
+                status: SuggestionStatus::Pending,
+                edited_from: None,
+            })
+        })
+        .collect()
+}
+
+/// Add credit suggestions to a report, on top of the values it already suggests
+///
+/// A suggestion for the same field is rewritten rather than duplicated. Returns the
+/// number of fields changed.
+pub fn merge_into(
+    report: &mut SuggestionsReport,
+    style: CreditStyle,
+    stores_credits: bool,
+) -> usize {
+    let suggestions = suggest(&report.apply_suggestions(), style, stores_credits);
+    report.merge(suggestions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(artist: &str, title: &str) -> TrackMetadata {
+        let mut metadata = TrackMetadata::default();
+        metadata.set_field("artist", artist);
+        metadata.set_field("title", title);
+        metadata
+    }
+
+    fn suggested(suggestions: &[MetadataSuggestion], field: &str) -> Option<String> {
+        suggestions
+            .iter()
+            .find(|s| s.field == field)
+            .map(|s| s.suggested_value.clone())
+    }
+
+    #[test]
+    fn test_split_credits() {
+        let metadata = track(
+            "Westside Gunn ft. Benny the Butcher",
+            "World Domination (Prod By MF DOOM) [feat. Conway, Boldy James & Elcamino]",
+        );
+        let split = CreditSplit::from_metadata(&metadata).unwrap();
+        assert_eq!(split.title.as_deref(), Some("World Domination"));
+        assert_eq!(split.artists, vec!["Westside Gunn"]);
+        assert_eq!(
+            split.featured,
+            vec!["Conway", "Boldy James", "Elcamino", "Benny the Butcher"]
+        );
+        assert_eq!(split.producers, vec!["MF DOOM"]);
+        assert!(!split.uncertain);
+
+        assert!(CreditSplit::from_metadata(&track("Billy Idol", "Dancing with Myself")).is_none());
+        let with = CreditSplit::from_metadata(&track("Zedd with Foxes", "Clarity")).unwrap();
+        assert_eq!(with.featured, vec!["Foxes"]);
+        assert!(with.uncertain);
+        let bracketed = CreditSplit::from_metadata(&track("Zedd", "Clarity (with Foxes)")).unwrap();
+        assert!(bracketed.uncertain);
+    }
+
+    #[test]
+    fn test_ambiguous_name_splits() {
+        let split = |title: &str| CreditSplit::from_metadata(&track("A", title)).unwrap();
+        assert_eq!(split("X (feat. B; C)").featured, vec!["B", "C"]);
+        assert!(!split("X (feat. B; C)").uncertain);
+        assert!(!split("X (feat. B, C & D)").uncertain);
+        assert!(!split("X (feat. Foxes)").uncertain);
+        assert!(split("X (feat. Simon & Garfunkel)").uncertain);
+        assert!(split("X (feat. Tyler, the Creator)").uncertain);
+        assert!(split("X (feat. B and C)").uncertain);
+
+        let suggestions = suggest(&track("A feat. B & C", "X"), CreditStyle::Artist, true);
+        assert!(suggestions.iter().all(|s| s.confidence == "Low"));
+    }
+
+    #[test]
+    fn test_suggest_by_style() {
+        let metadata = track("MF DOOM feat. Ghostface", "Angels (Prod By Madlib)");
+
+        let title = suggest(&metadata, CreditStyle::Title, true);
+        assert_eq!(
+            suggested(&title, "title").as_deref(),
+            Some("Angels (feat. Ghostface) (prod. by Madlib)")
+        );
+        assert_eq!(suggested(&title, "artist").as_deref(), Some("MF DOOM"));
+
+        let artist = suggest(&metadata, CreditStyle::Artist, true);
+        assert_eq!(suggested(&artist, "title").as_deref(), Some("Angels"));
+        assert_eq!(
+            suggested(&artist, "artist").as_deref(),
+            Some("MF DOOM; Ghostface")
+        );
+        assert_eq!(
+            suggested(&artist, "people:producer").as_deref(),
+            Some("Madlib")
+        );
+
+        let people = suggest(&metadata, CreditStyle::People, true);
+        assert_eq!(suggested(&people, "artist").as_deref(), Some("MF DOOM"));
+        assert_eq!(
+            suggested(&people, "people:featuring").as_deref(),
+            Some("Ghostface")
+        );
+
+        // Without an involved-people list producers stay in the title
+        let flac = suggest(&metadata, CreditStyle::People, false);
+        assert_eq!(
+            suggested(&flac, "title").as_deref(),
+            Some("Angels (prod. by Madlib)")
+        );
+        assert_eq!(
+            suggested(&flac, "artist").as_deref(),
+            Some("MF DOOM; Ghostface")
+        );
+
+        // Already in the canonical form
+        let canonical = track("MF DOOM", "Angels (feat. Ghostface)");
+        assert!(suggest(&canonical, CreditStyle::Title, true).is_empty());
+    }
+}
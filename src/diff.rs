@@ -0,0 +1,196 @@
+use crate::error::Result;
+use crate::metadata::formats::AudioFormat;
+use crate::metadata::{
+    reader, TrackMetadata, CUSTOM_PREFIX, FIELDS, PEOPLE_PREFIX, VALUE_SEPARATOR,
+};
+use std::fmt;
+use std::path::Path;
+
+/// Every tag value of a file by label ("title", "txxx:KEY", "picture:Front cover",
+/// "frame:PRIV owner" ...), in display order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TagSnapshot {
+    entries: Vec<(String, String)>,
+}
+
+/// A label whose value differs between two snapshots; None where it is absent
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl TagSnapshot {
+    /// Read the tags of a file, including the frames no field covers
+    pub fn read(file_path: &str) -> Result<Self> {
+        let metadata = reader::read_metadata(file_path)?;
+        let path = Path::new(file_path);
+        let other_frames = AudioFormat::detect(path)?.backend().other_frames(path)?;
+        Ok(Self::new(&metadata, other_frames))
+    }
+
+    /// Snapshot of `metadata` plus the (label, value) pairs of unmapped frames
+    pub fn new(metadata: &TrackMetadata, other_frames: Vec<(String, String)>) -> Self {
+        let mut snapshot = Self::default();
+        if !metadata.tag_types.is_empty() {
+            snapshot.push("tags".to_string(), metadata.tag_types.join(", "));
+        }
+        for field in FIELDS {
+            if let Some(value) = metadata.field_value(field) {
+                snapshot.push(field.to_string(), value);
+            }
+        }
+        for (key, value) in &metadata.custom {
+            snapshot.push(format!("{}{}", CUSTOM_PREFIX, key), value.clone());
+        }
+        for (role, names) in &metadata.people {
+            snapshot.push(
+                format!("{}{}", PEOPLE_PREFIX, role),
+                names.join(VALUE_SEPARATOR),
+            );
+        }
+        // The hash tells apart replaced art of the same size
+        for picture in &metadata.pictures {
+            snapshot.push(
+                format!("picture:{}", picture.picture_type),
+                format!("{}, SHA-256 {}", picture, picture.hash),
+            );
+        }
+        for lyrics in &metadata.lyrics {
+            snapshot.push(format!("lyrics:{}", lyrics.source), lyrics.to_string());
+        }
+        for (label, value) in other_frames {
+            snapshot.push(format!("frame:{}", label), value);
+        }
+        snapshot
+    }
+
+    /// Add an entry, numbering repeated labels ("picture:Other #2")
+    fn push(&mut self, label: String, value: String) {
+        let mut unique = label.clone();
+        let mut n = 1;
+        while self.value(&unique).is_some() {
+            n += 1;
+            unique = format!("{} #{}", label, n);
+        }
+        self.entries.push((unique, value));
+    }
+
+    fn value(&self, label: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Entries that differ in `after`: this snapshot's labels first, then new ones
+    pub fn diff(&self, after: &Self) -> Vec<FieldDiff> {
+        let labels = self.entries.iter().chain(
+            after
+                .entries
+                .iter()
+                .filter(|(label, _)| self.value(label).is_none()),
+        );
+        labels
+            .filter_map(|(label, _)| {
+                let before = self.value(label);
+                let now = after.value(label);
+                (before != now).then(|| FieldDiff {
+                    field: label.clone(),
+                    before: before.map(|v| v.to_string()),
+                    after: now.map(|v| v.to_string()),
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<String>| match value {
+            Some(value) => one_line(value),
+            None => "(missing)".to_string(),
+        };
+        let marker = match (&self.before, &self.after) {
+            (None, _) => '+',
+            (_, None) => '-',
+            _ => '~',
+        };
+        write!(
+            f,
+            "{} {}: {} → {}",
+            marker,
+            self.field,
+            show(&self.before),
+            show(&self.after)
+        )
+    }
+}
+
+/// Collapse line breaks and runs of whitespace so a value fits on one line, showing
+/// ID3v2.4 NUL separators as "; "
+fn one_line(value: &str) -> String {
+    value
+        .split('\0')
+        .map(|part| part.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join(VALUE_SEPARATOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::artwork::PictureInfo;
+
+    #[test]
+    fn test_diff_snapshots() {
+        let mut metadata = TrackMetadata::default();
+        metadata.set_field("title", "Pennyroyal");
+        metadata.set_field("year", "1999");
+        metadata.set_field("txxx:REPLAYGAIN_TRACK_GAIN", "-6.2 dB");
+        metadata
+            .pictures
+            .push(PictureInfo::new("Front cover", "image/jpeg", "", b"old"));
+        let before = TagSnapshot::new(
+            &metadata,
+            vec![("PRIV WM/Provider".to_string(), "3 bytes".to_string())],
+        );
+
+        metadata.set_field("year", "2012");
+        metadata.comment = Some("Mixtape\nMonkey".to_string());
+        metadata.set_field("people:producer", "Lee Bannon");
+        metadata.pictures[0] = PictureInfo::new("Front cover", "image/jpeg", "", b"new");
+        let after = TagSnapshot::new(&metadata, Vec::new());
+
+        let changed: Vec<String> = before.diff(&after).iter().map(|d| d.to_string()).collect();
+        assert_eq!(changed.len(), 5);
+        assert_eq!(changed[0], "~ year: 1999 → 2012");
+        assert!(changed[1].starts_with("~ picture:Front cover: Front cover, image/jpeg"));
+        assert_eq!(changed[2], "- frame:PRIV WM/Provider: 3 bytes → (missing)");
+        assert_eq!(changed[3], "+ comment: (missing) → Mixtape Monkey");
+        assert_eq!(changed[4], "+ people:producer: (missing) → Lee Bannon");
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn test_repeated_labels_are_numbered() {
+        let frames = vec![
+            ("COMM iTunNORM".to_string(), "a".to_string()),
+            ("COMM iTunNORM".to_string(), "b".to_string()),
+        ];
+        let before = TagSnapshot::new(&TrackMetadata::default(), frames);
+        let after = TagSnapshot::new(
+            &TrackMetadata::default(),
+            vec![("COMM iTunNORM".to_string(), "a".to_string())],
+        );
+        assert_eq!(
+            before.diff(&after),
+            vec![FieldDiff {
+                field: "frame:COMM iTunNORM #2".to_string(),
+                before: Some("b".to_string()),
+                after: None,
+            }]
+        );
+    }
+}
@@ -8,6 +8,9 @@ pub enum AgentError {
     #[error("Failed to parse metadata: {0}")]
     MetadataParse(String),
 
+    #[error("Audio data changed: {0}")]
+    AudioChanged(String),
+
     #[error("LLM request failed: {0}")]
     LlmRequest(String),
 
@@ -0,0 +1,473 @@
+use crate::agent::MusicAgent;
+use crate::error::{AgentError, Result};
+use crate::metadata::{reader, split_values, VALUE_SEPARATOR};
+use crate::prompts::TemplateInfo;
+use crate::suggestions::MetadataSuggestion;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A track with known-correct metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenTrack {
+    /// Path to the audio file, relative to the golden file's directory
+    pub file: String,
+    /// Correct value for each field, keyed by suggestion field name
+    pub expected: BTreeMap<String, String>,
+}
+
+/// Golden dataset loaded from JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenDataset {
+    pub tracks: Vec<GoldenTrack>,
+}
+
+impl GoldenDataset {
+    pub fn load_from_file(file_path: &str) -> Result<Self> {
+        let json = fs::read_to_string(file_path)
+            .map_err(|e| AgentError::FileRead(format!("Failed to read golden file: {}", e)))?;
+
+        serde_json::from_str(&json)
+            .map_err(|e| AgentError::MetadataParse(format!("Failed to parse golden file: {}", e)))
+    }
+}
+
+/// Confusion counts for a single field
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FieldScore {
+    /// Suggestions that matched the golden value
+    pub true_positives: u32,
+    /// Suggestions that did not match the golden value
+    pub false_positives: u32,
+    /// Fields that needed a fix but got no correct suggestion
+    pub false_negatives: u32,
+    /// Wrong suggestions on a field that was already correct
+    pub hallucinated: u32,
+}
+
+impl FieldScore {
+    pub fn precision(&self) -> Option<f64> {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+        )
+    }
+
+    pub fn recall(&self) -> Option<f64> {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+        )
+    }
+
+    fn add(&mut self, other: &FieldScore) {
+        self.true_positives += other.true_positives;
+        self.false_positives += other.false_positives;
+        self.false_negatives += other.false_negatives;
+        self.hallucinated += other.hallucinated;
+    }
+}
+
+fn ratio(numerator: u32, denominator: u32) -> Option<f64> {
+    if denominator == 0 {
+        None
+    } else {
+        Some(numerator as f64 / denominator as f64)
+    }
+}
+
+/// Per-track outcome of an evaluation run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackResult {
+    pub file: String,
+    pub suggestions: Vec<MetadataSuggestion>,
+    /// Fields that were already correct but the agent tried to change
+    pub hallucinated_fields: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Full result of running the agent over a golden dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalRun {
+    pub timestamp: String,
+    pub provider: String,
+    pub model: String,
+    pub prompt_template: TemplateInfo,
+    pub fields: BTreeMap<String, FieldScore>,
+    pub tracks: Vec<TrackResult>,
+}
+
+impl EvalRun {
+    /// Run the agent over every golden track and score its suggestions
+    pub async fn run(agent: &MusicAgent, golden_file: &str) -> Result<Self> {
+        let dataset = GoldenDataset::load_from_file(golden_file)?;
+        let base_dir = Path::new(golden_file).parent().unwrap_or(Path::new("."));
+
+        let mut fields: BTreeMap<String, FieldScore> = BTreeMap::new();
+        let mut tracks = Vec::new();
+
+        for golden in &dataset.tracks {
+            let path = base_dir.join(&golden.file);
+            println!("\n📖 {}", path.display());
+
+            let outcome = match reader::read_metadata(&path.to_string_lossy()) {
+                Ok(metadata) => agent
+                    .analyze_with_suggestions(&metadata)
+                    .await
+                    .map(|report| (metadata, report.suggestions)),
+                Err(e) => Err(e),
+            };
+
+            let (metadata, suggestions) = match outcome {
+                Ok(result) => result,
+                Err(e) => {
+                    println!("   ❌ {}", e);
+                    tracks.push(TrackResult {
+                        file: golden.file.clone(),
+                        suggestions: Vec::new(),
+                        hallucinated_fields: Vec::new(),
+                        error: Some(e.to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            let (scores, hallucinated_fields) =
+                score_track(&golden.expected, &suggestions, |field| {
+                    metadata.field_value(field)
+                });
+            for (field, score) in scores {
+                fields.entry(field).or_default().add(&score);
+            }
+
+            tracks.push(TrackResult {
+                file: golden.file.clone(),
+                suggestions,
+                hallucinated_fields,
+                error: None,
+            });
+        }
+
+        Ok(Self {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            provider: agent.provider_name().to_string(),
+            model: agent.model_name().to_string(),
+            prompt_template: agent.suggestions_template(),
+            fields,
+            tracks,
+        })
+    }
+
+    /// Scores summed across all fields
+    pub fn overall(&self) -> FieldScore {
+        let mut total = FieldScore::default();
+        for score in self.fields.values() {
+            total.add(score);
+        }
+        total
+    }
+
+    /// Short label identifying the run in comparisons
+    pub fn label(&self) -> String {
+        format!(
+            "{} / {} ({})",
+            self.model, self.prompt_template.name, self.prompt_template.hash
+        )
+    }
+
+    pub fn save_to_file(&self, file_path: &str) -> Result<()> {
+        save_json(self, file_path)
+    }
+
+    pub fn load_from_file(file_path: &str) -> Result<Self> {
+        let json = fs::read_to_string(file_path)
+            .map_err(|e| AgentError::FileRead(format!("Failed to read eval run: {}", e)))?;
+
+        serde_json::from_str(&json)
+            .map_err(|e| AgentError::MetadataParse(format!("Failed to parse eval run: {}", e)))
+    }
+
+    /// Display per-field scores
+    pub fn display(&self) {
+        println!("\n{}", "=".repeat(62));
+        println!("🧪 EVALUATION: {}", self.label());
+        println!("{}", "=".repeat(62));
+
+        println!(
+            "\n{:<16} {:>9} {:>9} {:>5} {:>5} {:>5} {:>6}",
+            "FIELD", "PRECISION", "RECALL", "TP", "FP", "FN", "HALLU"
+        );
+        for (field, score) in &self.fields {
+            print_score_row(field, score);
+        }
+        println!("{}", "-".repeat(62));
+        print_score_row("overall", &self.overall());
+
+        let failed = self.tracks.iter().filter(|t| t.error.is_some()).count();
+        if failed > 0 {
+            println!("\n⚠️  {} track(s) failed to evaluate", failed);
+        }
+
+        for track in &self.tracks {
+            if !track.hallucinated_fields.is_empty() {
+                println!(
+                    "🚩 {}: changed correct field(s) {}",
+                    track.file,
+                    track.hallucinated_fields.join(", ")
+                );
+            }
+        }
+    }
+}
+
+fn print_score_row(field: &str, score: &FieldScore) {
+    println!(
+        "{:<16} {:>9} {:>9} {:>5} {:>5} {:>5} {:>6}",
+        field,
+        format_ratio(score.precision()),
+        format_ratio(score.recall()),
+        score.true_positives,
+        score.false_positives,
+        score.false_negatives,
+        score.hallucinated
+    );
+}
+
+fn format_ratio(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("{:.1}%", v * 100.0))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Score one track's suggestions against its golden values
+///
+/// `expected` is taken to cover every field the agent should touch, so a suggestion
+/// for any other field is a false positive. Returns the per-field scores and the fields
+/// the agent wrongly tried to change although they were already correct.
+fn score_track<F>(
+    expected: &BTreeMap<String, String>,
+    suggestions: &[MetadataSuggestion],
+    current_value: F,
+) -> (BTreeMap<String, FieldScore>, Vec<String>)
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut scores = BTreeMap::new();
+    let mut hallucinated = Vec::new();
+
+    for (field, golden_value) in expected {
+        let golden_value = normalize_value(golden_value);
+        let already_correct = current_value(field)
+            .map(|v| normalize_value(&v) == golden_value)
+            .unwrap_or(false);
+
+        let mut score = FieldScore::default();
+        let mut fixed = false;
+
+        for suggestion in suggestions
+            .iter()
+            .filter(|s| normalize_field(&s.field) == *field)
+        {
+            if normalize_value(&suggestion.suggested_value) == golden_value {
+                // Re-suggesting an already-correct value is harmless, not a fix
+                if !already_correct {
+                    score.true_positives += 1;
+                    fixed = true;
+                }
+            } else {
+                score.false_positives += 1;
+                if already_correct {
+                    score.hallucinated += 1;
+                    hallucinated.push(field.clone());
+                }
+            }
+        }
+
+        if !already_correct && !fixed {
+            score.false_negatives += 1;
+        }
+
+        scores.insert(field.clone(), score);
+    }
+
+    for suggestion in suggestions {
+        let field = normalize_field(&suggestion.field);
+        if !expected.contains_key(&field) {
+            scores.entry(field).or_default().false_positives += 1;
+        }
+    }
+
+    (scores, hallucinated)
+}
+
+/// Map LLM field spellings like "Album Artist" onto `album_artist`
+fn normalize_field(field: &str) -> String {
+    field.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+/// Lower-case, with list separators evened out ("A;B" matches "a; b")
+fn normalize_value(value: &str) -> String {
+    split_values(value).join(VALUE_SEPARATOR).to_lowercase()
+}
+
+/// Change in one field's scores between two runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldComparison {
+    pub field: String,
+    pub precision_a: Option<f64>,
+    pub precision_b: Option<f64>,
+    pub recall_a: Option<f64>,
+    pub recall_b: Option<f64>,
+    pub hallucinated_a: u32,
+    pub hallucinated_b: u32,
+}
+
+/// Side-by-side comparison of two evaluation runs (e.g. model A vs model B)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalComparison {
+    pub run_a: String,
+    pub run_b: String,
+    pub fields: Vec<FieldComparison>,
+}
+
+impl EvalComparison {
+    pub fn new(a: &EvalRun, b: &EvalRun) -> Self {
+        let mut names: Vec<&String> = a.fields.keys().chain(b.fields.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        let empty = FieldScore::default();
+        let mut fields: Vec<FieldComparison> = names
+            .into_iter()
+            .map(|field| {
+                let score_a = a.fields.get(field).unwrap_or(&empty);
+                let score_b = b.fields.get(field).unwrap_or(&empty);
+                compare_scores(field, score_a, score_b)
+            })
+            .collect();
+        fields.push(compare_scores("overall", &a.overall(), &b.overall()));
+
+        Self {
+            run_a: a.label(),
+            run_b: b.label(),
+            fields,
+        }
+    }
+
+    pub fn save_to_file(&self, file_path: &str) -> Result<()> {
+        save_json(self, file_path)
+    }
+
+    pub fn display(&self) {
+        println!("\n{}", "=".repeat(62));
+        println!("⚖️  COMPARISON");
+        println!("{}", "=".repeat(62));
+        println!("A: {}", self.run_a);
+        println!("B: {}", self.run_b);
+
+        println!(
+            "\n{:<16} {:>14} {:>14} {:>12}",
+            "FIELD", "PRECISION A→B", "RECALL A→B", "HALLU A→B"
+        );
+        for field in &self.fields {
+            println!(
+                "{:<16} {:>14} {:>14} {:>12}",
+                field.field,
+                format!(
+                    "{}→{}",
+                    format_ratio(field.precision_a),
+                    format_ratio(field.precision_b)
+                ),
+                format!(
+                    "{}→{}",
+                    format_ratio(field.recall_a),
+                    format_ratio(field.recall_b)
+                ),
+                format!("{}→{}", field.hallucinated_a, field.hallucinated_b)
+            );
+        }
+    }
+}
+
+fn compare_scores(field: &str, a: &FieldScore, b: &FieldScore) -> FieldComparison {
+    FieldComparison {
+        field: field.to_string(),
+        precision_a: a.precision(),
+        precision_b: b.precision(),
+        recall_a: a.recall(),
+        recall_b: b.recall(),
+        hallucinated_a: a.hallucinated,
+        hallucinated_b: b.hallucinated,
+    }
+}
+
+fn save_json<T: Serialize>(value: &T, file_path: &str) -> Result<()> {
+    if let Some(parent) = Path::new(file_path).parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            AgentError::FileRead(format!("Failed to create output directory: {}", e))
+        })?;
+    }
+
+    let json = serde_json::to_string_pretty(value).map_err(|e| {
+        AgentError::MetadataParse(format!("Failed to serialize eval report: {}", e))
+    })?;
+
+    fs::write(file_path, json)
+        .map_err(|e| AgentError::FileRead(format!("Failed to write eval report: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suggestions::{SuggestionAction, SuggestionStatus};
+
+    fn suggestion(field: &str, value: &str) -> MetadataSuggestion {
+        MetadataSuggestion {
+            field: field.to_string(),
+            current_value: None,
+            suggested_value: value.to_string(),
+            confidence: "High".to_string(),
+            reason: String::new(),
+            action: SuggestionAction::Set,
+            status: SuggestionStatus::Pending,
+            edited_from: None,
+        }
+    }
+
+    #[test]
+    fn test_score_track() {
+        let expected = BTreeMap::from([
+            ("year".to_string(), "1970".to_string()),
+            ("genre".to_string(), "Folk Rock".to_string()),
+            ("album_artist".to_string(), "Grateful Dead".to_string()),
+            (
+                "artist".to_string(),
+                "Jerry Garcia; David Grisman".to_string(),
+            ),
+        ]);
+        let suggestions = vec![
+            suggestion("year", "1970"),
+            suggestion("Album Artist", "Jerry Garcia"),
+            suggestion("artist", "jerry garcia;David Grisman"),
+            suggestion("Composer", "Robert Hunter"),
+        ];
+        let current = |field: &str| match field {
+            "album_artist" => Some("Grateful Dead".to_string()),
+            _ => None,
+        };
+
+        let (scores, hallucinated) = score_track(&expected, &suggestions, current);
+
+        assert_eq!(scores["year"].true_positives, 1);
+        assert_eq!(scores["artist"].true_positives, 1);
+        assert_eq!(scores["genre"].false_negatives, 1);
+        assert_eq!(scores["album_artist"].hallucinated, 1);
+        assert_eq!(hallucinated, vec!["album_artist".to_string()]);
+        assert_eq!(scores["year"].precision(), Some(1.0));
+        assert_eq!(scores["genre"].recall(), Some(0.0));
+        // Not in the golden values, so not a wanted change
+        assert_eq!(scores["composer"].false_positives, 1);
+        assert_eq!(scores["composer"].precision(), Some(0.0));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::{split_values, TrackMetadata};
+use crate::suggestions::MetadataSuggestion;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Default location of the reviewer decision log
+pub const DEFAULT_STORE: &str = "public/feedback.jsonl";
+
+/// A reviewer's verdict on one suggestion, kept as a few-shot example
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackDecision {
+    pub timestamp: String,
+    pub file_path: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub field: String,
+    pub current_value: Option<String>,
+    pub suggested_value: String,
+    /// Value the reviewer kept when it differs from the suggestion
+    pub final_value: Option<String>,
+    pub accepted: bool,
+}
+
+impl FeedbackDecision {
+    pub fn new(metadata: &TrackMetadata, suggestion: &MetadataSuggestion, accepted: bool) -> Self {
+        // An edited suggestion keeps the LLM's value as the suggestion and the edit as final
+        let (suggested_value, final_value) = match suggestion.edited_from {
+            Some(ref original) => (original.clone(), Some(suggestion.suggested_value.clone())),
+            None => (suggestion.display_value(), None),
+        };
+
+        Self {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            file_path: metadata.file_path.clone(),
+            artist: metadata.artist(),
+            album: metadata.album.clone(),
+            genre: metadata.genre(),
+            field: suggestion.field.clone(),
+            current_value: suggestion.current_value.clone(),
+            suggested_value,
+            final_value,
+            accepted,
+        }
+    }
+
+    /// One-line rendering used inside the prompt
+    pub fn to_prompt_line(&self) -> String {
+        let current = self
+            .current_value
+            .as_deref()
+            .map(|v| format!("\"{}\"", v))
+            .unwrap_or_else(|| "None".to_string());
+
+        match (self.accepted, &self.final_value) {
+            (true, Some(final_value)) => format!(
+                "ACCEPTED WITH EDIT: {} {} → \"{}\" (suggested \"{}\")",
+                self.field, current, final_value, self.suggested_value
+            ),
+            (true, None) => format!(
+                "ACCEPTED: {} {} → \"{}\"",
+                self.field, current, self.suggested_value
+            ),
+            (false, _) => format!(
+                "REJECTED: {} {} → \"{}\"",
+                self.field, current, self.suggested_value
+            ),
+        }
+    }
+
+    /// How useful this decision is as an example for the given track
+    fn relevance(&self, metadata: &TrackMetadata) -> u32 {
+        let mut score = 0;
+        if shares_value(&self.artist, &metadata.artists) {
+            score += 3;
+        }
+        if same_text(&self.album, &metadata.album) {
+            score += 2;
+        }
+        if shares_value(&self.genre, &metadata.genres) {
+            score += 1;
+        }
+        if metadata.missing_fields().contains(&self.field.as_str()) {
+            score += 2;
+        }
+        score
+    }
+}
+
+fn same_text(a: &Option<String>, b: &Option<String>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.trim().eq_ignore_ascii_case(b.trim()),
+        _ => false,
+    }
+}
+
+/// True if a logged "; "-separated list and the track's values have one in common
+fn shares_value(logged: &Option<String>, values: &[String]) -> bool {
+    logged.as_deref().map(split_values).is_some_and(|logged| {
+        logged
+            .iter()
+            .any(|a| values.iter().any(|b| a.eq_ignore_ascii_case(b)))
+    })
+}
+
+/// Append-only log of reviewer decisions stored as JSON lines
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackStore {
+    path: String,
+    decisions: Vec<FeedbackDecision>,
+}
+
+impl FeedbackStore {
+    /// Load the store, treating a missing file as empty
+    pub fn load(path: &str) -> Result<Self> {
+        let mut store = Self {
+            path: path.to_string(),
+            decisions: Vec::new(),
+        };
+
+        if !Path::new(path).exists() {
+            return Ok(store);
+        }
+
+        let contents = fs::read_to_string(path)
+            .map_err(|e| AgentError::FileRead(format!("Failed to read feedback store: {}", e)))?;
+
+        for (i, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let decision = serde_json::from_str(line).map_err(|e| {
+                AgentError::MetadataParse(format!(
+                    "Invalid feedback entry on line {} of {}: {}",
+                    i + 1,
+                    path,
+                    e
+                ))
+            })?;
+            store.decisions.push(decision);
+        }
+
+        Ok(store)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn decisions(&self) -> &[FeedbackDecision] {
+        &self.decisions
+    }
+
+    /// Append decisions to the log on disk
+    pub fn record(&mut self, decisions: Vec<FeedbackDecision>) -> Result<()> {
+        if decisions.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(parent) = Path::new(&self.path).parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                AgentError::FileRead(format!("Failed to create feedback directory: {}", e))
+            })?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| AgentError::FileRead(format!("Failed to open feedback store: {}", e)))?;
+
+        for decision in &decisions {
+            let line = serde_json::to_string(decision)?;
+            writeln!(file, "{}", line)?;
+        }
+
+        self.decisions.extend(decisions);
+        Ok(())
+    }
+
+    /// Pick the most relevant decisions for a track, newest first among equals; decisions
+    /// with nothing in common with the track are never picked
+    pub fn select_examples(&self, metadata: &TrackMetadata, limit: usize) -> Vec<FeedbackDecision> {
+        let mut ranked: Vec<(u32, usize, &FeedbackDecision)> = self
+            .decisions
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (d.relevance(metadata), i, d))
+            .filter(|(relevance, _, _)| *relevance > 0)
+            .collect();
+
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, d)| d.clone())
+            .collect()
+    }
+}
+
+/// Format selected decisions as the `{{examples}}` prompt block
+pub fn format_examples(examples: &[FeedbackDecision]) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+
+    let mut block = String::from(
+        "Previous reviewer decisions on this library. Follow the same house style and avoid suggestions like the rejected ones:\n\n",
+    );
+    for example in examples {
+        block.push_str(&example.to_prompt_line());
+        block.push('\n');
+    }
+    block.push('\n');
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suggestions::{SuggestionAction, SuggestionStatus};
+
+    fn metadata(artist: &str, genre: Option<&str>) -> TrackMetadata {
+        TrackMetadata {
+            file_path: "song.mp3".to_string(),
+            artists: vec![artist.to_string()],
+            title: Some("Song".to_string()),
+            album: None,
+            year: None,
+            genres: genre.map(|g| g.to_string()).into_iter().collect(),
+            track_number: None,
+            album_artist: None,
+            duration_seconds: None,
+            ..Default::default()
+        }
+    }
+
+    fn suggestion(field: &str, value: &str) -> MetadataSuggestion {
+        MetadataSuggestion {
+            field: field.to_string(),
+            current_value: None,
+            suggested_value: value.to_string(),
+            confidence: "High".to_string(),
+            reason: String::new(),
+            action: SuggestionAction::Set,
+            status: SuggestionStatus::Pending,
+            edited_from: None,
+        }
+    }
+
+    #[test]
+    fn test_select_examples_prefers_same_artist() {
+        let store = FeedbackStore {
+            path: String::new(),
+            decisions: vec![
+                FeedbackDecision::new(
+                    &metadata("MF DOOM", None),
+                    &suggestion("genre", "Hip-Hop"),
+                    true,
+                ),
+                FeedbackDecision::new(
+                    &metadata("Grateful Dead", None),
+                    &suggestion("genre", "Folk Rock"),
+                    true,
+                ),
+                FeedbackDecision::new(
+                    &metadata("Other", Some("Jazz")),
+                    &suggestion("year", "1999"),
+                    false,
+                ),
+            ],
+        };
+
+        let examples = store.select_examples(&metadata("MF DOOM", Some("Rap")), 2);
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0].suggested_value, "Hip-Hop");
+    }
+
+    #[test]
+    fn test_select_examples_skips_unrelated() {
+        let store = FeedbackStore {
+            path: String::new(),
+            decisions: vec![
+                FeedbackDecision::new(
+                    &metadata("Grateful Dead", None),
+                    &suggestion("genre", "Folk Rock"),
+                    true,
+                ),
+                FeedbackDecision::new(
+                    &metadata("MF DOOM", None),
+                    &suggestion("genre", "Hip-Hop"),
+                    true,
+                ),
+            ],
+        };
+
+        let examples = store.select_examples(&metadata("MF DOOM", Some("Rap")), 5);
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].suggested_value, "Hip-Hop");
+    }
+}
@@ -0,0 +1,308 @@
+use crate::credits::{FEATURED_ROLE, PRODUCER_ROLE};
+use crate::error::{AgentError, Result};
+use crate::metadata::{is_known_field, TrackMetadata, PEOPLE_PREFIX};
+use crate::rename::strip_extension;
+use std::path::Path;
+
+/// Fields that only take whole numbers
+const NUMERIC_FIELDS: &[&str] = &[
+    "year",
+    "track_number",
+    "total_tracks",
+    "disc_number",
+    "total_discs",
+    "bpm",
+];
+
+/// Placeholder for a part of the path that is matched but not imported
+const IGNORED: &str = "_";
+
+/// A pattern such as `{artist}/{album}/{track} - {title} (Prod By {producer})` that
+/// the end of a file path is matched against
+#[derive(Debug, Clone)]
+pub struct PathPattern {
+    parts: Vec<Part>,
+    /// Number of path components the pattern covers
+    depth: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    /// Literal text, compared case-insensitively
+    Text(Vec<char>),
+    /// A field name (or `IGNORED`)
+    Field(String),
+}
+
+/// A field value found in a path, compared with what the tags already hold
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldImport {
+    pub field: String,
+    pub current: Option<String>,
+    pub value: String,
+    /// False when the tag already has a (different) value that is kept
+    pub apply: bool,
+}
+
+impl PathPattern {
+    /// Parse a pattern; placeholders are tag field names, the short names used by
+    /// `rename` ({track}, {disc}), {producer}, {featuring} or {_} to skip text
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let invalid = |why: String| {
+            AgentError::MetadataParse(format!("Invalid import pattern \"{}\": {}", pattern, why))
+        };
+
+        let pattern = strip_extension(pattern);
+        let mut parts = Vec::new();
+        let mut rest = pattern;
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                parts.push(Part::Text(rest[..open].chars().collect()));
+            }
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| invalid("unclosed \"{\"".to_string()))?
+                + open;
+            let field = field_name(&rest[open + 1..close])
+                .ok_or_else(|| invalid(format!("unknown field {}", &rest[open..=close])))?;
+            // Only a number has a clear end without text after it
+            if let Some(Part::Field(previous)) = parts.last() {
+                if !NUMERIC_FIELDS.contains(&previous.as_str()) {
+                    return Err(invalid(format!(
+                        "{} directly follows {{{}}}, so there's no telling them apart",
+                        &rest[open..=close],
+                        previous
+                    )));
+                }
+            }
+            parts.push(Part::Field(field));
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.chars().collect()));
+        }
+
+        if !parts
+            .iter()
+            .any(|p| matches!(p, Part::Field(f) if f != IGNORED))
+        {
+            return Err(invalid("no fields to import".to_string()));
+        }
+        Ok(Self {
+            parts,
+            depth: pattern.matches('/').count() + 1,
+        })
+    }
+
+    /// Field values from the last components of `path` (without its extension), or
+    /// None if the path doesn't fit the pattern
+    pub fn match_path(&self, path: &Path) -> Option<Vec<(String, String)>> {
+        let mut components: Vec<String> = path
+            .with_extension("")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        if components.len() < self.depth {
+            return None;
+        }
+        let text: Vec<char> = components
+            .split_off(components.len() - self.depth)
+            .join("/")
+            .chars()
+            .collect();
+
+        let mut values = Vec::new();
+        match_parts(&self.parts, &text, &mut values).then(|| {
+            values
+                .into_iter()
+                .filter(|(field, _)| field != IGNORED)
+                .collect()
+        })
+    }
+
+    /// What importing `path` would change in `metadata`
+    ///
+    /// Only empty fields are filled unless `overwrite`; values the tags already hold
+    /// are left out.
+    pub fn imports(
+        &self,
+        path: &Path,
+        metadata: &TrackMetadata,
+        overwrite: bool,
+    ) -> Option<Vec<FieldImport>> {
+        let imports = self
+            .match_path(path)?
+            .into_iter()
+            .filter_map(|(field, value)| {
+                let current = metadata.field_value(&field);
+                let mut updated = metadata.clone();
+                updated.set_field(&field, &value);
+                if updated.field_value(&field) == current {
+                    return None;
+                }
+                Some(FieldImport {
+                    apply: overwrite || current.is_none(),
+                    field,
+                    current,
+                    value,
+                })
+            })
+            .collect();
+        Some(imports)
+    }
+}
+
+/// The field a placeholder name stands for
+fn field_name(name: &str) -> Option<String> {
+    let field = match name.trim() {
+        "track" => "track_number".to_string(),
+        "disc" => "disc_number".to_string(),
+        "producer" => format!("{}{}", PEOPLE_PREFIX, PRODUCER_ROLE),
+        "featuring" => format!("{}{}", PEOPLE_PREFIX, FEATURED_ROLE),
+        name if name == IGNORED || is_known_field(name) => name.to_string(),
+        _ => return None,
+    };
+    Some(field)
+}
+
+/// Match `parts` against all of `text`, collecting captured values
+///
+/// Text fields take as little as they can, numbers as many digits as they can, and
+/// anything that leaves the rest unmatched is retried with the next candidate.
+fn match_parts(parts: &[Part], text: &[char], values: &mut Vec<(String, String)>) -> bool {
+    let Some((part, rest)) = parts.split_first() else {
+        return text.is_empty();
+    };
+
+    match part {
+        Part::Text(literal) => {
+            text.len() >= literal.len()
+                && text
+                    .iter()
+                    .zip(literal)
+                    .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+                && match_parts(rest, &text[literal.len()..], values)
+        }
+        Part::Field(field) => {
+            let numeric = NUMERIC_FIELDS.contains(&field.as_str());
+            let ends: Vec<usize> = if numeric {
+                let digits = text.iter().take_while(|c| c.is_ascii_digit()).count();
+                (1..=digits).rev().collect()
+            } else {
+                // A field can't reach into another directory
+                let limit = text.iter().position(|&c| c == '/').unwrap_or(text.len());
+                (1..=limit).collect()
+            };
+
+            for end in ends {
+                let value: String = text[..end].iter().collect::<String>().trim().to_string();
+                if value.is_empty() {
+                    continue;
+                }
+                values.push((field.clone(), value));
+                if match_parts(rest, &text[end..], values) {
+                    return true;
+                }
+                values.pop();
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(pattern: &str, path: &str) -> Option<Vec<(String, String)>> {
+        PathPattern::parse(pattern)
+            .unwrap()
+            .match_path(Path::new(path))
+    }
+
+    fn pairs(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values
+            .iter()
+            .map(|(f, v)| (f.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_match_path() {
+        assert_eq!(
+            matched(
+                "{track} - {title} (Prod By {producer})",
+                "rips/08 - Pennyroyal (prod by Lee Bannon).mp3"
+            ),
+            Some(pairs(&[
+                ("track_number", "08"),
+                ("title", "Pennyroyal"),
+                ("people:producer", "Lee Bannon"),
+            ]))
+        );
+        assert_eq!(
+            matched(
+                "{artist}/{year} - {album}/{disc}-{track} {title}.mp3",
+                "/music/Madvillain/2004 - Madvillainy/1-02 Accordion.MP3"
+            ),
+            Some(pairs(&[
+                ("artist", "Madvillain"),
+                ("year", "2004"),
+                ("album", "Madvillainy"),
+                ("disc_number", "1"),
+                ("track_number", "02"),
+                ("title", "Accordion"),
+            ]))
+        );
+        assert_eq!(
+            matched("{track}{title}", "11Two Thousand.flac"),
+            Some(pairs(&[("track_number", "11"), ("title", "Two Thousand")]))
+        );
+        assert_eq!(
+            matched("{_} - {title}", "a - b - c.ogg"),
+            Some(pairs(&[("title", "b - c")]))
+        );
+        assert_eq!(matched("{track} - {title}", "Pennyroyal.mp3"), None);
+        assert_eq!(matched("{artist}/{album}/{title}", "song.mp3"), None);
+    }
+
+    #[test]
+    fn test_imports_fill_empty_fields() {
+        let pattern = PathPattern::parse("{artist} - {track} - {title}").unwrap();
+        let mut metadata = TrackMetadata::default();
+        metadata.set_field("artist", "Joey Bada$$");
+        metadata.set_field("title", "Pennyroyal");
+
+        let imports = pattern
+            .imports(
+                Path::new("Joey Badass - 08 - Pennyroyal.mp3"),
+                &metadata,
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            imports,
+            vec![
+                FieldImport {
+                    field: "artist".to_string(),
+                    current: Some("Joey Bada$$".to_string()),
+                    value: "Joey Badass".to_string(),
+                    apply: false,
+                },
+                FieldImport {
+                    field: "track_number".to_string(),
+                    current: None,
+                    value: "08".to_string(),
+                    apply: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for pattern in ["{title", "{titel}", "{artist}{title}", "{_}.mp3"] {
+            assert!(PathPattern::parse(pattern).is_err(), "{}", pattern);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::error::{AgentError, Result};
+use crate::llm::LLMClient;
+use crate::metadata::TrackMetadata;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Default location of the library vector index
+pub const DEFAULT_INDEX: &str = "public/index.json";
+
+/// Number of texts sent to the embedding endpoint per request
+const EMBED_BATCH_SIZE: usize = 32;
+
+/// One embedded track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub file_path: String,
+    pub text: String,
+    pub vector: Vec<f32>,
+}
+
+/// A match returned by nearest-neighbour search
+#[derive(Debug, Clone)]
+pub struct SimilarTrack {
+    pub file_path: String,
+    pub text: String,
+    pub score: f32,
+}
+
+/// On-disk index of track embeddings for similarity search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorIndex {
+    pub embedding_model: String,
+    pub entries: Vec<IndexEntry>,
+}
+
+impl VectorIndex {
+    pub fn new(embedding_model: &str) -> Self {
+        Self {
+            embedding_model: embedding_model.to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Load an index, or start an empty one if the file doesn't exist yet
+    pub fn load_or_new(file_path: &str, embedding_model: &str) -> Result<Self> {
+        if !Path::new(file_path).exists() {
+            return Ok(Self::new(embedding_model));
+        }
+
+        let json = fs::read_to_string(file_path)
+            .map_err(|e| AgentError::FileRead(format!("Failed to read index: {}", e)))?;
+        let index: Self = serde_json::from_str(&json)
+            .map_err(|e| AgentError::MetadataParse(format!("Failed to parse index: {}", e)))?;
+
+        // Vectors from different models aren't comparable, so start over
+        if index.embedding_model != embedding_model {
+            println!(
+                "⚠️  Index was built with {}, rebuilding for {}",
+                index.embedding_model, embedding_model
+            );
+            return Ok(Self::new(embedding_model));
+        }
+
+        Ok(index)
+    }
+
+    pub fn save_to_file(&self, file_path: &str) -> Result<()> {
+        if let Some(parent) = Path::new(file_path).parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                AgentError::FileRead(format!("Failed to create index directory: {}", e))
+            })?;
+        }
+
+        let json = serde_json::to_string(self)
+            .map_err(|e| AgentError::MetadataParse(format!("Failed to serialize index: {}", e)))?;
+
+        fs::write(file_path, json)
+            .map_err(|e| AgentError::FileRead(format!("Failed to write index: {}", e)))
+    }
+
+    /// Embed `tracks`, found by scanning `root`, whose text is new or changed, and drop
+    /// stale entries; returns how many were embedded and how many were dropped
+    pub async fn update(
+        &mut self,
+        llm: &dyn LLMClient,
+        root: &str,
+        tracks: &[TrackMetadata],
+    ) -> Result<(usize, usize)> {
+        let removed = self.prune(root, tracks);
+        let pending: Vec<(String, String)> = tracks
+            .iter()
+            .map(|t| (t.file_path.clone(), t.to_embedding_text()))
+            .filter(|(path, text)| {
+                !self
+                    .entries
+                    .iter()
+                    .any(|e| &e.file_path == path && &e.text == text)
+            })
+            .collect();
+
+        for batch in pending.chunks(EMBED_BATCH_SIZE) {
+            let texts: Vec<String> = batch.iter().map(|(_, text)| text.clone()).collect();
+            let vectors = llm.embed(&texts).await?;
+
+            for ((file_path, text), vector) in batch.iter().zip(vectors) {
+                self.entries.retain(|e| &e.file_path != file_path);
+                self.entries.push(IndexEntry {
+                    file_path: file_path.clone(),
+                    text: text.clone(),
+                    vector,
+                });
+            }
+        }
+
+        Ok((pending.len(), removed))
+    }
+
+    /// Drop entries under `root` that the scan didn't find, and entries whose file no
+    /// longer exists; returns how many were dropped
+    fn prune(&mut self, root: &str, tracks: &[TrackMetadata]) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|e| {
+            let path = Path::new(&e.file_path);
+            let scanned = tracks.iter().any(|t| t.file_path == e.file_path);
+            path.exists() && (scanned || !path.starts_with(root))
+        });
+        before - self.entries.len()
+    }
+
+    /// Stored entry for a file, if it has been indexed
+    pub fn entry_for(&self, file_path: &str) -> Option<&IndexEntry> {
+        self.entries.iter().find(|e| e.file_path == file_path)
+    }
+
+    /// The `limit` entries most similar to `query` by cosine similarity
+    pub fn nearest(&self, query: &[f32], limit: usize, exclude: &str) -> Vec<SimilarTrack> {
+        let mut matches: Vec<SimilarTrack> = self
+            .entries
+            .iter()
+            .filter(|e| e.file_path != exclude)
+            .map(|e| SimilarTrack {
+                file_path: e.file_path.clone(),
+                text: e.text.clone(),
+                score: cosine_similarity(query, &e.vector),
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(file_path: &str, vector: Vec<f32>) -> IndexEntry {
+        IndexEntry {
+            file_path: file_path.to_string(),
+            text: String::new(),
+            vector,
+        }
+    }
+
+    #[test]
+    fn test_nearest_orders_by_similarity() {
+        let mut index = VectorIndex::new("test");
+        index.entries = vec![
+            entry("query.mp3", vec![1.0, 0.0]),
+            entry("far.mp3", vec![0.0, 1.0]),
+            entry("near.mp3", vec![0.9, 0.1]),
+        ];
+
+        let results = index.nearest(&[1.0, 0.0], 5, "query.mp3");
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].file_path, "near.mp3");
+        assert_eq!(results[1].file_path, "far.mp3");
+    }
+
+    #[test]
+    fn test_prune_stale_entries() {
+        let dir = std::env::temp_dir().join("music-agent-index-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("library")).unwrap();
+        fs::create_dir_all(dir.join("other")).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        for name in [
+            "library/kept.mp3",
+            "library/unreadable.mp3",
+            "other/kept.mp3",
+        ] {
+            fs::write(path(name), b"").unwrap();
+        }
+
+        let mut index = VectorIndex::new("test");
+        for name in [
+            "library/kept.mp3",
+            "library/unreadable.mp3",
+            "library/deleted.mp3",
+            "other/kept.mp3",
+            "other/deleted.mp3",
+        ] {
+            index.entries.push(entry(&path(name), vec![1.0]));
+        }
+        let tracks = vec![TrackMetadata {
+            file_path: path("library/kept.mp3"),
+            ..Default::default()
+        }];
+        let removed = index.prune(&path("library"), &tracks);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let kept: Vec<&str> = index.entries.iter().map(|e| e.file_path.as_str()).collect();
+        assert_eq!(removed, 3);
+        assert_eq!(kept, vec![path("library/kept.mp3"), path("other/kept.mp3")]);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod ollama;
+pub mod openai;
 
 use crate::error::Result;
 use async_trait::async_trait;
@@ -9,6 +10,15 @@ pub trait LLMClient: Send + Sync {
     /// Send a prompt to the LLM and get a response
     async fn generate(&self, prompt: &str) -> Result<String>;
 
+    /// Embed each input text into a vector (one vector per input, in order)
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>>;
+
     /// Get the name of the LLM provider
     fn provider_name(&self) -> &str;
+
+    /// Get the name of the model used for generation
+    fn model_name(&self) -> &str;
+
+    /// Get the name of the model used for embeddings
+    fn embedding_model_name(&self) -> &str;
 }
@@ -2,7 +2,6 @@ use crate::error::{AgentError, Result};
 use crate::llm::LLMClient;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 
 #[derive(Serialize, Deserialize, Debug)]
 struct OllamaRequest {
@@ -16,9 +15,21 @@ struct OllamaResponse {
     response: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+struct OllamaEmbedRequest {
+    model: String,
+    input: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
 pub struct OllamaClient {
     base_url: String,
     model: String,
+    embedding_model: String,
     client: reqwest::Client,
 }
 
@@ -27,6 +38,7 @@ impl OllamaClient {
         Self {
             base_url: base_url.to_string(),
             model: "llama3.2".to_string(), // Default model
+            embedding_model: "nomic-embed-text".to_string(),
             client: reqwest::Client::new(),
         }
     }
@@ -35,23 +47,20 @@ impl OllamaClient {
         self.model = model.to_string();
         self
     }
-}
 
-#[async_trait]
-impl LLMClient for OllamaClient {
-    async fn generate(&self, prompt: &str) -> Result<String> {
-        let url = format!("{}/api/generate", self.base_url);
+    pub fn with_embedding_model(mut self, model: &str) -> Self {
+        self.embedding_model = model.to_string();
+        self
+    }
 
-        let request_body = json!({
-            "model": self.model,
-            "prompt": prompt,
-            "stream": false
-        });
+    /// POST a JSON body and return the successful response
+    async fn post<T: Serialize>(&self, endpoint: &str, body: &T) -> Result<reqwest::Response> {
+        let url = format!("{}{}", self.base_url, endpoint);
 
         let response = self
             .client
             .post(&url)
-            .json(&request_body)
+            .json(body)
             .send()
             .await
             .map_err(|e| {
@@ -70,6 +79,21 @@ impl LLMClient for OllamaClient {
             )));
         }
 
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LLMClient for OllamaClient {
+    async fn generate(&self, prompt: &str) -> Result<String> {
+        let request_body = OllamaRequest {
+            model: self.model.clone(),
+            prompt: prompt.to_string(),
+            stream: false,
+        };
+
+        let response = self.post("/api/generate", &request_body).await?;
+
         let ollama_response: OllamaResponse = response.json().await.map_err(|e| {
             AgentError::LlmResponse(format!("Failed to parse Ollama response: {}", e))
         })?;
@@ -77,9 +101,40 @@ impl LLMClient for OllamaClient {
         Ok(ollama_response.response)
     }
 
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let request_body = OllamaEmbedRequest {
+            model: self.embedding_model.clone(),
+            input: inputs.to_vec(),
+        };
+
+        let response = self.post("/api/embed", &request_body).await?;
+
+        let embed_response: OllamaEmbedResponse = response.json().await.map_err(|e| {
+            AgentError::LlmResponse(format!("Failed to parse Ollama embeddings: {}", e))
+        })?;
+
+        if embed_response.embeddings.len() != inputs.len() {
+            return Err(AgentError::LlmResponse(format!(
+                "Ollama returned {} embeddings for {} inputs",
+                embed_response.embeddings.len(),
+                inputs.len()
+            )));
+        }
+
+        Ok(embed_response.embeddings)
+    }
+
     fn provider_name(&self) -> &str {
         "Ollama"
     }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    fn embedding_model_name(&self) -> &str {
+        &self.embedding_model
+    }
 }
 
 #[cfg(test)]
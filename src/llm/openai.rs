@@ -0,0 +1,187 @@
+use crate::error::{AgentError, Result};
+use crate::llm::LLMClient;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct EmbeddingRequest {
+    model: String,
+    input: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+/// Client for any server speaking the OpenAI `/v1` API (OpenAI, LM Studio, vLLM, ...)
+pub struct OpenAIClient {
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    embedding_model: String,
+    client: reqwest::Client,
+}
+
+impl OpenAIClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+            model: "gpt-4o-mini".to_string(), // Default model
+            embedding_model: "text-embedding-3-small".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self
+    }
+
+    pub fn with_embedding_model(mut self, model: &str) -> Self {
+        self.embedding_model = model.to_string();
+        self
+    }
+
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// POST a JSON body and return the successful response
+    async fn post<T: Serialize>(&self, endpoint: &str, body: &T) -> Result<reqwest::Response> {
+        let url = format!("{}{}", self.base_url, endpoint);
+
+        let mut request = self.client.post(&url).json(body);
+        if let Some(ref api_key) = self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            AgentError::LlmRequest(format!(
+                "Failed to connect to OpenAI-compatible server at {}: {}",
+                self.base_url, e
+            ))
+        })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AgentError::LlmRequest(format!(
+                "OpenAI request failed with status {}: {}",
+                status, error_text
+            )));
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LLMClient for OpenAIClient {
+    async fn generate(&self, prompt: &str) -> Result<String> {
+        let request_body = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: prompt.to_string(),
+            }],
+        };
+
+        let response = self.post("/v1/chat/completions", &request_body).await?;
+
+        let chat_response: ChatResponse = response.json().await.map_err(|e| {
+            AgentError::LlmResponse(format!("Failed to parse OpenAI response: {}", e))
+        })?;
+
+        chat_response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| AgentError::LlmResponse("OpenAI response had no choices".to_string()))
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let request_body = EmbeddingRequest {
+            model: self.embedding_model.clone(),
+            input: inputs.to_vec(),
+        };
+
+        let response = self.post("/v1/embeddings", &request_body).await?;
+
+        let mut embedding_response: EmbeddingResponse = response.json().await.map_err(|e| {
+            AgentError::LlmResponse(format!("Failed to parse OpenAI embeddings: {}", e))
+        })?;
+
+        if embedding_response.data.len() != inputs.len() {
+            return Err(AgentError::LlmResponse(format!(
+                "OpenAI returned {} embeddings for {} inputs",
+                embedding_response.data.len(),
+                inputs.len()
+            )));
+        }
+
+        // The API may return items out of order; `index` refers to the input position
+        embedding_response.data.sort_by_key(|d| d.index);
+        Ok(embedding_response
+            .data
+            .into_iter()
+            .map(|d| d.embedding)
+            .collect())
+    }
+
+    fn provider_name(&self) -> &str {
+        "OpenAI"
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    fn embedding_model_name(&self) -> &str {
+        &self.embedding_model
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_client_creation() {
+        let client = OpenAIClient::new("https://api.openai.com/").with_model("gpt-4o");
+        assert_eq!(client.provider_name(), "OpenAI");
+        assert_eq!(client.base_url, "https://api.openai.com");
+        assert_eq!(client.model, "gpt-4o");
+    }
+}
@@ -1,38 +1,360 @@
 mod agent;
+mod credits;
+mod diff;
 mod error;
+mod eval;
+mod feedback;
+mod import;
+mod index;
 mod llm;
 mod metadata;
+mod prompts;
+mod rename;
+mod review;
 mod suggestions;
 
 use agent::MusicAgent;
-use clap::Parser;
-use error::Result;
-use metadata::{reader, writer};
+use clap::{Parser, Subcommand, ValueEnum};
+use credits::CreditStyle;
+use diff::{FieldDiff, TagSnapshot};
+use error::{AgentError, Result};
+use eval::{EvalComparison, EvalRun};
+use feedback::{FeedbackDecision, FeedbackStore};
+use import::PathPattern;
+use index::VectorIndex;
+use llm::LLMClient;
+use metadata::artwork::{self, CoverArt, PictureInfo};
+use metadata::formats::{audio_hash, AudioFormat, Id3Version};
+use metadata::lyrics::Lyrics;
+use metadata::undo::{Change, UndoJournal};
+use metadata::writer::{InPlace, WriteOptions};
+use metadata::{reader, writer, TrackMetadata};
+use prompts::PromptTemplates;
+use rename::RenamePlan;
+use std::path::Path;
 use suggestions::SuggestionsReport;
 
 #[derive(Parser, Debug)]
 #[command(name = "music-agent")]
 #[command(about = "AI-powered music metadata analyzer", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Args {
-    /// Path to the MP3 file to analyze
-    #[arg(value_name = "FILE", required_unless_present = "apply")]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to the audio file to analyze (MP3, FLAC, M4A, Ogg or Opus)
+    #[arg(value_name = "FILE", required_unless_present_any = ["apply", "review"])]
     file: Option<String>,
 
-    /// LLM model to use (default: llama3.2)
-    #[arg(short, long, default_value = "llama3.2")]
-    model: String,
+    /// LLM model to use (default: llama3.2 for Ollama, gpt-4o-mini for OpenAI)
+    #[arg(short, long, global = true)]
+    model: Option<String>,
 
     /// Ollama server URL (default: http://localhost:11434)
-    #[arg(short, long, default_value = "http://localhost:11434")]
+    #[arg(short, long, default_value = "http://localhost:11434", global = true)]
     ollama_url: String,
 
+    /// LLM provider to use
+    #[arg(long, value_enum, default_value_t = Provider::Ollama, global = true)]
+    provider: Provider,
+
+    /// Base URL of an OpenAI-compatible server (API key read from OPENAI_API_KEY)
+    #[arg(long, default_value = "https://api.openai.com", global = true)]
+    openai_url: String,
+
+    /// Embedding model (default: nomic-embed-text for Ollama, text-embedding-3-small for OpenAI)
+    #[arg(long, value_name = "MODEL", global = true)]
+    embedding_model: Option<String>,
+
     /// Generate suggestions file instead of just analysis
     #[arg(short, long)]
     suggestions: bool,
 
-    /// Apply suggestions from a .suggestions.json file (creates new .updated.mp3)
+    /// Apply suggestions from a .suggestions.json file (writes a new copy under updated/)
     #[arg(short, long, value_name = "SUGGESTIONS_FILE")]
     apply: Option<String>,
+
+    /// Interactively accept, reject or edit each suggestion, then apply accepted ones
+    #[arg(short, long, value_name = "SUGGESTIONS_FILE", conflicts_with = "apply")]
+    review: Option<String>,
+
+    /// With --apply/--review: merge ID3v1/APEv2 values into the ID3v2 tag and strip the legacy tags
+    #[arg(long)]
+    consolidate: bool,
+
+    /// With --apply/--review: embed IMAGE as the front cover (omit IMAGE to use cover.jpg/folder.png beside the track)
+    #[arg(long, value_name = "IMAGE", num_args = 0..=1, default_missing_value = "")]
+    embed_art: Option<String>,
+
+    /// With --apply/--review: embed lyrics from an .lrc or text file (omit LRC to use the .lrc named after the track)
+    #[arg(long, value_name = "LRC", num_args = 0..=1, default_missing_value = "")]
+    embed_lyrics: Option<String>,
+
+    /// With --apply/--review: ID3v2 version to write to MP3s (default: 2.4)
+    #[arg(long, value_enum, value_name = "VERSION")]
+    id3_version: Option<TagVersion>,
+
+    /// With --apply/--review: change the original file instead of writing a copy, saving its old tags to the undo journal
+    #[arg(long)]
+    in_place: bool,
+
+    /// With --apply/--review: don't list the tag, art and frame changes after writing
+    #[arg(long)]
+    no_diff: bool,
+
+    /// Directory with custom prompt templates (analysis.txt / suggestions.txt)
+    #[arg(short, long, value_name = "TEMPLATE_DIR", global = true)]
+    template: Option<String>,
+
+    /// Reviewer decision log used for few-shot examples
+    #[arg(long, value_name = "FEEDBACK_FILE", default_value = feedback::DEFAULT_STORE, global = true)]
+    feedback: String,
+
+    /// Undo journal for --in-place writes
+    #[arg(long, value_name = "JOURNAL_FILE", default_value = metadata::undo::DEFAULT_JOURNAL, global = true)]
+    journal: String,
+
+    /// Where suggestions put "feat." / "Prod By" credits found in titles and artists
+    #[arg(long, value_enum, value_name = "STYLE", default_value_t = CreditMode::Off, global = true)]
+    credit_style: CreditMode,
+
+    /// Maximum number of past decisions injected as few-shot examples (0 disables)
+    #[arg(long, value_name = "N", default_value_t = 5, global = true)]
+    few_shot: usize,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Provider {
+    Ollama,
+    Openai,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum TagVersion {
+    #[value(name = "2.3")]
+    V23,
+    #[value(name = "2.4")]
+    V24,
+}
+
+impl From<TagVersion> for Id3Version {
+    fn from(version: TagVersion) -> Self {
+        match version {
+            TagVersion::V23 => Id3Version::V23,
+            TagVersion::V24 => Id3Version::V24,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CreditMode {
+    /// Leave credits alone
+    Off,
+    /// Keep them in the title: "Song (feat. B) (prod. by C)"
+    Title,
+    /// Featured artists join the artist field; producers go to the credits list
+    Artist,
+    /// Featured artists and producers go to the credits list (ID3 TMCL/TIPL)
+    People,
+}
+
+impl CreditMode {
+    fn style(self) -> Option<CreditStyle> {
+        match self {
+            CreditMode::Off => None,
+            CreditMode::Title => Some(CreditStyle::Title),
+            CreditMode::Artist => Some(CreditStyle::Artist),
+            CreditMode::People => Some(CreditStyle::People),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Score the agent's suggestions against a golden dataset
+    Eval {
+        /// Golden JSON file listing tracks and their correct metadata
+        #[arg(value_name = "GOLDEN_FILE")]
+        golden: String,
+
+        /// Where to write the run results (default: next to the golden file)
+        #[arg(long, value_name = "RUN_FILE")]
+        output: Option<String>,
+
+        /// Previous run to compare against (writes a .comparison.json report)
+        #[arg(long, value_name = "RUN_FILE")]
+        baseline: Option<String>,
+    },
+
+    /// Record accepted/rejected suggestions, or list recorded decisions
+    Feedback {
+        /// Suggestions file the decisions refer to (omit to list the log)
+        #[arg(value_name = "SUGGESTIONS_FILE")]
+        suggestions: Option<String>,
+
+        /// Numbers of the suggestions that were accepted (e.g. 1,3)
+        #[arg(long, value_delimiter = ',', value_name = "N")]
+        accept: Vec<usize>,
+
+        /// Numbers of the suggestions that were rejected
+        #[arg(long, value_delimiter = ',', value_name = "N")]
+        reject: Vec<usize>,
+    },
+
+    /// Save the pictures embedded in a file as image files
+    ExtractArt {
+        /// Track to extract pictures from
+        #[arg(value_name = "FILE")]
+        file: String,
+
+        /// Directory to write images to (default: beside the track)
+        #[arg(short = 'd', long, value_name = "DIR")]
+        output: Option<String>,
+    },
+
+    /// Save the lyrics embedded in a file as an .lrc file
+    ExtractLyrics {
+        /// Track to extract lyrics from
+        #[arg(value_name = "FILE")]
+        file: String,
+
+        /// Directory to write the .lrc file to (default: beside the track)
+        #[arg(short = 'd', long, value_name = "DIR")]
+        output: Option<String>,
+    },
+
+    /// Write copies of the MP3s under a directory with their ID3v2 tags converted to another version
+    ConvertTags {
+        /// Directory to scan recursively
+        #[arg(value_name = "DIR")]
+        dir: String,
+
+        /// ID3v2 version to convert to
+        #[arg(long, value_enum, value_name = "VERSION")]
+        to: TagVersion,
+
+        /// Convert the files themselves, saving their old tags to the undo journal
+        #[arg(long)]
+        in_place: bool,
+    },
+
+    /// Move tracks to paths built from their tags (prints the plan unless --execute is given)
+    Rename {
+        /// Track, or directory to scan recursively
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Path pattern, e.g. "{artist}/{album}/{track:02} {title}"; files keep their extension
+        #[arg(long, value_name = "PATTERN", default_value = rename::DEFAULT_PATTERN)]
+        pattern: String,
+
+        /// Path pattern for compilations
+        #[arg(long, value_name = "PATTERN", default_value = rename::DEFAULT_COMPILATION_PATTERN)]
+        compilation_pattern: String,
+
+        /// Directory the new paths start from (default: PATH, or the track's directory)
+        #[arg(short = 'd', long, value_name = "DIR")]
+        output: Option<String>,
+
+        /// Move the files, recording the moves in the undo journal
+        #[arg(long)]
+        execute: bool,
+    },
+
+    /// Fill empty tags from file paths matched against a pattern (prints the changes unless --execute is given)
+    TagFromPath {
+        /// Track, or directory to scan recursively
+        #[arg(value_name = "PATH")]
+        path: String,
+
+        /// Pattern for the end of each path, e.g. "{artist}/{album}/{track} - {title} (Prod By {producer})"; {_} skips text
+        #[arg(long, value_name = "PATTERN")]
+        pattern: String,
+
+        /// Also replace fields that already have a value
+        #[arg(long)]
+        overwrite: bool,
+
+        /// Write the tags (to copies under updated/ unless --in-place is given)
+        #[arg(long)]
+        execute: bool,
+
+        /// With --execute: change the files themselves, saving their old tags to the undo journal
+        #[arg(long, requires = "execute")]
+        in_place: bool,
+    },
+
+    /// Show the tag differences between two files, including art and custom frames
+    Diff {
+        /// File to compare from, e.g. the original
+        #[arg(value_name = "FILE_A")]
+        a: String,
+
+        /// File to compare to, e.g. the updated copy
+        #[arg(value_name = "FILE_B")]
+        b: String,
+    },
+
+    /// Restore tags changed by --in-place writes and files moved by rename (omit FILE and --run to list the runs)
+    Undo {
+        /// File to restore to before its latest in-place change or rename
+        #[arg(value_name = "FILE", conflicts_with = "run")]
+        file: Option<String>,
+
+        /// Restore every file changed by a run ("last" for the latest run)
+        #[arg(long, value_name = "RUN_ID")]
+        run: Option<String>,
+
+        /// Restore even if the file was changed again after the write
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Embed every supported audio file under a directory into the local vector index
+    Index {
+        /// Directory to scan recursively
+        #[arg(value_name = "DIR")]
+        dir: String,
+
+        /// Vector index file
+        #[arg(long, value_name = "INDEX_FILE", default_value = index::DEFAULT_INDEX)]
+        index: String,
+    },
+
+    /// Find the indexed tracks most similar to a file
+    Similar {
+        /// Track to find neighbours for
+        #[arg(value_name = "FILE")]
+        file: String,
+
+        /// Number of results to show
+        #[arg(short = 'k', long, default_value_t = 5)]
+        limit: usize,
+
+        /// Vector index file
+        #[arg(long, value_name = "INDEX_FILE", default_value = index::DEFAULT_INDEX)]
+        index: String,
+    },
+}
+
+/// How --apply / --review should change the copy beyond the suggestions
+struct ApplyOptions {
+    consolidate: bool,
+    embed_art: Option<String>,
+    embed_lyrics: Option<String>,
+    id3_version: Option<Id3Version>,
+    in_place: Option<InPlace>,
+    diff: bool,
+}
+
+impl ApplyOptions {
+    /// True if the copy is worth writing even with no suggestions to apply
+    fn changes_file(&self) -> bool {
+        self.consolidate
+            || self.embed_art.is_some()
+            || self.embed_lyrics.is_some()
+            || self.id3_version.is_some()
+    }
 }
 
 #[tokio::main]
@@ -42,26 +364,132 @@ async fn main() -> Result<()> {
     println!("🎵 Music Library Agent v0.2.0");
     println!("{}\n", "=".repeat(62));
 
+    match args.command {
+        Some(Command::Eval {
+            ref golden,
+            ref output,
+            ref baseline,
+        }) => {
+            let agent = create_agent(&args)?;
+            return eval_mode(&agent, golden, output.as_deref(), baseline.as_deref()).await;
+        }
+        Some(Command::Feedback {
+            ref suggestions,
+            ref accept,
+            ref reject,
+        }) => {
+            return feedback_mode(&args.feedback, suggestions.as_deref(), accept, reject);
+        }
+        Some(Command::Index { ref dir, ref index }) => {
+            let llm_client = create_llm_client(&args);
+            return index_mode(llm_client.as_ref(), dir, index).await;
+        }
+        Some(Command::Similar {
+            ref file,
+            limit,
+            ref index,
+        }) => {
+            let llm_client = create_llm_client(&args);
+            return similar_mode(llm_client.as_ref(), file, limit, index).await;
+        }
+        Some(Command::ExtractArt {
+            ref file,
+            ref output,
+        }) => {
+            return extract_art_mode(file, output.as_deref());
+        }
+        Some(Command::ExtractLyrics {
+            ref file,
+            ref output,
+        }) => {
+            return extract_lyrics_mode(file, output.as_deref());
+        }
+        Some(Command::ConvertTags {
+            ref dir,
+            to,
+            in_place,
+        }) => {
+            let in_place = in_place.then(|| new_run(&args.journal));
+            return convert_tags_mode(dir, to.into(), in_place.as_ref());
+        }
+        Some(Command::Rename {
+            ref path,
+            ref pattern,
+            ref compilation_pattern,
+            ref output,
+            execute,
+        }) => {
+            let templates = (
+                rename::Template::parse(pattern)?,
+                rename::Template::parse(compilation_pattern)?,
+            );
+            let run = execute.then(|| new_run(&args.journal));
+            return rename_mode(path, output.as_deref(), &templates, run.as_ref());
+        }
+        Some(Command::TagFromPath {
+            ref path,
+            ref pattern,
+            overwrite,
+            execute,
+            in_place,
+        }) => {
+            let pattern = PathPattern::parse(pattern)?;
+            let write = execute.then(|| WriteOptions {
+                in_place: in_place.then(|| new_run(&args.journal)),
+                ..Default::default()
+            });
+            return tag_from_path_mode(path, &pattern, overwrite, write.as_ref());
+        }
+        Some(Command::Diff { ref a, ref b }) => {
+            return diff_mode(a, b);
+        }
+        Some(Command::Undo {
+            ref file,
+            ref run,
+            force,
+        }) => {
+            return undo_mode(&args.journal, file.as_deref(), run.as_deref(), force);
+        }
+        None => {}
+    }
+
+    let apply_options = ApplyOptions {
+        consolidate: args.consolidate,
+        embed_art: args.embed_art.clone(),
+        embed_lyrics: args.embed_lyrics.clone(),
+        id3_version: args.id3_version.map(Id3Version::from),
+        in_place: args.in_place.then(|| new_run(&args.journal)),
+        diff: !args.no_diff,
+    };
+
     // Mode 1: Apply suggestions from JSON file
-    if let Some(suggestions_file) = args.apply {
-        return apply_suggestions_mode(&suggestions_file);
+    if let Some(ref suggestions_file) = args.apply {
+        return apply_suggestions_mode(suggestions_file, &args.feedback, &apply_options);
+    }
+
+    // Mode 1b: Review suggestions interactively, then apply accepted ones
+    if let Some(ref suggestions_file) = args.review {
+        return review_mode(suggestions_file, &args.feedback, &apply_options);
     }
 
     // Step 1: Read metadata from file
-    let file_path = args.file.expect("FILE is required for analysis mode");
+    let file_path = args
+        .file
+        .clone()
+        .expect("FILE is required for analysis mode");
     println!("📖 Reading metadata from: {}", file_path);
     let metadata = reader::read_metadata(&file_path)?;
+    print_tag_summary(&metadata);
 
-    // Step 2: Create LLM client
-    println!("🤖 Connecting to Ollama ({})...", args.ollama_url);
-    let llm_client = llm::ollama::OllamaClient::new(&args.ollama_url).with_model(&args.model);
-
-    // Step 3: Create agent and analyze
-    let agent = MusicAgent::new(Box::new(llm_client));
+    // Step 2: Create agent backed by the LLM client
+    let agent = create_agent(&args)?;
 
     // Mode 2: Generate suggestions
     if args.suggestions {
-        let suggestions_report = agent.analyze_with_suggestions(&metadata).await?;
+        let suggestions_report = agent
+            .analyze_with_suggestions(&metadata)
+            .await?
+            .with_audio_hash(audio_hash(Path::new(&file_path)).ok());
         suggestions_report.display();
 
         let file_path = suggestions_report.save_to_file()?;
@@ -81,22 +509,747 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-/// Apply suggestions from a JSON file to create updated MP3
-fn apply_suggestions_mode(suggestions_file: &str) -> Result<()> {
+/// Create the LLM client for the selected provider
+fn create_llm_client(args: &Args) -> Box<dyn LLMClient> {
+    match args.provider {
+        Provider::Ollama => {
+            println!("🤖 Connecting to Ollama ({})...", args.ollama_url);
+            let mut client = llm::ollama::OllamaClient::new(&args.ollama_url);
+            if let Some(ref model) = args.model {
+                client = client.with_model(model);
+            }
+            if let Some(ref model) = args.embedding_model {
+                client = client.with_embedding_model(model);
+            }
+            Box::new(client)
+        }
+        Provider::Openai => {
+            println!(
+                "🤖 Connecting to OpenAI-compatible server ({})...",
+                args.openai_url
+            );
+            let mut client = llm::openai::OpenAIClient::new(&args.openai_url)
+                .with_api_key(std::env::var("OPENAI_API_KEY").ok());
+            if let Some(ref model) = args.model {
+                client = client.with_model(model);
+            }
+            if let Some(ref model) = args.embedding_model {
+                client = client.with_embedding_model(model);
+            }
+            Box::new(client)
+        }
+    }
+}
+
+/// Create the LLM client and agent with the selected prompt templates
+fn create_agent(args: &Args) -> Result<MusicAgent> {
+    let llm_client = create_llm_client(args);
+
+    let templates = match args.template {
+        Some(ref dir) => {
+            println!("🧩 Loading prompt templates from: {}", dir);
+            PromptTemplates::load_from_dir(dir)?
+        }
+        None => PromptTemplates::default(),
+    };
+
+    let feedback = FeedbackStore::load(&args.feedback)?;
+
+    let agent = MusicAgent::new(llm_client)
+        .with_templates(templates)
+        .with_feedback(feedback, args.few_shot);
+    Ok(match args.credit_style.style() {
+        Some(style) => agent.with_credit_style(style),
+        None => agent,
+    })
+}
+
+/// Run the agent over a golden dataset, score it and optionally compare with a baseline
+async fn eval_mode(
+    agent: &MusicAgent,
+    golden_file: &str,
+    output: Option<&str>,
+    baseline: Option<&str>,
+) -> Result<()> {
+    println!("🧪 Evaluating against golden file: {}", golden_file);
+    let run = EvalRun::run(agent, golden_file).await?;
+    run.display();
+
+    let output = match output {
+        Some(path) => path.to_string(),
+        None => {
+            let template = run.prompt_template.name.replace(['/', '\\', ':'], "_");
+            let model = run.model.replace(['/', '\\', ':'], "_");
+            Path::new(golden_file)
+                .with_file_name(format!(
+                    "{}.{}-{}.eval.json",
+                    stem(golden_file),
+                    model,
+                    template
+                ))
+                .to_string_lossy()
+                .to_string()
+        }
+    };
+    run.save_to_file(&output)?;
+    println!("\n💾 Eval run saved to: {}", output);
+
+    if let Some(baseline_file) = baseline {
+        let baseline_run = EvalRun::load_from_file(baseline_file)?;
+        let comparison = EvalComparison::new(&baseline_run, &run);
+        comparison.display();
+
+        let comparison_file = Path::new(&output)
+            .with_file_name(format!("{}.comparison.json", stem(&output)))
+            .to_string_lossy()
+            .to_string();
+        comparison.save_to_file(&comparison_file)?;
+        println!("\n💾 Comparison saved to: {}", comparison_file);
+    }
+
+    Ok(())
+}
+
+fn stem(file_path: &str) -> String {
+    let name = Path::new(file_path)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    name.strip_suffix(".json")
+        .map(|s| s.to_string())
+        .unwrap_or(name)
+}
+
+/// Embed every track under a directory into the vector index
+async fn index_mode(llm: &dyn LLMClient, dir: &str, index_file: &str) -> Result<()> {
+    println!("📂 Scanning: {}", dir);
+    let mut tracks = Vec::new();
+    for file in reader::find_audio_files(dir)? {
+        match reader::read_metadata(&file) {
+            Ok(metadata) => tracks.push(metadata),
+            Err(e) => println!("   ⚠️  Skipping {}: {}", file, e),
+        }
+    }
+
+    let mut index = VectorIndex::load_or_new(index_file, llm.embedding_model_name())?;
+    println!(
+        "🧮 Embedding {} track(s) with {}...",
+        tracks.len(),
+        llm.embedding_model_name()
+    );
+    let (embedded, removed) = index.update(llm, dir, &tracks).await?;
+    index.save_to_file(index_file)?;
+
+    println!(
+        "\n✅ {} new or changed, {} removed, {} total in {}",
+        embedded,
+        removed,
+        index.entries.len(),
+        index_file
+    );
+    Ok(())
+}
+
+/// Show the indexed tracks nearest to a file
+async fn similar_mode(
+    llm: &dyn LLMClient,
+    file: &str,
+    limit: usize,
+    index_file: &str,
+) -> Result<()> {
+    println!("📖 Reading metadata from: {}", file);
+    let metadata = reader::read_metadata(file)?;
+    let text = metadata.to_embedding_text();
+
+    let index = VectorIndex::load_or_new(index_file, llm.embedding_model_name())?;
+    if index.entries.is_empty() {
+        return Err(AgentError::FileRead(format!(
+            "Index {} is empty. Build it first with: cargo run --release -- index <DIR>",
+            index_file
+        )));
+    }
+
+    // Reuse the stored vector unless the tags changed since indexing
+    let query = match index.entry_for(file) {
+        Some(entry) if entry.text == text => entry.vector.clone(),
+        _ => llm.embed(std::slice::from_ref(&text)).await?.remove(0),
+    };
+
+    println!("\n🔎 Tracks similar to: {}\n", text);
+    for (i, track) in index.nearest(&query, limit, file).iter().enumerate() {
+        println!("{}. [{:.3}] {}", i + 1, track.score, track.text);
+        println!("   {}", track.file_path);
+    }
+    Ok(())
+}
+
+/// Show which tag formats were found and where they disagree
+fn print_tag_summary(metadata: &TrackMetadata) {
+    if metadata.tag_types.is_empty() {
+        println!("🏷️  No tags found");
+    } else {
+        println!("🏷️  Tags found: {}", metadata.tag_types.join(", "));
+    }
+
+    for conflict in &metadata.tag_conflicts {
+        let values: Vec<String> = conflict
+            .values
+            .iter()
+            .map(|(tag, value)| format!("{} \"{}\"", tag, value))
+            .collect();
+        println!(
+            "⚠️  {} differs between tags: {}",
+            conflict.field,
+            values.join(" vs ")
+        );
+    }
+    if !metadata.tag_conflicts.is_empty() {
+        println!("💡 Use --consolidate when applying to merge everything into one ID3v2 tag");
+    }
+
+    if let Some(mismatch) = &metadata.format_mismatch {
+        println!("⚠️  {}", mismatch);
+    }
+    for issue in &metadata.encoding_issues {
+        println!("⚠️  Encoding: {}", issue);
+    }
+
+    for picture in &metadata.pictures {
+        println!("🖼️  {}", picture);
+    }
+    for warning in artwork::picture_warnings(&metadata.pictures) {
+        println!("⚠️  {}", warning);
+    }
+
+    for lyrics in &metadata.lyrics {
+        println!("🎤 Lyrics: {}", lyrics);
+    }
+}
+
+/// Convert the MP3s under a directory whose ID3v2 tag isn't `version` yet, writing
+/// copies or (with `in_place`) changing the files themselves
+fn convert_tags_mode(dir: &str, version: Id3Version, in_place: Option<&InPlace>) -> Result<()> {
+    println!("📂 Scanning: {}", dir);
+    let mut converted = 0;
+    let mut unchanged = 0;
+    for file in reader::find_audio_files(dir)? {
+        if AudioFormat::from_path(Path::new(&file)) != Some(AudioFormat::Mp3) {
+            continue;
+        }
+        match writer::convert_tags_safely(&file, version, in_place) {
+            Ok(Some(written)) if in_place.is_some() => {
+                println!("   🔄 {}", written.path);
+                converted += 1;
+            }
+            Ok(Some(written)) => {
+                println!("   🔄 {} → {}", file, written.path);
+                converted += 1;
+            }
+            Ok(None) => unchanged += 1,
+            Err(e) => println!("   ⚠️  Skipping {}: {}", file, e),
+        }
+    }
+
+    println!(
+        "\n✅ Converted {} file(s) to {} ({} already {} or untagged)",
+        converted, version, unchanged, version
+    );
+    match in_place {
+        Some(in_place) if converted > 0 => print_undo_hint(in_place),
+        None if converted > 0 => {
+            println!("   Originals are unchanged; the copies are in updated/ beside them")
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Start a run of in-place writes journaled to `journal`
+fn new_run(journal: &str) -> InPlace {
+    InPlace {
+        journal: journal.to_string(),
+        run_id: UndoJournal::new_run_id(),
+    }
+}
+
+fn print_undo_hint(in_place: &InPlace) {
+    println!(
+        "   Undo information saved to {} (run {})",
+        in_place.journal, in_place.run_id
+    );
+    let journal = if in_place.journal == metadata::undo::DEFAULT_JOURNAL {
+        String::new()
+    } else {
+        format!(" --journal {}", in_place.journal)
+    };
+    println!(
+        "\n💡 To undo, run:\n   cargo run --release -- undo --run {}{}",
+        in_place.run_id, journal
+    );
+}
+
+/// Restore files from the undo journal, or list its runs
+fn undo_mode(journal_file: &str, file: Option<&str>, run: Option<&str>, force: bool) -> Result<()> {
+    let mut journal = UndoJournal::load(journal_file)?;
+
+    let entries = match (file, run) {
+        (Some(file), _) => journal.latest_for(file)?.into_iter().collect(),
+        (None, Some(run)) => journal.run(run),
+        (None, None) => {
+            println!(
+                "↩️  {} change(s) in {}\n",
+                journal.entries().len(),
+                journal.path()
+            );
+            let mut runs: Vec<(&str, usize)> = Vec::new();
+            for entry in journal.entries() {
+                match runs.last_mut() {
+                    Some((id, count)) if *id == entry.run_id => *count += 1,
+                    _ => runs.push((&entry.run_id, 1)),
+                }
+            }
+            for (id, count) in runs {
+                println!("  {}  {} file(s)", id, count);
+            }
+            return Ok(());
+        }
+    };
+
+    if entries.is_empty() {
+        println!("↩️  Nothing to undo in {}", journal.path());
+        return Ok(());
+    }
+
+    let mut restored = Vec::new();
+    for entry in entries.iter().rev() {
+        match journal.restore(entry, force) {
+            Ok(()) => {
+                match &entry.change {
+                    Change::Moved { moved_from } => {
+                        println!("   ↩️  {} → {}", entry.file_path, moved_from)
+                    }
+                    Change::Tags { .. } => println!("   ↩️  {}", entry.file_path),
+                }
+                restored.push(entry.clone());
+            }
+            Err(e) => println!("   ⚠️  Skipping {}: {}", entry.file_path, e),
+        }
+    }
+    journal.remove(&restored)?;
+
+    println!(
+        "\n✅ Undid {} of {} change(s)",
+        restored.len(),
+        entries.len()
+    );
+    Ok(())
+}
+
+/// Show the tags a pattern finds in file paths, and write them with `write`
+fn tag_from_path_mode(
+    path: &str,
+    pattern: &PathPattern,
+    overwrite: bool,
+    write: Option<&WriteOptions>,
+) -> Result<()> {
+    let files = if Path::new(path).is_dir() {
+        reader::find_audio_files(path)?
+    } else {
+        vec![path.to_string()]
+    };
+    println!("📂 Matching {} file(s) against the pattern", files.len());
+
+    let mut updates = Vec::new();
+    let mut unmatched = 0;
+    let mut unreadable = 0;
+    for file in &files {
+        let metadata = match reader::read_metadata(file) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                println!("   ⚠️  Skipping {}: {}", file, e);
+                unreadable += 1;
+                continue;
+            }
+        };
+        let Some(imports) = pattern.imports(Path::new(file), &metadata, overwrite) else {
+            println!("   ⚠️  {} doesn't match the pattern", file);
+            unmatched += 1;
+            continue;
+        };
+        if imports.is_empty() {
+            continue;
+        }
+
+        println!("\n📄 {}", file);
+        let mut updated = metadata.clone();
+        for import in &imports {
+            let current = import.current.as_deref().unwrap_or("(missing)");
+            if import.apply {
+                println!("   {}: {} → {}", import.field, current, import.value);
+                updated.set_field(&import.field, &import.value);
+            } else {
+                println!(
+                    "   {}: keeping {} (path says {}; use --overwrite to replace)",
+                    import.field, current, import.value
+                );
+            }
+        }
+        if imports.iter().any(|i| i.apply) {
+            updates.push((file, updated));
+        }
+    }
+
+    println!(
+        "\n🏷️  {} file(s) to update, {} unchanged, {} not matching, {} unreadable",
+        updates.len(),
+        files.len() - updates.len() - unmatched - unreadable,
+        unmatched,
+        unreadable
+    );
+    let Some(options) = write else {
+        if !updates.is_empty() {
+            println!("\n💡 To write these tags, run again with --execute");
+        }
+        return Ok(());
+    };
+
+    let mut written = 0;
+    for (file, metadata) in &updates {
+        match writer::write_metadata_safely(file, metadata, options) {
+            Ok(copy) if options.in_place.is_some() => {
+                println!("   ✍️  {}", copy.path);
+                written += 1;
+            }
+            Ok(copy) => {
+                println!("   ✍️  {} → {}", file, copy.path);
+                written += 1;
+            }
+            Err(e) => println!("   ⚠️  Skipping {}: {}", file, e),
+        }
+    }
+    println!(
+        "\n✅ Wrote tags to {} of {} file(s)",
+        written,
+        updates.len()
+    );
+    match &options.in_place {
+        Some(in_place) if written > 0 => print_undo_hint(in_place),
+        None if written > 0 => {
+            println!("   Originals are unchanged; the copies are in updated/ beside them")
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Compare the tags of two files
+fn diff_mode(a: &str, b: &str) -> Result<()> {
+    println!("📊 Comparing tags");
+    println!("   A: {}", a);
+    println!("   B: {}", b);
+    println!();
+    print_tag_diff(&TagSnapshot::read(a)?.diff(&TagSnapshot::read(b)?));
+    Ok(())
+}
+
+/// One line per difference ("+" added, "-" removed, "~" changed), then a count
+fn print_tag_diff(diffs: &[FieldDiff]) {
+    if diffs.is_empty() {
+        println!("   ✅ The tags are identical");
+        return;
+    }
+    for diff in diffs {
+        println!("   {}", diff);
+    }
+    println!("   {} difference(s)", diffs.len());
+}
+
+/// Print the moves that put tracks where their tags say, and make them with `run`
+fn rename_mode(
+    path: &str,
+    output: Option<&str>,
+    (pattern, compilation): &(rename::Template, rename::Template),
+    run: Option<&InPlace>,
+) -> Result<()> {
+    let (files, root) = if Path::new(path).is_dir() {
+        (reader::find_audio_files(path)?, path)
+    } else {
+        let parent = Path::new(path).parent().and_then(|p| p.to_str());
+        (
+            vec![path.to_string()],
+            parent.filter(|p| !p.is_empty()).unwrap_or("."),
+        )
+    };
+    let root = Path::new(output.unwrap_or(root));
+
+    println!(
+        "📂 Planning new paths for {} file(s) under {}",
+        files.len(),
+        root.display()
+    );
+    let plan = RenamePlan::build(&files, root, pattern, compilation);
+
+    for step in &plan.moves {
+        println!("   {}\n   → {}", step.from.display(), step.to.display());
+    }
+    for (file, reason) in &plan.skipped {
+        println!("   ⚠️  Skipping {}: {}", file.display(), reason);
+    }
+    println!(
+        "\n🗂️  {} to move, {} already in place, {} skipped",
+        plan.moves.len(),
+        plan.unchanged,
+        plan.skipped.len()
+    );
+
+    let Some(run) = run else {
+        if !plan.moves.is_empty() {
+            println!("\n💡 To move the files, run again with --execute");
+        }
+        return Ok(());
+    };
+    if plan.moves.is_empty() {
+        return Ok(());
+    }
+
+    plan.execute(&run.journal, &run.run_id)?;
+    println!("\n✅ Moved {} file(s)", plan.moves.len());
+    print_undo_hint(run);
+    Ok(())
+}
+
+/// Save every embedded picture of a track to image files
+fn extract_art_mode(file: &str, output_dir: Option<&str>) -> Result<()> {
+    let path = Path::new(file);
+    let format = AudioFormat::detect(path)?;
+
+    let pictures = format.backend().extract_pictures(path)?;
+    if pictures.is_empty() {
+        println!("🖼️  No embedded pictures in {}", file);
+        return Ok(());
+    }
+
+    let dir = match output_dir {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+    };
+    println!("🖼️  Extracting {} picture(s) from {}", pictures.len(), file);
+    let track_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    for (info, data) in &pictures {
+        let saved = artwork::save_picture(&dir, &track_stem, info, data)?;
+        println!("   {} → {}", info, saved.display());
+    }
+
+    let infos: Vec<PictureInfo> = pictures.into_iter().map(|(info, _)| info).collect();
+    for warning in artwork::picture_warnings(&infos) {
+        println!("⚠️  {}", warning);
+    }
+
+    Ok(())
+}
+
+/// Save the embedded lyrics of a track as "<stem>.lrc"
+fn extract_lyrics_mode(file: &str, output_dir: Option<&str>) -> Result<()> {
+    let path = Path::new(file);
+    let format = AudioFormat::detect(path)?;
+
+    // Synced lyrics come first and make the better .lrc
+    let Some(lyrics) = format.backend().extract_lyrics(path)?.into_iter().next() else {
+        println!("🎤 No embedded lyrics in {}", file);
+        return Ok(());
+    };
+
+    let metadata = reader::read_metadata(file)?;
+    let mut output = path.with_extension("lrc");
+    if let Some(dir) = output_dir {
+        std::fs::create_dir_all(dir)?;
+        output = Path::new(dir).join(output.file_name().unwrap_or_default());
+    }
+    if output.exists() {
+        return Err(AgentError::FileRead(format!(
+            "{} already exists (remove it or choose another directory with -d)",
+            output.display()
+        )));
+    }
+
+    std::fs::write(&output, lyrics.to_lrc(&metadata))?;
+    println!(
+        "🎤 Saved {} {}line(s) → {}",
+        lyrics.lines.len(),
+        if lyrics.is_synced() { "synced " } else { "" },
+        output.display()
+    );
+    if !lyrics.is_synced() {
+        println!("   (unsynchronized lyrics - the .lrc has no timestamps)");
+    }
+    Ok(())
+}
+
+/// Apply suggestions from a JSON file to create an updated copy of the track
+fn apply_suggestions_mode(
+    suggestions_file: &str,
+    feedback_file: &str,
+    options: &ApplyOptions,
+) -> Result<()> {
+    println!("📂 Loading suggestions from: {}", suggestions_file);
+    let mut suggestions = SuggestionsReport::load_from_file(suggestions_file)?;
+
+    write_updated_file(&mut suggestions, suggestions_file, options)?;
+
+    // Unreviewed files are applied wholesale, so count them as accepted for future
+    // few-shot examples (reviewed files were logged during review)
+    if !suggestions.is_reviewed() {
+        let decisions = suggestions
+            .suggestions
+            .iter()
+            .map(|s| FeedbackDecision::new(&suggestions.current_metadata, s, true))
+            .collect();
+        FeedbackStore::load(feedback_file)?.record(decisions)?;
+    }
+
+    Ok(())
+}
+
+/// Walk the suggestions interactively, save the decisions and apply accepted ones
+fn review_mode(suggestions_file: &str, feedback_file: &str, options: &ApplyOptions) -> Result<()> {
     println!("📂 Loading suggestions from: {}", suggestions_file);
-    let suggestions = SuggestionsReport::load_from_file(suggestions_file)?;
+    let mut suggestions = SuggestionsReport::load_from_file(suggestions_file)?;
+
+    if suggestions.suggestions.is_empty() {
+        println!("\n✅ No suggestions to review!");
+        return Ok(());
+    }
+
+    let decisions = {
+        let stdin = std::io::stdin();
+        let mut input = stdin.lock();
+        let mut output = std::io::stdout();
+        review::review_suggestions(&mut suggestions, &mut input, &mut output)?
+    };
+
+    suggestions.save_to_path(suggestions_file)?;
+    println!("\n💾 Decisions saved to: {}", suggestions_file);
+
+    let mut store = FeedbackStore::load(feedback_file)?;
+    let count = decisions.len();
+    store.record(decisions)?;
+    if count > 0 {
+        println!("📚 Recorded {} decision(s) to {}", count, store.path());
+    }
+
+    if !suggestions.should_apply && !options.changes_file() {
+        println!("\n✅ No suggestions accepted - nothing to apply");
+        return Ok(());
+    }
+
+    write_updated_file(&mut suggestions, suggestions_file, options)
+}
+
+/// Resolve and check the image requested with --embed-art
+fn load_cover_art(
+    suggestions: &SuggestionsReport,
+    embed_art: Option<&str>,
+) -> Result<Option<CoverArt>> {
+    let Some(image) = embed_art else {
+        return Ok(None);
+    };
+
+    let track = Path::new(&suggestions.file_path);
+    let image_path = if image.is_empty() {
+        CoverArt::find_beside(track).ok_or_else(|| {
+            AgentError::FileRead(format!(
+                "No cover.jpg/folder.png found beside {}",
+                suggestions.file_path
+            ))
+        })?
+    } else {
+        image.into()
+    };
+
+    let art = CoverArt::load(&image_path)?;
+    let info = art.info();
+    println!("\n🖼️  Embedding {} ({})", image_path.display(), info);
+
+    // Check the new cover alongside the pictures it will sit next to
+    let mut pictures: Vec<PictureInfo> = suggestions
+        .current_metadata
+        .pictures
+        .iter()
+        .filter(|p| p.picture_type != "Front cover")
+        .cloned()
+        .collect();
+    pictures.insert(0, info.clone());
+    for warning in artwork::picture_warnings(&pictures) {
+        println!("⚠️  {}", warning);
+    }
+    if suggestions
+        .current_metadata
+        .pictures
+        .iter()
+        .any(|p| p.hash == info.hash)
+    {
+        println!("   (already embedded - the picture will be left as is)");
+    }
+
+    Ok(Some(art))
+}
+
+/// Resolve and parse the lyrics requested with --embed-lyrics
+fn load_lyrics(
+    suggestions: &SuggestionsReport,
+    embed_lyrics: Option<&str>,
+) -> Result<Option<Lyrics>> {
+    let Some(lrc) = embed_lyrics else {
+        return Ok(None);
+    };
+
+    let lrc_path = if lrc.is_empty() {
+        Lyrics::find_beside(Path::new(&suggestions.file_path)).ok_or_else(|| {
+            AgentError::FileRead(format!(
+                "No .lrc file found beside {}",
+                suggestions.file_path
+            ))
+        })?
+    } else {
+        lrc.into()
+    };
+
+    let lyrics = Lyrics::load(&lrc_path)?;
+    let frames = if lyrics.is_synced() {
+        "SYLT + USLT"
+    } else {
+        "USLT"
+    };
+    println!(
+        "\n🎤 Embedding {} ({}) as {}",
+        lrc_path.display(),
+        lyrics.info("lyrics"),
+        frames
+    );
+    Ok(Some(lyrics))
+}
+
+/// Write the applicable suggestions to a NEW copy of the original file and record the
+/// copy in the suggestions file
+fn write_updated_file(
+    suggestions: &mut SuggestionsReport,
+    suggestions_file: &str,
+    options: &ApplyOptions,
+) -> Result<()> {
+    let applicable = suggestions.applicable_suggestions();
 
     println!("\n📋 Suggestions to apply:");
-    for (i, suggestion) in suggestions.suggestions.iter().enumerate() {
+    for (i, suggestion) in applicable.iter().enumerate() {
         println!(
             "  {}. {} → {}",
             i + 1,
             suggestion.field,
-            suggestion.suggested_value
+            suggestion.display_value()
         );
     }
 
-    if suggestions.suggestions.is_empty() {
+    if applicable.is_empty() && !options.changes_file() {
         println!("\n✅ No suggestions to apply!");
         return Ok(());
     }
@@ -105,13 +1258,149 @@ fn apply_suggestions_mode(suggestions_file: &str) -> Result<()> {
     let updated_metadata = suggestions.apply_suggestions();
 
     // Write to NEW file (never overwrites original)
-    println!("\n✍️  Writing updated metadata to NEW file...");
-    let output_file = writer::write_metadata_safely(&suggestions.file_path, &updated_metadata)?;
+    let tag_format = AudioFormat::from_path(Path::new(&suggestions.file_path))
+        .map(|f| f.backend().name())
+        .unwrap_or("metadata");
+    match options.in_place {
+        Some(_) => println!("\n✍️  Writing updated {} tags in place...", tag_format),
+        None => println!("\n✍️  Writing updated {} tags to NEW file...", tag_format),
+    }
+    let write_options = WriteOptions {
+        consolidate: options.consolidate,
+        cover_art: load_cover_art(suggestions, options.embed_art.as_deref())?,
+        lyrics: load_lyrics(suggestions, options.embed_lyrics.as_deref())?,
+        remove_fields: suggestions.removed_fields(),
+        id3_version: options.id3_version,
+        in_place: options.in_place.clone(),
+    };
+    // Taken before writing, since an in-place write replaces the original tags
+    let before = options
+        .diff
+        .then(|| TagSnapshot::read(&suggestions.file_path))
+        .transpose()?;
+    let written =
+        writer::write_metadata_safely(&suggestions.file_path, &updated_metadata, &write_options)?;
+    println!(
+        "🔐 Audio data verified unchanged (SHA-256 {}…)",
+        &written.audio_hash[..16]
+    );
+    if let Some(ref expected) = suggestions.audio_hash {
+        if *expected != written.audio_hash {
+            println!(
+                "⚠️  The audio of {} has changed since these suggestions were made",
+                suggestions.file_path
+            );
+        }
+    }
+    suggestions.record_applied(&written.path, &written.audio_hash);
+    suggestions.save_to_path(suggestions_file)?;
+
+    if let Some(version) = options.id3_version.filter(|_| tag_format == "ID3v2") {
+        println!("🔄 Saved the tag as {}", version);
+    }
+
+    if options.consolidate {
+        let legacy: Vec<&str> = updated_metadata
+            .tag_types
+            .iter()
+            .map(|t| t.as_str())
+            .filter(|t| matches!(*t, "ID3v1" | "APEv2"))
+            .collect();
+        if !legacy.is_empty() {
+            println!(
+                "🧹 Merged {} into the ID3v2 tag and stripped the legacy tags",
+                legacy.join(" and ")
+            );
+        }
+    }
+
+    // Removed fields only leave the primary tag; old ID3v1/APEv2 copies still show through
+    let legacy_tags = updated_metadata
+        .tag_types
+        .iter()
+        .any(|t| matches!(t.as_str(), "ID3v1" | "APEv2"));
+    if !write_options.remove_fields.is_empty() && legacy_tags && !options.consolidate {
+        println!(
+            "⚠️  Removed {} from the ID3v2 tag only - add --consolidate to strip the ID3v1/APEv2 copies too",
+            write_options.remove_fields.join(", ")
+        );
+    }
 
     println!("\n✅ SUCCESS!");
-    println!("   Original file: {} (unchanged)", suggestions.file_path);
-    println!("   Updated file:  {}", output_file);
-    println!("\n💡 Compare the files and keep the one you prefer!");
+    match options.in_place {
+        Some(ref in_place) => {
+            println!("   Updated file:  {} (in place)", written.path);
+            print_undo_hint(in_place);
+        }
+        None => {
+            println!("   Original file: {} (unchanged)", suggestions.file_path);
+            println!("   Updated file:  {}", written.path);
+            println!("\n💡 Compare the files and keep the one you prefer:");
+            println!(
+                "   cargo run --release -- diff \"{}\" \"{}\"",
+                suggestions.file_path, written.path
+            );
+        }
+    }
+
+    if let Some(before) = before {
+        println!("\n📊 Tag changes:");
+        print_tag_diff(&before.diff(&TagSnapshot::read(&written.path)?));
+    }
+
+    Ok(())
+}
+
+/// Record reviewer decisions for a suggestions file, or list the decision log
+fn feedback_mode(
+    feedback_file: &str,
+    suggestions_file: Option<&str>,
+    accept: &[usize],
+    reject: &[usize],
+) -> Result<()> {
+    let mut store = FeedbackStore::load(feedback_file)?;
+
+    let Some(suggestions_file) = suggestions_file else {
+        println!(
+            "📚 {} recorded decision(s) in {}\n",
+            store.decisions().len(),
+            store.path()
+        );
+        for decision in store.decisions() {
+            println!("  {}  ({})", decision.to_prompt_line(), decision.file_path);
+        }
+        return Ok(());
+    };
+
+    let report = SuggestionsReport::load_from_file(suggestions_file)?;
+    let mut decisions = Vec::new();
+    for (numbers, accepted) in [(accept, true), (reject, false)] {
+        for &n in numbers {
+            let suggestion = n
+                .checked_sub(1)
+                .and_then(|i| report.suggestions.get(i))
+                .ok_or_else(|| {
+                    AgentError::MetadataParse(format!(
+                        "No suggestion #{} in {} ({} available)",
+                        n,
+                        suggestions_file,
+                        report.suggestions.len()
+                    ))
+                })?;
+            decisions.push(FeedbackDecision::new(
+                &report.current_metadata,
+                suggestion,
+                accepted,
+            ));
+        }
+    }
+
+    for decision in &decisions {
+        println!("  {}", decision.to_prompt_line());
+    }
+    let count = decisions.len();
+    store.record(decisions)?;
+    println!("\n📚 Recorded {} decision(s) to {}", count, store.path());
 
     Ok(())
 }
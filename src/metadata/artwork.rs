@@ -0,0 +1,289 @@
+use crate::error::{AgentError, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Art larger than this is flagged (many players and devices choke on big images)
+pub const MAX_ART_BYTES: usize = 1024 * 1024;
+/// Art wider or taller than this is flagged
+pub const MAX_ART_DIMENSION: u32 = 3000;
+
+/// Image files picked up beside a track, in order of preference (case-insensitive)
+const SIDECAR_NAMES: &[&str] = &[
+    "cover.jpg",
+    "cover.jpeg",
+    "cover.png",
+    "folder.jpg",
+    "folder.jpeg",
+    "folder.png",
+    "front.jpg",
+    "front.png",
+];
+
+/// Summary of an embedded picture (the image bytes themselves are not kept)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PictureInfo {
+    /// e.g. "Front cover"
+    pub picture_type: String,
+    pub mime_type: String,
+    #[serde(default)]
+    pub description: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub size_bytes: usize,
+    /// Short SHA-256 of the image data, used to spot duplicates
+    pub hash: String,
+}
+
+impl PictureInfo {
+    pub fn new(picture_type: &str, mime_type: &str, description: &str, data: &[u8]) -> Self {
+        let (width, height) = match image_dimensions(data) {
+            Some((w, h)) => (Some(w), Some(h)),
+            None => (None, None),
+        };
+        Self {
+            picture_type: picture_type.to_string(),
+            mime_type: mime_type.to_string(),
+            description: description.to_string(),
+            width,
+            height,
+            size_bytes: data.len(),
+            hash: short_hash(data),
+        }
+    }
+}
+
+impl fmt::Display for PictureInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.picture_type, self.mime_type)?;
+        if let (Some(w), Some(h)) = (self.width, self.height) {
+            write!(f, " {}x{}", w, h)?;
+        }
+        write!(f, ", {} KB", self.size_bytes.div_ceil(1024))
+    }
+}
+
+/// An image to embed as the front cover
+#[derive(Debug, Clone)]
+pub struct CoverArt {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl CoverArt {
+    /// Load an image file, detecting its type from the content
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).map_err(|e| {
+            AgentError::FileRead(format!("Failed to read image {}: {}", path.display(), e))
+        })?;
+        let mime_type = detect_mime(&data).ok_or_else(|| {
+            AgentError::FileRead(format!("Not a JPEG, PNG or GIF image: {}", path.display()))
+        })?;
+        Ok(Self {
+            mime_type: mime_type.to_string(),
+            data,
+        })
+    }
+
+    /// Look for cover.jpg / folder.png etc. in the track's directory
+    pub fn find_beside(track: &Path) -> Option<PathBuf> {
+        let dir = track.parent().filter(|p| !p.as_os_str().is_empty())?;
+        let entries: Vec<PathBuf> = fs::read_dir(dir)
+            .ok()?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+
+        SIDECAR_NAMES.iter().find_map(|name| {
+            entries
+                .iter()
+                .find(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.eq_ignore_ascii_case(name))
+                })
+                .cloned()
+        })
+    }
+
+    pub fn info(&self) -> PictureInfo {
+        PictureInfo::new("Front cover", &self.mime_type, "", &self.data)
+    }
+}
+
+/// Problems worth fixing: oversized, non-square, unreadable or duplicate pictures
+pub fn picture_warnings(pictures: &[PictureInfo]) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    for (i, picture) in pictures.iter().enumerate() {
+        let label = &picture.picture_type;
+        if picture.size_bytes > MAX_ART_BYTES {
+            warnings.push(format!(
+                "{} is {:.1} MB (over {} MB)",
+                label,
+                picture.size_bytes as f64 / (1024.0 * 1024.0),
+                MAX_ART_BYTES / (1024 * 1024)
+            ));
+        }
+
+        match (picture.width, picture.height) {
+            (Some(w), Some(h)) => {
+                if w.max(h) > MAX_ART_DIMENSION {
+                    warnings.push(format!(
+                        "{} is {}x{} (over {}px)",
+                        label, w, h, MAX_ART_DIMENSION
+                    ));
+                }
+                // Allow a 1% difference for scans cropped slightly off
+                if w.abs_diff(h) * 100 > w.max(h) {
+                    warnings.push(format!("{} is not square ({}x{})", label, w, h));
+                }
+            }
+            _ => warnings.push(format!("{} has unrecognized image data", label)),
+        }
+
+        if let Some(original) = pictures[..i].iter().find(|p| p.hash == picture.hash) {
+            warnings.push(format!(
+                "{} is a duplicate of the {} picture",
+                label,
+                original.picture_type.to_lowercase()
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Write extracted image data to `dir` as "<stem> - <picture type>.<ext>"
+pub fn save_picture(dir: &Path, stem: &str, info: &PictureInfo, data: &[u8]) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+
+    let kind = info.picture_type.to_lowercase().replace(' ', "-");
+    let extension = extension_for(&info.mime_type);
+    let mut path = dir.join(format!("{} - {}.{}", stem, kind, extension));
+    let mut counter = 1;
+    while path.exists() {
+        path = dir.join(format!("{} - {}-{}.{}", stem, kind, counter, extension));
+        counter += 1;
+    }
+
+    fs::write(&path, data)
+        .map_err(|e| AgentError::FileRead(format!("Failed to write {}: {}", path.display(), e)))?;
+    Ok(path)
+}
+
+/// File extension for a MIME type
+fn extension_for(mime_type: &str) -> &'static str {
+    match mime_type.to_lowercase().as_str() {
+        "image/png" => "png",
+        "image/gif" => "gif",
+        _ => "jpg",
+    }
+}
+
+pub fn detect_mime(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"GIF8") {
+        Some("image/gif")
+    } else {
+        None
+    }
+}
+
+/// Width and height of a JPEG, PNG or GIF image
+pub fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    match detect_mime(data)? {
+        "image/png" => {
+            let be = |at: usize| {
+                data.get(at..at + 4)
+                    .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+            };
+            Some((be(16)?, be(20)?))
+        }
+        "image/gif" => {
+            let le = |at: usize| {
+                data.get(at..at + 2)
+                    .map(|b| u32::from(u16::from_le_bytes([b[0], b[1]])))
+            };
+            Some((le(6)?, le(8)?))
+        }
+        _ => jpeg_dimensions(data),
+    }
+}
+
+/// Walk JPEG segments to the first start-of-frame marker
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let mut offset = 2;
+    while offset + 4 <= data.len() {
+        if data[offset] != 0xff {
+            return None;
+        }
+        let marker = data[offset + 1];
+        // Fill bytes and standalone markers have no length
+        if marker == 0xff {
+            offset += 1;
+            continue;
+        }
+        if (0xd0..=0xd9).contains(&marker) || marker == 0x01 {
+            offset += 2;
+            continue;
+        }
+
+        let len = usize::from(u16::from_be_bytes([data[offset + 2], data[offset + 3]]));
+        let is_sof = (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc);
+        if is_sof {
+            let segment = data.get(offset + 4..offset + 9)?;
+            let height = u32::from(u16::from_be_bytes([segment[1], segment[2]]));
+            let width = u32::from(u16::from_be_bytes([segment[3], segment[4]]));
+            return Some((width, height));
+        }
+        offset += 2 + len;
+    }
+    None
+}
+
+/// First 8 bytes of the SHA-256 of `data`, in hex
+pub fn short_hash(data: &[u8]) -> String {
+    Sha256::digest(data)[..8]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn test_dimensions_and_warnings() {
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00];
+        jpeg.extend_from_slice(&[0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0xf4, 0x02, 0x58]);
+        assert_eq!(image_dimensions(&jpeg), Some((600, 500)));
+
+        let front = PictureInfo::new("Front cover", "image/png", "", &png(4000, 4000));
+        let back = PictureInfo::new("Back cover", "image/png", "", &png(4000, 4000));
+        let jpeg = PictureInfo::new("Media", "image/jpeg", "", &jpeg);
+
+        let warnings = picture_warnings(&[front, back, jpeg]);
+        assert_eq!(
+            warnings,
+            vec![
+                "Front cover is 4000x4000 (over 3000px)",
+                "Back cover is 4000x4000 (over 3000px)",
+                "Back cover is a duplicate of the front cover picture",
+                "Media is not square (600x500)",
+            ]
+        );
+    }
+}
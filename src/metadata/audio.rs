@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Properties of the encoded audio stream (not tags)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioProperties {
+    /// e.g. "MPEG-1 Layer III"
+    pub codec: String,
+    pub duration_ms: u64,
+    /// Average bitrate for VBR streams
+    pub bitrate_kbps: u32,
+    pub sample_rate: u32,
+    pub channel_mode: ChannelMode,
+    pub vbr: bool,
+    /// Encoder string from the LAME/Xing header (e.g. "LAME3.100")
+    pub encoder: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelMode {
+    Stereo,
+    JointStereo,
+    DualChannel,
+    Mono,
+}
+
+impl AudioProperties {
+    pub fn duration_seconds(&self) -> u32 {
+        (self.duration_ms / 1000) as u32
+    }
+}
+
+impl fmt::Display for ChannelMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Stereo => "Stereo",
+            Self::JointStereo => "Joint Stereo",
+            Self::DualChannel => "Dual Channel",
+            Self::Mono => "Mono",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for AudioProperties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {} kbps {}, {} Hz, {}",
+            self.codec,
+            self.bitrate_kbps,
+            if self.vbr { "VBR" } else { "CBR" },
+            self.sample_rate,
+            self.channel_mode
+        )?;
+        if let Some(ref encoder) = self.encoder {
+            write!(f, ", encoder {}", encoder)?;
+        }
+        Ok(())
+    }
+}
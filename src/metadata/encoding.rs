@@ -0,0 +1,229 @@
+use crate::metadata::{TrackMetadata, CUSTOM_PREFIX, FIELDS, PEOPLE_PREFIX};
+use encoding_rs::{SHIFT_JIS, WINDOWS_1251, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Encodings that old taggers wrote into frames declared as ISO-8859-1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceEncoding {
+    #[serde(rename = "UTF-8")]
+    Utf8,
+    #[serde(rename = "Shift-JIS")]
+    ShiftJis,
+    #[serde(rename = "Windows-1251")]
+    Windows1251,
+}
+
+impl fmt::Display for SourceEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SourceEncoding::Utf8 => "UTF-8",
+            SourceEncoding::ShiftJis => "Shift-JIS",
+            SourceEncoding::Windows1251 => "Windows-1251",
+        })
+    }
+}
+
+/// A field whose text looks like bytes of another encoding read as ISO-8859-1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncodingIssue {
+    pub field: String,
+    pub value: String,
+    pub repaired: String,
+    pub source: SourceEncoding,
+}
+
+impl fmt::Display for EncodingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} \"{}\" looks like {} read as ISO-8859-1 (\"{}\")",
+            self.field, self.value, self.source, self.repaired
+        )
+    }
+}
+
+/// The text `value` was most likely meant to be, and the encoding it was written in
+///
+/// Only text made entirely of ISO-8859-1 (or Windows-1252) characters can be mojibake.
+/// UTF-8 is decoded strictly, so ordinary accents like "Beyoncé" never qualify;
+/// Shift-JIS and Windows-1251 only count when the result is plausible Japanese or
+/// Russian text, with no Latin letters mixed into the repaired words.
+pub fn repair(value: &str) -> Option<(String, SourceEncoding)> {
+    let bytes = latin1_bytes(value)?;
+    if bytes.is_ascii() {
+        return None;
+    }
+
+    if let Ok(text) = std::str::from_utf8(&bytes) {
+        return Some((text.to_string(), SourceEncoding::Utf8));
+    }
+
+    // Cyrillic letters pair up into valid kanji, so kana decides between the two
+    let japanese = shift_jis(&bytes);
+    if let Some(text) = japanese.as_ref().filter(|t| t.chars().any(is_kana)) {
+        return Some((text.clone(), SourceEncoding::ShiftJis));
+    }
+    if let Some(text) = windows_1251(&bytes) {
+        return Some((text, SourceEncoding::Windows1251));
+    }
+    japanese.map(|text| (text, SourceEncoding::ShiftJis))
+}
+
+/// Likely mis-encoded text in the tag fields, custom tags and credits
+pub fn find_issues(metadata: &TrackMetadata) -> Vec<EncodingIssue> {
+    let fields = FIELDS
+        .iter()
+        .map(|f| f.to_string())
+        .chain(
+            metadata
+                .custom
+                .keys()
+                .map(|k| format!("{}{}", CUSTOM_PREFIX, k)),
+        )
+        .chain(
+            metadata
+                .people
+                .keys()
+                .map(|r| format!("{}{}", PEOPLE_PREFIX, r)),
+        );
+
+    fields
+        .filter_map(|field| {
+            let value = metadata.field_value(&field)?;
+            let (repaired, source) = repair(&value)?;
+            Some(EncodingIssue {
+                field,
+                value,
+                repaired,
+                source,
+            })
+        })
+        .collect()
+}
+
+/// The bytes behind text decoded as ISO-8859-1, or as Windows-1252 for "â€™" and the like
+fn latin1_bytes(value: &str) -> Option<Vec<u8>> {
+    value
+        .chars()
+        .map(|c| match u8::try_from(c) {
+            Ok(byte) => Some(byte),
+            Err(_) => {
+                let mut buffer = [0; 4];
+                let (bytes, _, unmappable) = WINDOWS_1252.encode(c.encode_utf8(&mut buffer));
+                (!unmappable && bytes.len() == 1).then(|| bytes[0])
+            }
+        })
+        .collect()
+}
+
+fn shift_jis(bytes: &[u8]) -> Option<String> {
+    let text = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(bytes)?;
+    let plausible = text.chars().any(|c| is_kana(c) || is_kanji(c))
+        && !text.chars().any(|c| {
+            // Half-width katakana and user-defined characters mean Latin-1 bytes
+            // that happen to decode
+            ('\u{FF61}'..='\u{FF9F}').contains(&c) || ('\u{E000}'..='\u{F8FF}').contains(&c)
+        });
+    plausible.then(|| text.into_owned())
+}
+
+fn windows_1251(bytes: &[u8]) -> Option<String> {
+    let text = WINDOWS_1251.decode_without_bom_handling_and_without_replacement(bytes)?;
+    // Every word that had high bytes must now be Cyrillic through and through
+    let plausible = bytes
+        .split(|b| b.is_ascii_whitespace())
+        .filter(|word| !word.is_ascii())
+        .all(|word| {
+            word.iter().all(|&b| {
+                if b.is_ascii() {
+                    !b.is_ascii_alphabetic()
+                } else {
+                    WINDOWS_1251
+                        .decode_without_bom_handling(&[b])
+                        .0
+                        .chars()
+                        .all(is_cyrillic)
+                }
+            })
+        });
+    plausible.then(|| text.into_owned())
+}
+
+fn is_kana(c: char) -> bool {
+    ('\u{3041}'..='\u{30FF}').contains(&c)
+}
+
+fn is_kanji(c: char) -> bool {
+    ('\u{4E00}'..='\u{9FFF}').contains(&c)
+}
+
+fn is_cyrillic(c: char) -> bool {
+    ('\u{0400}'..='\u{04FF}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// How a tagger that assumed ISO-8859-1 displays bytes of another encoding
+    fn as_latin1(bytes: &[u8]) -> String {
+        bytes.iter().map(|&b| b as char).collect()
+    }
+
+    #[test]
+    fn test_repair_mojibake() {
+        let utf8 = as_latin1("Björk".as_bytes());
+        assert_eq!(utf8, "BjÃ¶rk");
+        assert_eq!(
+            repair(&utf8),
+            Some(("Björk".to_string(), SourceEncoding::Utf8))
+        );
+        assert_eq!(
+            repair("Donâ€™t Stop"),
+            Some(("Don’t Stop".to_string(), SourceEncoding::Utf8))
+        );
+
+        let (bytes, _, _) = WINDOWS_1251.encode("Кино - Группа крови");
+        assert_eq!(
+            repair(&as_latin1(&bytes)),
+            Some((
+                "Кино - Группа крови".to_string(),
+                SourceEncoding::Windows1251
+            ))
+        );
+
+        for japanese in ["ひとりぼっちの夜", "宇多田"] {
+            let (bytes, _, _) = SHIFT_JIS.encode(japanese);
+            assert_eq!(
+                repair(&as_latin1(&bytes)),
+                Some((japanese.to_string(), SourceEncoding::ShiftJis))
+            );
+        }
+    }
+
+    #[test]
+    fn test_correct_text_left_alone() {
+        for value in [
+            "Beyoncé",
+            "Sigur Rós",
+            "Mötley Crüe",
+            "Émilie Simon",
+            "Café del Mar",
+            "Björk",
+            "Кино",
+            "Plain ASCII",
+        ] {
+            assert_eq!(repair(value), None, "{}", value);
+        }
+
+        let mut metadata = TrackMetadata::default();
+        metadata.set_field("artist", "BjÃ¶rk");
+        metadata.set_field("title", "Jóga");
+        metadata.set_field("txxx:Label", "Ã‰ditions");
+        let issues = find_issues(&metadata);
+        let fields: Vec<&str> = issues.iter().map(|i| i.field.as_str()).collect();
+        assert_eq!(fields, ["artist", "txxx:Label"]);
+        assert_eq!(issues[1].repaired, "Éditions");
+    }
+}
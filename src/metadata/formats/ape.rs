@@ -0,0 +1,201 @@
+use crate::metadata::{parse_bool, TrackMetadata};
+
+const PREAMBLE: &[u8; 8] = b"APETAGEX";
+const FOOTER_LEN: usize = 32;
+const ID3V1_LEN: usize = 128;
+const FLAG_HAS_HEADER: u32 = 1 << 31;
+/// Item flag bits 1-2: 0 means UTF-8 text
+const ITEM_TYPE_MASK: u32 = 0b110;
+
+/// APE item keys for each field (matched case-insensitively)
+const FIELD_KEYS: &[(&str, &[&str])] = &[
+    ("artist", &["Artist"]),
+    ("title", &["Title"]),
+    ("album", &["Album"]),
+    ("year", &["Year"]),
+    ("genre", &["Genre"]),
+    ("track_number", &["Track"]),
+    ("disc_number", &["Disc"]),
+    ("album_artist", &["Album Artist", "AlbumArtist"]),
+    ("composer", &["Composer"]),
+    ("comment", &["Comment"]),
+    ("bpm", &["BPM"]),
+    ("publisher", &["Label", "Publisher"]),
+    ("isrc", &["ISRC"]),
+    ("compilation", &["Compilation"]),
+    ("artist_sort", &["ArtistSort"]),
+    ("album_sort", &["AlbumSort"]),
+];
+
+/// An APEv2 tag found at the end of a file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApeTag {
+    /// Text items; binary items (e.g. cover art) are skipped
+    pub items: Vec<(String, String)>,
+    /// Byte range of the whole tag, header included
+    pub range: std::ops::Range<usize>,
+}
+
+impl ApeTag {
+    /// Find and parse an APE tag at the end of the file or right before an ID3v1 trailer
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let has_id3v1 = data.len() >= ID3V1_LEN && &data[data.len() - ID3V1_LEN..][..3] == b"TAG";
+        let mut candidates = vec![data.len()];
+        if has_id3v1 {
+            candidates.insert(0, data.len() - ID3V1_LEN);
+        }
+
+        candidates
+            .into_iter()
+            .find_map(|end| Self::parse_before(data, end))
+    }
+
+    fn parse_before(data: &[u8], end: usize) -> Option<Self> {
+        let footer = data.get(end.checked_sub(FOOTER_LEN)?..end)?;
+        if &footer[..8] != PREAMBLE {
+            return None;
+        }
+
+        let size = le_u32(footer, 12)? as usize;
+        let count = le_u32(footer, 16)?;
+        let flags = le_u32(footer, 20)?;
+        let items_start = end.checked_sub(size)?;
+        let header_len = if flags & FLAG_HAS_HEADER != 0 { 32 } else { 0 };
+        let start = items_start.checked_sub(header_len)?;
+
+        let body = &data[items_start..end - FOOTER_LEN];
+        let mut items = Vec::new();
+        let mut offset = 0;
+        for _ in 0..count {
+            let value_len = le_u32(body, offset)? as usize;
+            let item_flags = le_u32(body, offset + 4)?;
+            let key_start = offset + 8;
+            let key_len = body.get(key_start..)?.iter().position(|&b| b == 0)?;
+            let key = String::from_utf8_lossy(&body[key_start..key_start + key_len]).to_string();
+            let value_start = key_start + key_len + 1;
+            let value = body.get(value_start..value_start + value_len)?;
+            if item_flags & ITEM_TYPE_MASK == 0 {
+                items.push((key, String::from_utf8_lossy(value).to_string()));
+            }
+            offset = value_start + value_len;
+        }
+
+        Some(Self {
+            items,
+            range: start..end,
+        })
+    }
+
+    fn field(&self, field: &str) -> Option<String> {
+        // Multiple values are separated by NUL; keep the first
+        self.values(field).into_iter().next()
+    }
+
+    /// Every value of the first item stored for the field
+    fn values(&self, field: &str) -> Vec<String> {
+        let keys = FIELD_KEYS
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, keys)| *keys)
+            .unwrap_or(&[]);
+        keys.iter()
+            .find_map(|key| self.items.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)))
+            .map(|(_, v)| {
+                v.split('\0')
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Map the text items onto TrackMetadata
+    pub fn to_metadata(&self) -> TrackMetadata {
+        let (track_number, total_tracks) = split_pair(self.field("track_number"));
+        let (disc_number, total_discs) = split_pair(self.field("disc_number"));
+
+        TrackMetadata {
+            artists: self.values("artist"),
+            title: self.field("title"),
+            album: self.field("album"),
+            year: self
+                .field("year")
+                .and_then(|y| y.get(..4).and_then(|y| y.parse().ok())),
+            genres: self.values("genre"),
+            track_number,
+            album_artist: self.field("album_artist"),
+            total_tracks,
+            disc_number,
+            total_discs,
+            composer: self.field("composer"),
+            comment: self.field("comment"),
+            bpm: self
+                .field("bpm")
+                .and_then(|b| b.parse::<f32>().ok())
+                .map(|b| b.round() as u32),
+            publisher: self.field("publisher"),
+            isrc: self.field("isrc"),
+            compilation: self.field("compilation").and_then(|c| parse_bool(&c)),
+            artist_sort: self.field("artist_sort"),
+            album_sort: self.field("album_sort"),
+            ..Default::default()
+        }
+    }
+}
+
+fn split_pair(value: Option<String>) -> (Option<u32>, Option<u32>) {
+    let Some(value) = value else {
+        return (None, None);
+    };
+    match value.split_once('/') {
+        Some((number, total)) => (number.trim().parse().ok(), total.trim().parse().ok()),
+        None => (value.parse().ok(), None),
+    }
+}
+
+fn le_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(key: &str, value: &str) -> Vec<u8> {
+        let mut out = (value.len() as u32).to_le_bytes().to_vec();
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(key.as_bytes());
+        out.push(0);
+        out.extend_from_slice(value.as_bytes());
+        out
+    }
+
+    #[test]
+    fn test_ape_tag_before_id3v1() {
+        let mut items = item("Artist", "Boards of Canada\0Christ.");
+        items.extend(item("Track", "4/17"));
+
+        let mut footer = PREAMBLE.to_vec();
+        footer.extend_from_slice(&2000u32.to_le_bytes());
+        footer.extend_from_slice(&((items.len() + FOOTER_LEN) as u32).to_le_bytes());
+        footer.extend_from_slice(&2u32.to_le_bytes());
+        footer.extend_from_slice(&0u32.to_le_bytes());
+        footer.extend_from_slice(&[0; 8]);
+
+        let mut data = b"\xff\xfbAUDIO".to_vec();
+        data.extend_from_slice(&items);
+        data.extend_from_slice(&footer);
+        let mut id3v1 = b"TAG".to_vec();
+        id3v1.resize(ID3V1_LEN, 0);
+        data.extend_from_slice(&id3v1);
+
+        let tag = ApeTag::read(&data).unwrap();
+        assert_eq!(tag.range, 7..data.len() - ID3V1_LEN);
+
+        let metadata = tag.to_metadata();
+        assert_eq!(metadata.artists, vec!["Boards of Canada", "Christ."]);
+        assert_eq!(metadata.track_number, Some(4));
+        assert_eq!(metadata.total_tracks, Some(17));
+    }
+}
@@ -0,0 +1,268 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::formats::vorbis::VorbisComments;
+use crate::metadata::formats::{id3v2_size, TagBackend};
+use crate::metadata::TrackMetadata;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+const FLAC_MARKER: &[u8; 4] = b"fLaC";
+const BLOCK_STREAMINFO: u8 = 0;
+const BLOCK_PADDING: u8 = 1;
+const BLOCK_VORBIS_COMMENT: u8 = 4;
+
+/// Vorbis comments in native FLAC files
+pub struct FlacBackend;
+
+impl TagBackend for FlacBackend {
+    fn name(&self) -> &str {
+        "Vorbis comment (FLAC)"
+    }
+
+    fn read(&self, path: &Path) -> Result<TrackMetadata> {
+        let data = fs::read(path)?;
+        let file = FlacFile::parse(&data)
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+
+        let comments = file.comments()?;
+        let mut metadata = comments
+            .as_ref()
+            .map(|c| c.to_metadata(&path.to_string_lossy()))
+            .unwrap_or_default();
+        if comments.is_some() {
+            metadata.tag_types.push("Vorbis comment".to_string());
+        }
+        metadata.duration_seconds = file.duration_seconds();
+        Ok(metadata)
+    }
+
+    fn write(&self, path: &Path, metadata: &TrackMetadata) -> Result<()> {
+        let data = fs::read(path)?;
+        let mut file = FlacFile::parse(&data)
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+
+        let mut comments = file.comments()?.unwrap_or_else(|| VorbisComments {
+            vendor: "music-agent".to_string(),
+            comments: Vec::new(),
+        });
+        comments.apply_metadata(metadata);
+        file.set_comments(&comments);
+
+        fs::write(path, file.to_bytes(&data))?;
+        Ok(())
+    }
+
+    fn remove_fields(&self, path: &Path, fields: &[String]) -> Result<()> {
+        let data = fs::read(path)?;
+        let mut file = FlacFile::parse(&data)
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+
+        let Some(mut comments) = file.comments()? else {
+            return Ok(());
+        };
+        comments.remove_fields(fields);
+        file.set_comments(&comments);
+
+        fs::write(path, file.to_bytes(&data))?;
+        Ok(())
+    }
+
+    /// Everything after the metadata blocks
+    fn audio_ranges(&self, data: &[u8]) -> std::result::Result<Vec<Range<usize>>, String> {
+        let file = FlacFile::parse(data)?;
+        Ok(std::iter::once(file.audio_offset..data.len()).collect())
+    }
+}
+
+/// A metadata block: type and raw body
+#[derive(Debug, Clone)]
+struct MetadataBlock {
+    block_type: u8,
+    data: Vec<u8>,
+}
+
+/// Parsed FLAC header: metadata blocks plus where the audio frames start
+#[derive(Debug)]
+struct FlacFile {
+    /// Bytes before `fLaC` (a stray ID3v2 tag some taggers prepend)
+    prefix_len: usize,
+    blocks: Vec<MetadataBlock>,
+    audio_offset: usize,
+}
+
+impl FlacFile {
+    fn parse(data: &[u8]) -> std::result::Result<Self, String> {
+        let prefix_len = id3v2_size(data);
+        if data.get(prefix_len..prefix_len + 4) != Some(FLAC_MARKER) {
+            return Err("not a FLAC stream (missing fLaC marker)".to_string());
+        }
+
+        let mut blocks = Vec::new();
+        let mut offset = prefix_len + 4;
+        loop {
+            let header = data
+                .get(offset..offset + 4)
+                .ok_or("truncated metadata block header")?;
+            let is_last = header[0] & 0x80 != 0;
+            let block_type = header[0] & 0x7f;
+            let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+            offset += 4;
+
+            let body = data
+                .get(offset..offset + len)
+                .ok_or("truncated metadata block")?;
+            blocks.push(MetadataBlock {
+                block_type,
+                data: body.to_vec(),
+            });
+            offset += len;
+
+            if is_last {
+                break;
+            }
+        }
+
+        if blocks.first().map(|b| b.block_type) != Some(BLOCK_STREAMINFO) {
+            return Err("first metadata block is not STREAMINFO".to_string());
+        }
+
+        Ok(Self {
+            prefix_len,
+            blocks,
+            audio_offset: offset,
+        })
+    }
+
+    fn comments(&self) -> Result<Option<VorbisComments>> {
+        self.blocks
+            .iter()
+            .find(|b| b.block_type == BLOCK_VORBIS_COMMENT)
+            .map(|b| VorbisComments::parse(&b.data))
+            .transpose()
+    }
+
+    fn set_comments(&mut self, comments: &VorbisComments) {
+        let block = MetadataBlock {
+            block_type: BLOCK_VORBIS_COMMENT,
+            data: comments.to_bytes(),
+        };
+        match self
+            .blocks
+            .iter_mut()
+            .find(|b| b.block_type == BLOCK_VORBIS_COMMENT)
+        {
+            Some(existing) => *existing = block,
+            // Place new comments right after STREAMINFO
+            None => self.blocks.insert(1, block),
+        }
+    }
+
+    /// Duration from STREAMINFO's sample rate and total sample count
+    fn duration_seconds(&self) -> Option<u32> {
+        let info = &self.blocks.first()?.data;
+        if info.len() < 18 {
+            return None;
+        }
+        let sample_rate =
+            (u32::from(info[10]) << 12) | (u32::from(info[11]) << 4) | (u32::from(info[12]) >> 4);
+        let total_samples = (u64::from(info[13] & 0x0f) << 32)
+            | u64::from(u32::from_be_bytes([info[14], info[15], info[16], info[17]]));
+        if sample_rate == 0 || total_samples == 0 {
+            return None;
+        }
+        Some((total_samples / u64::from(sample_rate)) as u32)
+    }
+
+    /// Serialize the metadata blocks followed by the untouched audio frames
+    ///
+    /// If a padding block exists it is resized so the audio keeps its offset when the
+    /// new comments fit; otherwise the padding is dropped and the audio shifts.
+    fn to_bytes(&self, original: &[u8]) -> Vec<u8> {
+        let original_header_len = self.audio_offset - self.prefix_len;
+        let mut blocks: Vec<MetadataBlock> = self
+            .blocks
+            .iter()
+            .filter(|b| b.block_type != BLOCK_PADDING)
+            .cloned()
+            .collect();
+
+        let used: usize = 4 + blocks.iter().map(|b| 4 + b.data.len()).sum::<usize>();
+        let had_padding = self.blocks.iter().any(|b| b.block_type == BLOCK_PADDING);
+        if had_padding && used + 4 <= original_header_len {
+            blocks.push(MetadataBlock {
+                block_type: BLOCK_PADDING,
+                data: vec![0; original_header_len - used - 4],
+            });
+        }
+
+        let mut out = Vec::with_capacity(original.len());
+        out.extend_from_slice(&original[..self.prefix_len]);
+        out.extend_from_slice(FLAC_MARKER);
+        for (i, block) in blocks.iter().enumerate() {
+            let last_flag = if i == blocks.len() - 1 { 0x80 } else { 0 };
+            let len = (block.data.len() as u32).to_be_bytes();
+            out.push(last_flag | block.block_type);
+            out.extend_from_slice(&len[1..]);
+            out.extend_from_slice(&block.data);
+        }
+        out.extend_from_slice(&original[self.audio_offset..]);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal FLAC: STREAMINFO (44.1 kHz, 441000 samples), comments, padding, fake audio
+    fn sample_flac() -> Vec<u8> {
+        let mut info = vec![0u8; 34];
+        info[10] = 0x0a;
+        info[11] = 0xc4;
+        info[12] = 0x42;
+        info[14..18].copy_from_slice(&441000u32.to_be_bytes());
+
+        let comments = VorbisComments {
+            vendor: "test".to_string(),
+            comments: vec![("TITLE".to_string(), "Pennyroyal".to_string())],
+        }
+        .to_bytes();
+
+        let mut data = FLAC_MARKER.to_vec();
+        data.push(BLOCK_STREAMINFO);
+        data.extend_from_slice(&(info.len() as u32).to_be_bytes()[1..]);
+        data.extend_from_slice(&info);
+        data.push(BLOCK_VORBIS_COMMENT);
+        data.extend_from_slice(&(comments.len() as u32).to_be_bytes()[1..]);
+        data.extend_from_slice(&comments);
+        data.push(0x80 | BLOCK_PADDING);
+        data.extend_from_slice(&256u32.to_be_bytes()[1..]);
+        data.extend_from_slice(&[0; 256]);
+        data.extend_from_slice(b"\xff\xf8AUDIOFRAMES");
+        data
+    }
+
+    #[test]
+    fn test_flac_read_write_round_trip() {
+        let path = std::env::temp_dir().join("music-agent-flac-test.flac");
+        let original = sample_flac();
+        fs::write(&path, &original).unwrap();
+
+        let mut metadata = FlacBackend.read(&path).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Pennyroyal"));
+        assert_eq!(metadata.duration_seconds, Some(10));
+
+        metadata.artists = vec!["Joey Bada$$".to_string()];
+        FlacBackend.write(&path, &metadata).unwrap();
+
+        let written = fs::read(&path).unwrap();
+        let reread = FlacBackend.read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reread.artists, vec!["Joey Bada$$"]);
+        assert_eq!(reread.title.as_deref(), Some("Pennyroyal"));
+        // Padding absorbed the growth, so the audio stays at the same offset
+        assert_eq!(written.len(), original.len());
+        assert!(written.ends_with(b"\xff\xf8AUDIOFRAMES"));
+    }
+}
@@ -0,0 +1,397 @@
+use crate::metadata::formats::id3v2_size;
+use crate::metadata::split_values;
+use id3::frame::{Content, InvolvedPeopleList, InvolvedPeopleListItem};
+use id3::{Encoding, Frame, Tag, TagLike, Timestamp, Version};
+use std::collections::BTreeMap;
+
+const FLAG_UNSYNCHRONISATION: u8 = 0x80;
+const FLAG_EXTENDED_HEADER: u8 = 0x40;
+/// v2.3 frame format flags: compression, encryption, grouping
+const V23_ENCODED_FLAGS: u16 = 0x00e0;
+/// v2.4 frame format flags: grouping, compression, encryption, unsync, data length
+const V24_ENCODED_FLAGS: u16 = 0x004f;
+/// Text frames read as lists: artists and genres
+const MULTI_VALUE_FRAMES: &[&str] = &["TPE1", "TCON"];
+/// Roles kept in TIPL; every other role (instruments, vocals, featuring) is a TMCL credit
+const PRODUCTION_ROLES: &[&str] = &[
+    "producer",
+    "co-producer",
+    "engineer",
+    "mix",
+    "mixer",
+    "dj-mix",
+    "arranger",
+    "mastering",
+];
+
+/// A frame as stored in the file: ID and body bytes, header excluded
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawFrame {
+    pub id: String,
+    pub body: Vec<u8>,
+}
+
+/// Walk the frames of a leading ID3v2.3/2.4 tag without decoding them
+///
+/// Returns None when there is no such tag, or when the tag or one of its frames is
+/// unsynchronised, compressed or encrypted, since those bodies can't be copied verbatim.
+pub fn raw_frames(data: &[u8]) -> Option<Vec<RawFrame>> {
+    let end = id3v2_size(data).min(data.len());
+    let major = *data.get(3)?;
+    if end == 0 || !(major == 3 || major == 4) {
+        return None;
+    }
+    let flags = data[5];
+    if flags & FLAG_UNSYNCHRONISATION != 0 {
+        return None;
+    }
+
+    let mut offset = 10;
+    if flags & FLAG_EXTENDED_HEADER != 0 {
+        let size = data.get(10..14)?;
+        offset += match major {
+            // v2.4 counts the size field itself, v2.3 doesn't
+            4 => syncsafe(size),
+            _ => 4 + u32::from_be_bytes(size.try_into().ok()?) as usize,
+        };
+    }
+
+    let mut frames = Vec::new();
+    while offset + 10 <= end {
+        let header = &data[offset..offset + 10];
+        // Padding
+        if header[0] == 0 {
+            break;
+        }
+        let id = String::from_utf8_lossy(&header[..4]).to_string();
+        let size = match major {
+            4 => syncsafe(&header[4..8]),
+            _ => u32::from_be_bytes(header[4..8].try_into().ok()?) as usize,
+        };
+        let format_flags = u16::from_be_bytes([header[8], header[9]]);
+        let encoded = match major {
+            4 => V24_ENCODED_FLAGS,
+            _ => V23_ENCODED_FLAGS,
+        };
+        if format_flags & encoded != 0 {
+            return None;
+        }
+
+        let body = data.get(offset + 10..offset + 10 + size)?;
+        frames.push(RawFrame {
+            id,
+            body: body.to_vec(),
+        });
+        offset += 10 + size;
+    }
+
+    Some(frames)
+}
+
+/// Copy of `tag` with its frames translated for `version`
+///
+/// For v2.3, TDRC becomes TYER/TDAT/TIME, TDOR becomes TORY, TIPL/TMCL merge into IPLS,
+/// null-separated values are joined with "/" and text is re-encoded as UTF-16.
+/// For v2.4 the dates and IPLS are translated back, "/"-separated artists and
+/// genres become separate values (see [`text_values`]) and text is re-encoded as UTF-8.
+/// ISO-8859-1 text never survives a conversion, so repaired mojibake can't be mangled.
+pub fn convert(tag: &Tag, version: Version) -> Tag {
+    let v23 = version != Version::Id3v24;
+    let mut output = Tag::with_version(version);
+    let date = recording_date(tag);
+    let original = original_date(tag);
+
+    for frame in tag.frames() {
+        match frame.id() {
+            // Dates that parse are rebuilt below; anything else is copied as is
+            "TDRC" | "TYER" | "TDAT" | "TIME" if date.is_some() => {}
+            "TDOR" | "TORY" if original.is_some() => {}
+            "IPLS" | "TIPL" | "TMCL" if frame.content().involved_people_list().is_some() => {}
+            id if !v23
+                && tag.version() != Version::Id3v24
+                && MULTI_VALUE_FRAMES.contains(&id)
+                && frame.content().text().is_some() =>
+            {
+                let values = Content::new_text_values(text_values(tag, id));
+                output.add_frame(
+                    Frame::with_content(id, values).set_encoding(unicode(frame.encoding())),
+                );
+            }
+            _ => {
+                output.add_frame(if v23 { to_v23(frame) } else { to_v24(frame) });
+            }
+        }
+    }
+
+    if let Some(date) = date {
+        if v23 {
+            output.set_text("TYER", format!("{:04}", date.year));
+            if let (Some(month), Some(day)) = (date.month, date.day) {
+                output.set_text("TDAT", format!("{:02}{:02}", day, month));
+            }
+            if let (Some(hour), Some(minute)) = (date.hour, date.minute) {
+                output.set_text("TIME", format!("{:02}{:02}", hour, minute));
+            }
+        } else {
+            output.set_date_recorded(date);
+        }
+    }
+    if let Some(original) = original {
+        if v23 {
+            output.set_text("TORY", format!("{:04}", original.year));
+        } else {
+            output.set_text("TDOR", original.to_string());
+        }
+    }
+    set_involved_people(&mut output, &involved_people(tag));
+    output
+}
+
+/// Involved people by lower-case role, from IPLS, TIPL and TMCL
+pub fn involved_people(tag: &Tag) -> BTreeMap<String, Vec<String>> {
+    let mut people: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for id in ["IPLS", "TIPL", "TMCL"] {
+        let lists = tag
+            .frames()
+            .filter(|frame| frame.id() == id)
+            .filter_map(|frame| frame.content().involved_people_list());
+        for item in lists.flat_map(|list| list.items.iter()) {
+            let names = people
+                .entry(item.involvement.trim().to_lowercase())
+                .or_default();
+            for name in split_values(&item.involvee) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+    people
+}
+
+/// Replace the involved-people frames with `people`: one IPLS for v2.3, or TIPL for
+/// production roles and TMCL for the rest in v2.4
+pub fn set_involved_people(tag: &mut Tag, people: &BTreeMap<String, Vec<String>>) {
+    for id in ["IPLS", "TIPL", "TMCL"] {
+        tag.remove(id);
+    }
+
+    let mut lists: BTreeMap<&str, Vec<InvolvedPeopleListItem>> = BTreeMap::new();
+    for (role, names) in people {
+        let id = match tag.version() {
+            Version::Id3v24 if PRODUCTION_ROLES.contains(&role.as_str()) => "TIPL",
+            Version::Id3v24 => "TMCL",
+            _ => "IPLS",
+        };
+        lists
+            .entry(id)
+            .or_default()
+            .extend(names.iter().map(|name| InvolvedPeopleListItem {
+                involvement: role.clone(),
+                involvee: name.clone(),
+            }));
+    }
+    for (id, items) in lists {
+        let list = Content::InvolvedPeopleList(InvolvedPeopleList { items });
+        tag.add_frame(Frame::with_content(id, list));
+    }
+}
+
+/// Values of a text frame: NUL- or ";"-separated, and in v2.2/2.3 also "/"-separated
+///
+/// A "/" is only treated as a separator when every part is longer than two characters,
+/// so names like "AC/DC" or "M/A/R/R/S" stay whole.
+pub fn text_values(tag: &Tag, id: &str) -> Vec<String> {
+    let Some(text) = tag.get(id).and_then(|frame| frame.content().text()) else {
+        return Vec::new();
+    };
+    if tag.version() == Version::Id3v24 {
+        return split_values(text);
+    }
+
+    split_values(text)
+        .iter()
+        .flat_map(|value| {
+            let parts: Vec<&str> = value.split('/').map(str::trim).collect();
+            if parts.len() > 1 && parts.iter().all(|p| p.chars().count() > 2) {
+                parts
+            } else {
+                vec![value.as_str()]
+            }
+        })
+        .fold(Vec::new(), |mut values: Vec<String>, value| {
+            if !values.iter().any(|v| v.eq_ignore_ascii_case(value)) {
+                values.push(value.to_string());
+            }
+            values
+        })
+}
+
+/// Leaves ISO-8859-1 unspecified, which the id3 crate writes as UTF-8 in v2.4 and
+/// UTF-16 in v2.3 (unspecified still compares equal, so raw-copied frames match)
+fn unicode(encoding: Option<Encoding>) -> Option<Encoding> {
+    encoding.filter(|&e| e != Encoding::Latin1)
+}
+
+/// A frame as written to v2.4: no ISO-8859-1 text
+fn to_v24(frame: &Frame) -> Frame {
+    frame.clone().set_encoding(unicode(frame.encoding()))
+}
+
+/// A frame as v2.3 allows it: no null-separated values, UTF-8 or ISO-8859-1 text
+fn to_v23(frame: &Frame) -> Frame {
+    let mut frame = match frame.content() {
+        Content::Text(text) if text.contains('\0') => {
+            Frame::with_content(frame.id(), Content::Text(text.replace('\0', "/")))
+                .set_encoding(unicode(frame.encoding()))
+        }
+        _ => to_v24(frame),
+    };
+    if matches!(
+        frame.encoding(),
+        Some(Encoding::UTF8) | Some(Encoding::UTF16BE)
+    ) {
+        frame = frame.set_encoding(Some(Encoding::UTF16));
+    }
+    frame
+}
+
+/// Recording date from TDRC, or from the v2.3 TYER (year), TDAT (DDMM) and TIME (HHMM)
+fn recording_date(tag: &Tag) -> Option<Timestamp> {
+    if let Some(date) = tag.date_recorded() {
+        return Some(date);
+    }
+    let year = tag.get("TYER")?.content().text()?.trim().parse().ok()?;
+    let (day, month) = digit_pairs(tag, "TDAT").unzip();
+    let (hour, minute) = digit_pairs(tag, "TIME").unzip();
+    Some(Timestamp {
+        year,
+        month,
+        day: month.and(day),
+        hour: day.and(hour),
+        minute: day.and(minute),
+        second: None,
+    })
+}
+
+/// Original release date from TDOR, or just the year from TORY
+fn original_date(tag: &Tag) -> Option<Timestamp> {
+    tag.original_date_released()
+        .or_else(|| tag.get("TORY")?.content().text()?.trim().parse().ok())
+}
+
+/// A four-digit text frame split into two numbers ("0507" -> (5, 7))
+fn digit_pairs(tag: &Tag, id: &str) -> Option<(u8, u8)> {
+    let text = tag.get(id)?.content().text()?.trim();
+    if text.len() != 4 || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((text[..2].parse().ok()?, text[2..].parse().ok()?))
+}
+
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0usize, |acc, b| (acc << 7) | usize::from(b & 0x7f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_frames_v23() {
+        let txxx = b"\x00REPLAYGAIN_TRACK_GAIN\x00-6.20 dB";
+        let mut frames = b"TXXX".to_vec();
+        frames.extend_from_slice(&(txxx.len() as u32).to_be_bytes());
+        frames.extend_from_slice(&[0, 0]);
+        frames.extend_from_slice(txxx);
+        frames.extend_from_slice(&[0; 16]);
+
+        let mut data = b"ID3\x03\x00\x00\x00\x00\x00".to_vec();
+        data.push(frames.len() as u8);
+        data.extend_from_slice(&frames);
+        data.extend_from_slice(b"\xff\xfbAUDIO");
+
+        let raw = raw_frames(&data).unwrap();
+        assert_eq!(
+            raw,
+            vec![RawFrame {
+                id: "TXXX".to_string(),
+                body: txxx.to_vec(),
+            }]
+        );
+
+        data[5] = FLAG_UNSYNCHRONISATION;
+        assert_eq!(raw_frames(&data), None);
+    }
+
+    #[test]
+    fn test_convert_dates_and_separators() {
+        let mut tag = Tag::with_version(Version::Id3v24);
+        tag.set_text_values("TPE1", ["Madvillain", "MF DOOM"]);
+        tag.set_text("TDRC", "2004-03-23T14:05");
+
+        let v23 = convert(&tag, Version::Id3v23);
+        assert_eq!(v23.artist(), Some("Madvillain/MF DOOM"));
+        assert_eq!(v23.year(), Some(2004));
+        assert_eq!(v23.get("TDAT").unwrap().content().text(), Some("2303"));
+        assert_eq!(v23.get("TIME").unwrap().content().text(), Some("1405"));
+        assert!(v23.get("TDRC").is_none());
+
+        let v24 = convert(&v23, Version::Id3v24);
+        assert_eq!(
+            v24.date_recorded().map(|d| d.to_string()).as_deref(),
+            Some("2004-03-23T14:05")
+        );
+        assert!(v24.get("TYER").is_none());
+        assert_eq!(v24.artists(), Some(vec!["Madvillain", "MF DOOM"]));
+
+        // ISO-8859-1 frames come out as UTF-16 (v2.3) or UTF-8 (v2.4)
+        let mut tag = Tag::with_version(Version::Id3v23);
+        tag.add_frame(Frame::text("TIT2", "Jóga").set_encoding(Some(Encoding::Latin1)));
+        for version in [Version::Id3v23, Version::Id3v24] {
+            let mut data = Vec::new();
+            convert(&tag, version).write_to(&mut data, version).unwrap();
+            let body = &raw_frames(&data).unwrap()[0].body;
+            assert_ne!(body[0], Encoding::Latin1 as u8);
+        }
+    }
+
+    #[test]
+    fn test_involved_people_frames() {
+        let people = BTreeMap::from([
+            ("featuring".to_string(), vec!["Ghostface".to_string()]),
+            ("producer".to_string(), vec!["Madlib".to_string()]),
+        ]);
+        let mut tag = Tag::with_version(Version::Id3v24);
+        set_involved_people(&mut tag, &people);
+        let ids: Vec<&str> = tag.frames().map(|f| f.id()).collect();
+        assert_eq!(ids, vec!["TIPL", "TMCL"]);
+
+        let v23 = convert(&tag, Version::Id3v23);
+        let ids: Vec<&str> = v23.frames().map(|f| f.id()).collect();
+        assert_eq!(ids, vec!["IPLS"]);
+        assert_eq!(involved_people(&v23), people);
+        assert_eq!(involved_people(&convert(&v23, Version::Id3v24)), people);
+    }
+
+    #[test]
+    fn test_text_values_by_version() {
+        let mut tag = Tag::with_version(Version::Id3v23);
+        tag.set_artist("Madvillain/MF DOOM");
+        tag.set_genre("Hip Hop; Jazz Rap");
+        assert_eq!(text_values(&tag, "TPE1"), vec!["Madvillain", "MF DOOM"]);
+        assert_eq!(text_values(&tag, "TCON"), vec!["Hip Hop", "Jazz Rap"]);
+
+        tag.set_artist("AC/DC");
+        assert_eq!(text_values(&tag, "TPE1"), vec!["AC/DC"]);
+
+        // v2.4 separates with NUL only
+        let mut tag = Tag::with_version(Version::Id3v24);
+        tag.set_text_values("TPE1", ["Madvillain", "MF DOOM"]);
+        tag.set_genre("Drum/Bass");
+        assert_eq!(text_values(&tag, "TPE1"), vec!["Madvillain", "MF DOOM"]);
+        assert_eq!(text_values(&tag, "TCON"), vec!["Drum/Bass"]);
+    }
+}
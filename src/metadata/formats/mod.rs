@@ -0,0 +1,395 @@
+pub mod ape;
+pub mod flac;
+pub mod id3v2;
+pub mod mp3;
+pub mod mp4;
+pub mod mpeg;
+pub mod ogg;
+pub mod vorbis;
+
+use crate::error::{AgentError, Result};
+use crate::metadata::artwork::{CoverArt, PictureInfo};
+use crate::metadata::lyrics::Lyrics;
+use crate::metadata::TrackMetadata;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+
+/// Reads and writes one container's tag format
+pub trait TagBackend {
+    /// Human-readable tag format name
+    fn name(&self) -> &str;
+
+    /// Read the tags of a file into TrackMetadata
+    fn read(&self, path: &Path) -> Result<TrackMetadata>;
+
+    /// Write every field set in `metadata` into the file at `path`, in place
+    fn write(&self, path: &Path, metadata: &TrackMetadata) -> Result<()>;
+
+    /// Delete the given fields (suggestion names, e.g. "genre") from the file, in place
+    fn remove_fields(&self, path: &Path, fields: &[String]) -> Result<()>;
+
+    /// Byte ranges of `data` holding the encoded audio, in order; tag writes must not
+    /// change their contents
+    fn audio_ranges(&self, data: &[u8]) -> std::result::Result<Vec<Range<usize>>, String>;
+
+    /// Replace the front cover with `art`
+    fn embed_cover(&self, _path: &Path, _art: &CoverArt) -> Result<()> {
+        Err(AgentError::MetadataParse(format!(
+            "Embedding cover art is not supported for {} tags",
+            self.name()
+        )))
+    }
+
+    /// Embedded pictures with their image data
+    fn extract_pictures(&self, _path: &Path) -> Result<Vec<(PictureInfo, Vec<u8>)>> {
+        Ok(Vec::new())
+    }
+
+    /// Replace the embedded lyrics with `lyrics`
+    fn embed_lyrics(&self, _path: &Path, _lyrics: &Lyrics) -> Result<()> {
+        Err(AgentError::MetadataParse(format!(
+            "Embedding lyrics is not supported for {} tags",
+            self.name()
+        )))
+    }
+
+    /// Embedded lyrics, synchronized ones first
+    fn extract_lyrics(&self, _path: &Path) -> Result<Vec<Lyrics>> {
+        Ok(Vec::new())
+    }
+
+    /// Frames that TrackMetadata doesn't cover, as (label, value) pairs with binary data
+    /// summarised; formats that map every item to a field return nothing
+    fn other_frames(&self, _path: &Path) -> Result<Vec<(String, String)>> {
+        Ok(Vec::new())
+    }
+
+    /// Remove legacy tag formats left next to the primary tag; returns what was removed
+    fn strip_legacy_tags(&self, _path: &Path) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Rewrite the ID3v2 tag as `version`, translating frames; returns false if the file
+    /// has no ID3v2 tag or already uses that version
+    fn convert_id3_version(&self, _path: &Path, _version: Id3Version) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// ID3v2 revision to write; v2.3 is what older players and car stereos understand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Id3Version {
+    V23,
+    #[default]
+    V24,
+}
+
+impl fmt::Display for Id3Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V23 => write!(f, "ID3v2.3"),
+            Self::V24 => write!(f, "ID3v2.4"),
+        }
+    }
+}
+
+/// Supported audio containers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Mp3,
+    Flac,
+    Mp4,
+    Ogg,
+}
+
+/// What the first bytes of a file say it contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Content {
+    Audio(AudioFormat),
+    /// Audio in a format without tag support, e.g. "AAC (ADTS)"
+    Unsupported(&'static str),
+    Unknown,
+}
+
+/// How much of the file after any ID3v2 tag is read to detect its format
+const SNIFF_LEN: u64 = 64 * 1024;
+
+impl AudioFormat {
+    /// Format of an existing file from its contents (see [`AudioFormat::detect`])
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::detect(path).ok()
+    }
+
+    /// Format of an existing file from its magic bytes, with the extension only as a
+    /// hint for MP3s whose first frame doesn't directly follow the ID3v2 tag
+    pub fn detect(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut header = Vec::new();
+        file.by_ref().take(10).read_to_end(&mut header)?;
+        let tag_len = id3v2_size(&header);
+        file.seek(SeekFrom::Start(tag_len as u64))?;
+        let mut head = Vec::new();
+        file.take(SNIFF_LEN).read_to_end(&mut head)?;
+
+        match sniff(&head, tag_len > 0, Self::from_extension(path)) {
+            Content::Audio(format) => Ok(format),
+            content => Err(unsupported(path, content)),
+        }
+    }
+
+    /// Format from the file extension alone (case-insensitive)
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "mp3" => Some(Self::Mp3),
+            "flac" => Some(Self::Flac),
+            "m4a" | "m4b" | "mp4" => Some(Self::Mp4),
+            "ogg" | "oga" | "opus" => Some(Self::Ogg),
+            _ => None,
+        }
+    }
+
+    /// Format from the first bytes of a file's contents
+    pub fn sniff(data: &[u8], hint: Option<Self>) -> Content {
+        let tag_len = id3v2_size(data);
+        sniff(data.get(tag_len..).unwrap_or_default(), tag_len > 0, hint)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mp3 => "MP3",
+            Self::Flac => "FLAC",
+            Self::Mp4 => "MP4",
+            Self::Ogg => "Ogg",
+        }
+    }
+
+    /// Usual extension of the format, without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::Flac => "flac",
+            Self::Mp4 => "m4a",
+            Self::Ogg => "ogg",
+        }
+    }
+
+    /// Why `path`'s extension doesn't fit content in this format, if it doesn't
+    pub fn extension_mismatch(&self, path: &Path) -> Option<String> {
+        if Self::from_extension(path) == Some(*self) {
+            return None;
+        }
+        let extension = match path.extension() {
+            Some(extension) => format!("The .{} extension", extension.to_string_lossy()),
+            None => "A missing extension".to_string(),
+        };
+        Some(format!(
+            "{} doesn't match the {} content (expected .{})",
+            extension,
+            self.name(),
+            self.extension()
+        ))
+    }
+
+    /// True if the tag format has involved-people frames (ID3 TIPL/TMCL/IPLS)
+    pub fn stores_credits(&self) -> bool {
+        matches!(self, Self::Mp3)
+    }
+
+    pub fn backend(&self) -> &'static dyn TagBackend {
+        match self {
+            Self::Mp3 => &mp3::Id3Backend,
+            Self::Flac => &flac::FlacBackend,
+            Self::Mp4 => &mp4::Mp4Backend,
+            Self::Ogg => &ogg::OggBackend,
+        }
+    }
+}
+
+/// SHA-256 (hex) of the audio data of a file, leaving out all tags
+pub fn audio_hash(path: &Path) -> Result<String> {
+    let data = fs::read(path)?;
+    let ranges = audio_ranges(path, &data)?;
+    Ok(sha256_hex(ranges.into_iter().map(|r| &data[r])))
+}
+
+/// Byte ranges holding audio in `data`, the contents of the file at `path`
+pub fn audio_ranges(path: &Path, data: &[u8]) -> Result<Vec<Range<usize>>> {
+    let format = match AudioFormat::sniff(data, AudioFormat::from_extension(path)) {
+        Content::Audio(format) => format,
+        content => return Err(unsupported(path, content)),
+    };
+    format
+        .backend()
+        .audio_ranges(data)
+        .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))
+}
+
+/// SHA-256 (hex) of the concatenated parts
+pub fn sha256_hex<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Size of a leading ID3v2 tag, or 0 if there is none
+pub fn id3v2_size(data: &[u8]) -> usize {
+    if data.len() < 10 || &data[..3] != b"ID3" {
+        return 0;
+    }
+    let size = data[6..10]
+        .iter()
+        .fold(0usize, |acc, b| (acc << 7) | usize::from(b & 0x7f));
+    let footer = if data[5] & 0x10 != 0 { 10 } else { 0 };
+    10 + size + footer
+}
+
+/// Content type of `data`, the bytes after an ID3v2 tag if `tagged`
+///
+/// Without two consecutive frames right at the start, MP3 audio is only accepted when an
+/// ID3v2 tag or the extension suggests it, since stray sync bytes are common.
+fn sniff(data: &[u8], tagged: bool, hint: Option<AudioFormat>) -> Content {
+    let magic = |at: usize, bytes: &[u8]| data.get(at..at + bytes.len()) == Some(bytes);
+
+    if magic(0, b"fLaC") {
+        Content::Audio(AudioFormat::Flac)
+    } else if magic(0, b"OggS") {
+        Content::Audio(AudioFormat::Ogg)
+    } else if magic(4, b"ftyp") {
+        Content::Audio(AudioFormat::Mp4)
+    } else if data.len() >= 2 && data[0] == 0xff && data[1] & 0xf6 == 0xf0 {
+        // ADTS shares the MPEG sync word but has layer bits 00
+        Content::Unsupported("AAC (ADTS)")
+    } else if magic(0, b"RIFF") && magic(8, b"WAVE") {
+        Content::Unsupported("WAV")
+    } else if magic(0, b"FORM") && (magic(8, b"AIFF") || magic(8, b"AIFC")) {
+        Content::Unsupported("AIFF")
+    } else {
+        let expected = tagged || hint == Some(AudioFormat::Mp3);
+        match mpeg::find_audio(data, 0) {
+            Some(0) => Content::Audio(AudioFormat::Mp3),
+            Some(_) if expected => Content::Audio(AudioFormat::Mp3),
+            _ if expected && mpeg::is_frame_header(data) => Content::Audio(AudioFormat::Mp3),
+            _ => Content::Unknown,
+        }
+    }
+}
+
+fn unsupported(path: &Path, content: Content) -> AgentError {
+    match content {
+        Content::Unsupported(kind) => AgentError::FileRead(format!(
+            "{} contains {} audio, which has no tag support (expected MP3, FLAC, M4A, Ogg or Opus)",
+            path.display(),
+            kind
+        )),
+        _ => AgentError::FileRead(format!(
+            "Unsupported file type (expected MP3, FLAC, M4A, Ogg or Opus): {}",
+            path.display()
+        )),
+    }
+}
+
+/// A minimal MP3 for tests: an ID3v2.3 tag titled "Pennyroyal" plus `extra_frames`,
+/// then one silent MPEG frame
+#[cfg(test)]
+pub fn test_mp3(extra_frames: &[u8]) -> Vec<u8> {
+    let mut frames = id3_frame("TIT2", b"\x00Pennyroyal");
+    frames.extend_from_slice(extra_frames);
+    let mut data = b"ID3\x03\x00\x00".to_vec();
+    let size = frames.len();
+    data.extend((0..4).rev().map(|i| ((size >> (7 * i)) & 0x7f) as u8));
+    data.extend_from_slice(&frames);
+    data.extend_from_slice(&[0xff, 0xfb, 0x90, 0x44]);
+    data.resize(data.len() + 413, 0);
+    data
+}
+
+/// An ID3v2.3 frame with `body` as its content
+#[cfg(test)]
+pub fn id3_frame(id: &str, body: &[u8]) -> Vec<u8> {
+    let mut out = id.as_bytes().to_vec();
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    out.extend_from_slice(&[0, 0]);
+    out.extend_from_slice(body);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_content() {
+        let frame = |len| {
+            let mut frame = vec![0xff, 0xfb, 0x90, 0x44];
+            frame.resize(len, 0);
+            frame
+        };
+        let mp3 = [frame(417), frame(417)].concat();
+        let mut tagged = b"ID3\x04\x00\x00\x00\x00\x00\x04junk".to_vec();
+        tagged.extend_from_slice(&frame(417));
+        // A lone sync word, as found in stray bytes
+        let lone = [frame(417), b"not audio".to_vec()].concat();
+        let mut aac = vec![0xff, 0xf1, 0x50, 0x80];
+        aac.resize(64, 0);
+
+        let cases: [(&[u8], Option<AudioFormat>, Content); 8] = [
+            (
+                b"fLaC\x00\x00\x00\x22",
+                None,
+                Content::Audio(AudioFormat::Flac),
+            ),
+            (b"OggS\x00\x02", None, Content::Audio(AudioFormat::Ogg)),
+            (
+                b"\x00\x00\x00\x20ftypM4A ",
+                None,
+                Content::Audio(AudioFormat::Mp4),
+            ),
+            (&mp3, None, Content::Audio(AudioFormat::Mp3)),
+            (&tagged, None, Content::Audio(AudioFormat::Mp3)),
+            (
+                &lone,
+                Some(AudioFormat::Mp3),
+                Content::Audio(AudioFormat::Mp3),
+            ),
+            (&lone, None, Content::Unknown),
+            (
+                &aac,
+                Some(AudioFormat::Mp3),
+                Content::Unsupported("AAC (ADTS)"),
+            ),
+        ];
+        for (data, hint, expected) in cases {
+            assert_eq!(AudioFormat::sniff(data, hint), expected);
+        }
+    }
+
+    #[test]
+    fn test_extension_mismatch() {
+        assert_eq!(
+            AudioFormat::Mp3.extension_mismatch(Path::new("TRACK.MP3")),
+            None
+        );
+        assert_eq!(
+            AudioFormat::Mp4.extension_mismatch(Path::new("a.m4b")),
+            None
+        );
+        assert_eq!(
+            AudioFormat::Flac.extension_mismatch(Path::new("song.mp3")),
+            Some("The .mp3 extension doesn't match the FLAC content (expected .flac)".to_string())
+        );
+        assert!(AudioFormat::Ogg
+            .extension_mismatch(Path::new("song"))
+            .is_some());
+    }
+}
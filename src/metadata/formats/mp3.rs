@@ -0,0 +1,759 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::artwork::{short_hash, CoverArt, PictureInfo};
+use crate::metadata::formats::ape::ApeTag;
+use crate::metadata::formats::id3v2::{self, RawFrame};
+use crate::metadata::formats::{id3v2_size, mpeg, Id3Version, TagBackend};
+use crate::metadata::lyrics::{LyricLine, Lyrics};
+use crate::metadata::{
+    custom_key, parse_bool, people_role, TagConflict, TrackMetadata, CUSTOM_PREFIX, FIELDS,
+};
+use id3::frame::{
+    Comment, Content, ExtendedText, Lyrics as Uslt, Picture, PictureType, SynchronisedLyrics,
+    SynchronisedLyricsType, TimestampFormat, Unknown,
+};
+use id3::{v1, Frame, Tag, TagLike, Timestamp, Version};
+use std::fs;
+use std::io::Cursor;
+use std::ops::Range;
+use std::path::Path;
+
+/// Frames other tools rely on (ReplayGain, MusicBrainz IDs, DJ cue points); a rewrite
+/// carries them over byte-for-byte unless a suggestion changes them
+const PRESERVED_FRAMES: &[&str] = &["TXXX", "PRIV", "GEOB", "UFID"];
+
+/// Frames read into TrackMetadata fields, pictures or lyrics (COMM only without a description)
+const MAPPED_FRAMES: &[&str] = &[
+    "TPE1", "TIT2", "TALB", "TYER", "TDRC", "TCON", "TRCK", "TPE2", "TPOS", "TCOM", "TBPM", "TPUB",
+    "TSRC", "TCMP", "TSOP", "TSOA", "TIPL", "TMCL", "IPLS", "TXXX", "APIC", "USLT", "SYLT",
+];
+
+/// ID3v2 tags in MP3 files
+pub struct Id3Backend;
+
+impl TagBackend for Id3Backend {
+    fn name(&self) -> &str {
+        "ID3v2"
+    }
+
+    /// Reads ID3v2, falling back to APEv2 and ID3v1 for fields it lacks
+    fn read(&self, path: &Path) -> Result<TrackMetadata> {
+        let file_path = path.to_string_lossy();
+        let data = fs::read(path)?;
+
+        let mut sources: Vec<(String, TrackMetadata)> = Vec::new();
+        let mut tlen = None;
+        let mut pictures = Vec::new();
+        let mut lyrics = Vec::new();
+        let id3v2_error = match Tag::read_from_path(path) {
+            Ok(tag) => {
+                tlen = tag.duration();
+                pictures = tag.pictures().map(picture_info).collect();
+                lyrics = tag_lyrics(&tag)
+                    .into_iter()
+                    .map(|(source, l)| l.info(source))
+                    .collect();
+                sources.push((version_name(tag.version()), id3v2_to_metadata(&tag)));
+                None
+            }
+            Err(e) => Some(e),
+        };
+        if let Some(ape) = ApeTag::read(&data) {
+            sources.push(("APEv2".to_string(), ape.to_metadata()));
+        }
+        if let Ok(tag) = v1::Tag::read_from_path(path) {
+            sources.push(("ID3v1".to_string(), id3v1_to_metadata(&tag)));
+        }
+
+        // A file without any tag is fine (everything is missing); a broken one is not
+        if let Some(e) = id3v2_error.filter(|_| sources.is_empty()) {
+            if !matches!(e.kind, id3::ErrorKind::NoTag) {
+                return Err(AgentError::MetadataParse(format!(
+                    "Failed to read ID3 tags from {}: {}",
+                    file_path, e
+                )));
+            }
+        }
+
+        // Earlier sources win; ID3v1 comes last since its fields are truncated
+        let mut metadata = TrackMetadata::default();
+        for (_, source) in &sources {
+            metadata.fill_missing_from(source);
+        }
+        metadata.tag_conflicts = find_conflicts(&sources);
+        metadata.legacy_fields = legacy_fields(&metadata, &sources);
+        metadata.tag_types = sources.into_iter().map(|(name, _)| name).collect();
+
+        metadata.pictures = pictures;
+        metadata.lyrics = lyrics;
+        metadata.file_path = file_path.to_string();
+        metadata.audio = mpeg::read_properties(&data);
+        // TLEN is in milliseconds; the measured stream length wins when available
+        metadata.duration_seconds = metadata
+            .audio
+            .as_ref()
+            .map(|a| a.duration_seconds())
+            .or(tlen.map(|ms| ms / 1000));
+        Ok(metadata)
+    }
+
+    fn write(&self, path: &Path, metadata: &TrackMetadata) -> Result<()> {
+        // Read existing tag (with v2.4 frames, so the year lands in TDRC) or create new one
+        let mut tag = Tag::read_from_path(path)
+            .map(|tag| id3v2::convert(&tag, Version::Id3v24))
+            .unwrap_or_else(|_| Tag::new());
+
+        // Update fields if provided
+        // Multiple values are NUL-separated here; write_tag joins them with "/" for v2.3
+        if !metadata.artists.is_empty() {
+            tag.set_text_values("TPE1", &metadata.artists);
+        }
+
+        if let Some(ref title) = metadata.title {
+            tag.set_title(title);
+        }
+
+        if let Some(ref album) = metadata.album {
+            tag.set_album(album);
+        }
+
+        // Keep a full recording date when its year is already right
+        if let Some(year) = metadata.year {
+            if tag.date_recorded().map(|d| d.year) != Some(year) {
+                tag.set_date_recorded(Timestamp {
+                    year,
+                    month: None,
+                    day: None,
+                    hour: None,
+                    minute: None,
+                    second: None,
+                });
+            }
+        }
+
+        if !metadata.genres.is_empty() {
+            tag.set_text_values("TCON", &metadata.genres);
+        }
+
+        if let Some(track) = metadata.track_number {
+            tag.set_track(track);
+        }
+
+        if let Some(ref album_artist) = metadata.album_artist {
+            tag.set_album_artist(album_artist);
+        }
+
+        if let Some(total_tracks) = metadata.total_tracks {
+            tag.set_total_tracks(total_tracks);
+        }
+
+        if let Some(disc) = metadata.disc_number {
+            tag.set_disc(disc);
+        }
+
+        if let Some(total_discs) = metadata.total_discs {
+            tag.set_total_discs(total_discs);
+        }
+
+        if let Some(ref composer) = metadata.composer {
+            tag.set_text("TCOM", composer);
+        }
+
+        // Plain comments are keyed by language too; drop every one so "XXX" or empty
+        // language comments from other taggers aren't left beside the new one
+        if let Some(ref comment) = metadata.comment {
+            tag.remove_comment(Some(""), None);
+            tag.add_frame(Comment {
+                lang: "eng".to_string(),
+                description: String::new(),
+                text: comment.clone(),
+            });
+        }
+
+        if let Some(bpm) = metadata.bpm {
+            tag.set_text("TBPM", bpm.to_string());
+        }
+
+        if let Some(ref publisher) = metadata.publisher {
+            tag.set_text("TPUB", publisher);
+        }
+
+        if let Some(ref isrc) = metadata.isrc {
+            tag.set_text("TSRC", isrc);
+        }
+
+        if let Some(compilation) = metadata.compilation {
+            tag.set_text("TCMP", if compilation { "1" } else { "0" });
+        }
+
+        if let Some(ref artist_sort) = metadata.artist_sort {
+            tag.set_text("TSOP", artist_sort);
+        }
+
+        if let Some(ref album_sort) = metadata.album_sort {
+            tag.set_text("TSOA", album_sort);
+        }
+
+        if !metadata.people.is_empty() && id3v2::involved_people(&tag) != metadata.people {
+            id3v2::set_involved_people(&mut tag, &metadata.people);
+        }
+
+        // Only touch TXXX frames whose value changed so the rest stay byte-identical
+        for (key, value) in &metadata.custom {
+            let current = tag
+                .extended_texts()
+                .find(|t| t.description.eq_ignore_ascii_case(key));
+            if current.is_some_and(|t| extended_value(t) == value) {
+                continue;
+            }
+            let description = current.map_or_else(|| key.clone(), |t| t.description.clone());
+            tag.remove_extended_text(Some(&description), None);
+            tag.add_frame(ExtendedText {
+                description,
+                value: value.clone(),
+            });
+        }
+
+        // Write to the file (the writer passes the NEW copy) with ID3v2.4; the writer
+        // converts it afterwards if another version was asked for
+        write_tag(path, &tag, Version::Id3v24, "write ID3 tags")
+    }
+
+    fn remove_fields(&self, path: &Path, fields: &[String]) -> Result<()> {
+        let Some(mut tag) = read_tag(path)? else {
+            return Ok(());
+        };
+
+        for field in fields {
+            match field.as_str() {
+                "artist" => tag.remove_artist(),
+                "title" => tag.remove_title(),
+                "album" => tag.remove_album(),
+                "year" => {
+                    for id in ["TYER", "TDAT", "TIME", "TDRC"] {
+                        tag.remove(id);
+                    }
+                }
+                "genre" => tag.remove_genre(),
+                "track_number" => tag.remove_track(),
+                "total_tracks" => tag.remove_total_tracks(),
+                "disc_number" => tag.remove_disc(),
+                "total_discs" => tag.remove_total_discs(),
+                "album_artist" => tag.remove_album_artist(),
+                // Remove the comment `read` reports: the plain one if there is one
+                "comment" => {
+                    let has_plain = tag.comments().any(|c| c.description.is_empty());
+                    tag.remove_comment(has_plain.then_some(""), None);
+                }
+                other => {
+                    if let Some(id) = frame_id(other) {
+                        tag.remove(id);
+                    } else if let Some(role) = people_role(other) {
+                        let mut people = id3v2::involved_people(&tag);
+                        if people.remove(&role).is_some() {
+                            id3v2::set_involved_people(&mut tag, &people);
+                        }
+                    } else if let Some(key) = custom_key(other) {
+                        let description = tag
+                            .extended_texts()
+                            .find(|t| t.description.eq_ignore_ascii_case(key))
+                            .map(|t| t.description.clone());
+                        if let Some(description) = description {
+                            tag.remove_extended_text(Some(&description), None);
+                        }
+                    }
+                }
+            }
+        }
+
+        let version = tag.version();
+        write_tag(path, &tag, version, "write ID3 tags")
+    }
+
+    /// Everything between the ID3v2 tag and any trailing APEv2/ID3v1 tag
+    fn audio_ranges(&self, data: &[u8]) -> std::result::Result<Vec<Range<usize>>, String> {
+        let start = id3v2_size(data).min(data.len());
+        Ok(std::iter::once(start..mpeg::audio_end(data).max(start)).collect())
+    }
+
+    /// Replace any front cover APIC frame, skipping the write if it's already embedded
+    fn embed_cover(&self, path: &Path, art: &CoverArt) -> Result<()> {
+        let mut tag = Tag::read_from_path(path).unwrap_or_else(|_| Tag::new());
+        if tag.pictures().any(|p| p.data == art.data) {
+            return Ok(());
+        }
+
+        tag.remove_picture_by_type(PictureType::CoverFront);
+        tag.add_frame(Picture {
+            mime_type: art.mime_type.clone(),
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+            data: art.data.clone(),
+        });
+
+        let version = tag.version();
+        write_tag(path, &tag, version, "embed cover art")
+    }
+
+    /// Replace USLT (and, for timed lyrics, SYLT) frames with `lyrics`
+    ///
+    /// Synced lyrics are written to both frames since many players only show USLT.
+    fn embed_lyrics(&self, path: &Path, lyrics: &Lyrics) -> Result<()> {
+        let mut tag = Tag::read_from_path(path).unwrap_or_else(|_| Tag::new());
+
+        tag.remove_all_lyrics();
+        tag.add_frame(Uslt {
+            lang: lyrics.language.clone(),
+            description: String::new(),
+            text: lyrics.text(),
+        });
+        if lyrics.is_synced() {
+            tag.remove_all_synchronised_lyrics();
+            tag.add_frame(SynchronisedLyrics {
+                lang: lyrics.language.clone(),
+                timestamp_format: TimestampFormat::Ms,
+                content_type: SynchronisedLyricsType::Lyrics,
+                description: String::new(),
+                content: lyrics
+                    .lines
+                    .iter()
+                    .map(|l| (l.time_ms.unwrap_or(0), l.text.clone()))
+                    .collect(),
+            });
+        }
+
+        let version = tag.version();
+        write_tag(path, &tag, version, "embed lyrics")
+    }
+
+    fn extract_lyrics(&self, path: &Path) -> Result<Vec<Lyrics>> {
+        let Some(tag) = read_tag(path)? else {
+            return Ok(Vec::new());
+        };
+        Ok(tag_lyrics(&tag).into_iter().map(|(_, l)| l).collect())
+    }
+
+    fn extract_pictures(&self, path: &Path) -> Result<Vec<(PictureInfo, Vec<u8>)>> {
+        let Some(tag) = read_tag(path)? else {
+            return Ok(Vec::new());
+        };
+        Ok(tag
+            .pictures()
+            .map(|p| (picture_info(p), p.data.clone()))
+            .collect())
+    }
+
+    fn other_frames(&self, path: &Path) -> Result<Vec<(String, String)>> {
+        let Some(tag) = read_tag(path)? else {
+            return Ok(Vec::new());
+        };
+        Ok(tag.frames().filter_map(frame_summary).collect())
+    }
+
+    /// Drop APEv2 and ID3v1 tags so only the ID3v2 tag remains
+    fn strip_legacy_tags(&self, path: &Path) -> Result<Vec<String>> {
+        let mut data = fs::read(path)?;
+        let mut removed = Vec::new();
+
+        let has_id3v1 = data.len() >= 128 && &data[data.len() - 128..][..3] == b"TAG";
+        if has_id3v1 {
+            data.truncate(data.len() - 128);
+            removed.push("ID3v1".to_string());
+        }
+        // ID3v1 always sits last, so an APE tag is now at the very end
+        if let Some(ape) = ApeTag::read(&data) {
+            data.drain(ape.range);
+            removed.push("APEv2".to_string());
+        }
+
+        if !removed.is_empty() {
+            fs::write(path, data)?;
+        }
+        Ok(removed)
+    }
+
+    fn convert_id3_version(&self, path: &Path, version: Id3Version) -> Result<bool> {
+        let target = match version {
+            Id3Version::V23 => Version::Id3v23,
+            Id3Version::V24 => Version::Id3v24,
+        };
+        match read_tag(path)? {
+            Some(tag) if tag.version() != target => {
+                write_tag(path, &tag, target, "convert ID3 tags")?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// The file's ID3v2 tag, or None if it has none
+fn read_tag(path: &Path) -> Result<Option<Tag>> {
+    match Tag::read_from_path(path) {
+        Ok(tag) => Ok(Some(tag)),
+        Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => Ok(None),
+        Err(e) => Err(AgentError::MetadataParse(format!(
+            "Failed to read ID3 tags from {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Write `tag` over the file's current tag as `version`, copying preserved frames that
+/// are still unchanged from the current tag's raw bytes instead of re-encoding them
+fn write_tag(path: &Path, tag: &Tag, version: Version, action: &str) -> Result<()> {
+    let tag = id3v2::convert(tag, version);
+    let failed = |e: &dyn std::fmt::Display| {
+        AgentError::MetadataParse(format!("Failed to {}: {}", action, e))
+    };
+
+    let mut originals = preserved_frames(&fs::read(path)?);
+    let mut copied = Vec::new();
+    let mut output = Tag::with_version(version);
+    for frame in tag.frames() {
+        match originals.iter().position(|(decoded, _)| decoded == frame) {
+            Some(i) => {
+                let (_, raw) = originals.remove(i);
+                output.add_frame(Frame::with_content(
+                    frame.id(),
+                    Content::Unknown(Unknown {
+                        data: raw.body.clone(),
+                        version,
+                    }),
+                ));
+                copied.push(raw);
+            }
+            None => {
+                output.add_frame(frame.clone());
+            }
+        }
+    }
+    output
+        .write_to_path(path, version)
+        .map_err(|e| failed(&e))?;
+
+    // Check the copies landed intact; the writer deletes the file on error
+    let written = id3v2::raw_frames(&fs::read(path)?).unwrap_or_default();
+    if let Some(lost) = copied.iter().find(|raw| !written.contains(raw)) {
+        return Err(failed(&format!(
+            "the {} frame was not preserved byte-for-byte",
+            lost.id
+        )));
+    }
+    Ok(())
+}
+
+/// Decoded TXXX/PRIV/GEOB/UFID frames of a file paired with their raw bytes
+///
+/// Empty if the raw frames can't be matched one-to-one with the decoded ones
+/// (e.g. an unsynchronised tag); those frames are then re-encoded as usual.
+fn preserved_frames(data: &[u8]) -> Vec<(Frame, RawFrame)> {
+    let (Some(raw), Ok(tag)) = (id3v2::raw_frames(data), Tag::read_from2(Cursor::new(data))) else {
+        return Vec::new();
+    };
+    let raw: Vec<RawFrame> = raw
+        .into_iter()
+        .filter(|f| PRESERVED_FRAMES.contains(&f.id.as_str()))
+        .collect();
+    let decoded: Vec<&Frame> = tag
+        .frames()
+        .filter(|f| PRESERVED_FRAMES.contains(&f.id()))
+        .collect();
+
+    let aligned =
+        raw.len() == decoded.len() && raw.iter().zip(&decoded).all(|(r, d)| r.id == d.id());
+    if !aligned {
+        return Vec::new();
+    }
+    decoded.into_iter().cloned().zip(raw).collect()
+}
+
+fn picture_info(picture: &Picture) -> PictureInfo {
+    PictureInfo::new(
+        &picture.picture_type.to_string(),
+        &picture.mime_type,
+        &picture.description,
+        &picture.data,
+    )
+}
+
+/// SYLT then USLT frames as (frame id, lyrics)
+fn tag_lyrics(tag: &Tag) -> Vec<(&'static str, Lyrics)> {
+    let synced = tag.synchronised_lyrics().map(|sylt| {
+        // Timestamps counted in MPEG frames can't be mapped to LRC times, so drop them
+        let is_ms = sylt.timestamp_format == TimestampFormat::Ms;
+        let lines = sylt
+            .content
+            .iter()
+            .map(|(time, text)| LyricLine {
+                time_ms: is_ms.then_some(*time),
+                text: text.trim_end().to_string(),
+            })
+            .collect();
+        ("SYLT", Lyrics::new(&sylt.lang, lines))
+    });
+    let unsynced = tag
+        .lyrics()
+        .map(|uslt| ("USLT", Lyrics::from_text(&uslt.lang, &uslt.text)));
+    synced.chain(unsynced).collect()
+}
+
+/// "ID3v2.3" etc.
+fn version_name(version: Version) -> String {
+    let minor = match version {
+        Version::Id3v22 => 2,
+        Version::Id3v23 => 3,
+        Version::Id3v24 => 4,
+    };
+    format!("ID3v2.{}", minor)
+}
+
+fn id3v2_to_metadata(tag: &Tag) -> TrackMetadata {
+    // Prefer the plain comment over described ones (e.g. iTunNORM)
+    let comment = tag
+        .comments()
+        .find(|c| c.description.is_empty())
+        .or_else(|| tag.comments().next())
+        .map(|c| c.text.clone());
+
+    TrackMetadata {
+        artists: id3v2::text_values(tag, "TPE1"),
+        title: tag.title().map(|s| s.to_string()),
+        album: tag.album().map(|s| s.to_string()),
+        year: tag.year().or_else(|| tag.date_recorded().map(|d| d.year)),
+        genres: id3v2::text_values(tag, "TCON"),
+        track_number: tag.track(),
+        album_artist: tag.album_artist().map(|s| s.to_string()),
+        total_tracks: tag.total_tracks(),
+        disc_number: tag.disc(),
+        total_discs: tag.total_discs(),
+        composer: text_frame(tag, "TCOM"),
+        comment,
+        // BPM is sometimes written with decimals ("120.5")
+        bpm: text_frame(tag, "TBPM")
+            .and_then(|b| b.trim().parse::<f32>().ok())
+            .map(|b| b.round() as u32),
+        publisher: text_frame(tag, "TPUB"),
+        isrc: text_frame(tag, "TSRC"),
+        compilation: text_frame(tag, "TCMP").and_then(|c| parse_bool(&c)),
+        artist_sort: text_frame(tag, "TSOP"),
+        album_sort: text_frame(tag, "TSOA"),
+        people: id3v2::involved_people(tag),
+        custom: tag
+            .extended_texts()
+            .map(|t| (t.description.clone(), extended_value(t).to_string()))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// TXXX value without the NUL terminator some taggers leave on it
+fn extended_value(text: &ExtendedText) -> &str {
+    text.value.trim_end_matches('\0')
+}
+
+fn id3v1_to_metadata(tag: &v1::Tag) -> TrackMetadata {
+    let text = |value: &str| {
+        let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+        (!value.is_empty()).then(|| value.to_string())
+    };
+
+    TrackMetadata {
+        artists: text(&tag.artist).into_iter().collect(),
+        title: text(&tag.title),
+        album: text(&tag.album),
+        year: tag.year.trim().parse().ok().filter(|y| *y > 0),
+        genres: tag.genre().map(|g| g.to_string()).into_iter().collect(),
+        track_number: tag.track.filter(|t| *t > 0).map(u32::from),
+        comment: text(&tag.comment),
+        ..Default::default()
+    }
+}
+
+/// Fields of the merged metadata that no ID3v2 tag supplied
+fn legacy_fields(merged: &TrackMetadata, sources: &[(String, TrackMetadata)]) -> Vec<String> {
+    let primary = sources
+        .iter()
+        .find(|(name, _)| name.starts_with("ID3v2"))
+        .map(|(_, source)| source);
+    let custom = merged
+        .custom
+        .keys()
+        .map(|key| format!("{}{}", CUSTOM_PREFIX, key));
+    FIELDS
+        .iter()
+        .map(|field| field.to_string())
+        .chain(custom)
+        .filter(|field| {
+            merged.field_value(field).is_some()
+                && primary.and_then(|p| p.field_value(field)).is_none()
+        })
+        .collect()
+}
+
+/// Fields whose values differ between tag formats
+///
+/// ID3v1 text is capped at 30 bytes, so a v1 value that is a prefix of the other
+/// value is not reported as a conflict.
+fn find_conflicts(sources: &[(String, TrackMetadata)]) -> Vec<TagConflict> {
+    FIELDS
+        .iter()
+        .filter_map(|field| {
+            let values: Vec<(&String, String)> = sources
+                .iter()
+                .filter_map(|(tag, metadata)| metadata.field_value(field).map(|v| (tag, v)))
+                .collect();
+
+            let agree = |(tag_a, a): &(&String, String), (tag_b, b): &(&String, String)| {
+                let (a, b) = (a.trim().to_lowercase(), b.trim().to_lowercase());
+                a == b
+                    || (tag_a.as_str() == "ID3v1" && b.starts_with(&a))
+                    || (tag_b.as_str() == "ID3v1" && a.starts_with(&b))
+            };
+            let conflicting = values
+                .iter()
+                .enumerate()
+                .any(|(i, a)| values[i + 1..].iter().any(|b| !agree(a, b)));
+
+            conflicting.then(|| TagConflict {
+                field: field.to_string(),
+                values: values
+                    .into_iter()
+                    .map(|(tag, value)| (tag.clone(), value))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Text frame holding a field that has no dedicated id3 accessor
+fn frame_id(field: &str) -> Option<&'static str> {
+    match field {
+        "composer" => Some("TCOM"),
+        "bpm" => Some("TBPM"),
+        "publisher" => Some("TPUB"),
+        "isrc" => Some("TSRC"),
+        "compilation" => Some("TCMP"),
+        "artist_sort" => Some("TSOP"),
+        "album_sort" => Some("TSOA"),
+        _ => None,
+    }
+}
+
+/// Label and value of a frame that isn't mapped to a field, e.g. ("PRIV WM/Provider",
+/// "12 bytes, SHA-256 0123abcd…"); None for mapped frames
+fn frame_summary(frame: &Frame) -> Option<(String, String)> {
+    let id = frame.id();
+    let plain_comment = matches!(frame.content(), Content::Comment(c) if c.description.is_empty());
+    if MAPPED_FRAMES.contains(&id) || plain_comment {
+        return None;
+    }
+    let binary = |data: &[u8]| format!("{} bytes, SHA-256 {}", data.len(), short_hash(data));
+
+    let (qualifier, value) = match frame.content() {
+        Content::Text(text) => (String::new(), text.replace('\0', "; ")),
+        Content::Link(link) => (String::new(), link.clone()),
+        Content::ExtendedLink(link) => (link.description.clone(), link.link.clone()),
+        Content::Comment(c) => (c.description.clone(), c.text.clone()),
+        Content::Private(p) => (p.owner_identifier.clone(), binary(&p.private_data)),
+        Content::UniqueFileIdentifier(u) => (
+            u.owner_identifier.clone(),
+            String::from_utf8_lossy(&u.identifier).to_string(),
+        ),
+        Content::EncapsulatedObject(o) => (
+            o.description.clone(),
+            format!("{} {}, {}", o.mime_type, o.filename, binary(&o.data)),
+        ),
+        Content::Unknown(u) => (String::new(), binary(&u.data)),
+        other => (String::new(), other.to_string()),
+    };
+    let label = match qualifier.trim() {
+        "" => id.to_string(),
+        qualifier => format!("{} {}", id, qualifier),
+    };
+    Some((label, value))
+}
+
+/// Text content of a frame, if present
+fn text_frame(tag: &Tag, id: &str) -> Option<String> {
+    tag.get(id)
+        .and_then(|frame| frame.content().text())
+        .map(|s| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::formats::{id3_frame, test_mp3};
+
+    /// Minimal MP3 holding `frames` after the title, written to a temp file
+    fn tagged_file(name: &str, frames: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, test_mp3(frames)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_comment_replaces_other_languages() {
+        let mut frames = id3_frame("COMM", b"\x00XXX\x00MixtapeMonkey.com");
+        frames.extend(id3_frame("COMM", b"\x00\x00\x00\x00\x00old"));
+        frames.extend(id3_frame("COMM", b"\x00engiTunNORM\x00 0000"));
+        let path = tagged_file("music-agent-id3-comment-test.mp3", &frames);
+
+        let mut metadata = Id3Backend.read(&path).unwrap();
+        metadata.set_field("comment", "Pro Era");
+        Id3Backend.write(&path, &metadata).unwrap();
+        let tag = Tag::read_from_path(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let comments: Vec<(&str, &str)> = tag
+            .comments()
+            .map(|c| (c.description.as_str(), c.text.as_str()))
+            .collect();
+        assert_eq!(comments, vec![("iTunNORM", " 0000"), ("", "Pro Era")]);
+    }
+
+    #[test]
+    fn test_custom_frames_survive_rewrite() {
+        // ID3v2.3 tag as written by other tools: a Latin-1 TXXX with a trailing NUL,
+        // a UTF-16 TXXX, PRIV and GEOB frames
+        let gain = b"\x00REPLAYGAIN_TRACK_GAIN\x00-6.20 dB\x00".to_vec();
+        let mut mbid = b"\x01\xff\xfeM\x00B\x00\x00\x00\xff\xfe".to_vec();
+        mbid.extend_from_slice(b"1\x002\x003\x00");
+        let private = b"Serato\x00\x01\x02\x03".to_vec();
+        let object = b"\x00application/octet-stream\x00\x00Serato Markers_\x00\x01\x01".to_vec();
+
+        let mut frames = Vec::new();
+        for (id, body) in [
+            ("TXXX", &gain),
+            ("TXXX", &mbid),
+            ("PRIV", &private),
+            ("GEOB", &object),
+        ] {
+            frames.extend(id3_frame(id, body));
+        }
+        let path = tagged_file("music-agent-id3-custom-test.mp3", &frames);
+
+        let mut metadata = Id3Backend.read(&path).unwrap();
+        assert_eq!(metadata.custom["REPLAYGAIN_TRACK_GAIN"], "-6.20 dB");
+        assert_eq!(metadata.custom["MB"], "123");
+
+        metadata.set_field("artist", "Joey Bada$$; Capital STEEZ");
+        metadata.set_field("txxx:mb", "456");
+        Id3Backend.write(&path, &metadata).unwrap();
+
+        let written = id3v2::raw_frames(&fs::read(&path).unwrap()).unwrap();
+        let reread = Id3Backend.read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        for (id, body) in [("TXXX", gain), ("PRIV", private), ("GEOB", object)] {
+            assert!(written.contains(&RawFrame {
+                id: id.to_string(),
+                body
+            }));
+        }
+        assert!(!written.iter().any(|f| f.body == mbid));
+        assert_eq!(reread.custom["MB"], "456");
+        assert_eq!(reread.artists, vec!["Joey Bada$$", "Capital STEEZ"]);
+    }
+}
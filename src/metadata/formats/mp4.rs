@@ -0,0 +1,851 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::artwork::{detect_mime, CoverArt, PictureInfo};
+use crate::metadata::formats::TagBackend;
+use crate::metadata::{custom_key, split_values, TrackMetadata};
+use id3::v1;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Atoms whose body is a list of child atoms
+const CONTAINERS: &[&[u8; 4]] = &[
+    b"moov", b"trak", b"mdia", b"minf", b"stbl", b"udta", b"ilst",
+];
+
+/// Text items in `ilst`, keyed by the TrackMetadata field they map to
+const TEXT_ITEMS: &[(&str, &[u8; 4])] = &[
+    ("title", b"\xa9nam"),
+    ("artist", b"\xa9ART"),
+    ("album_artist", b"aART"),
+    ("album", b"\xa9alb"),
+    ("year", b"\xa9day"),
+    ("genre", b"\xa9gen"),
+    ("composer", b"\xa9wrt"),
+    ("comment", b"\xa9cmt"),
+    ("artist_sort", b"soar"),
+    ("album_sort", b"soal"),
+];
+
+/// iTunes freeform (`----`) items, keyed by field
+const FREEFORM_ITEMS: &[(&str, &str)] = &[("isrc", "ISRC"), ("publisher", "LABEL")];
+const FREEFORM_MEAN: &str = "com.apple.iTunes";
+
+/// `data` atom type codes
+const TYPE_IMPLICIT: u32 = 0;
+const TYPE_UTF8: u32 = 1;
+const TYPE_GIF: u32 = 12;
+const TYPE_JPEG: u32 = 13;
+const TYPE_PNG: u32 = 14;
+const TYPE_INTEGER: u32 = 21;
+
+/// iTunes-style `ilst` atoms in MP4/M4A files
+pub struct Mp4Backend;
+
+impl TagBackend for Mp4Backend {
+    fn name(&self) -> &str {
+        "MP4 ilst"
+    }
+
+    fn read(&self, path: &Path) -> Result<TrackMetadata> {
+        let data = fs::read(path)?;
+        let moov = find_moov(&data)
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+        let moov = Atom::parse_container(b"moov", &data[moov.body.clone()])
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+
+        let mut metadata = match moov.find_path(&[b"udta", b"meta", b"ilst"]) {
+            Some(ilst) => TrackMetadata {
+                tag_types: vec!["MP4 ilst".to_string()],
+                ..ilst_to_metadata(ilst)
+            },
+            None => TrackMetadata::default(),
+        };
+        metadata.file_path = path.to_string_lossy().to_string();
+        metadata.duration_seconds = moov.child(b"mvhd").and_then(|m| mvhd_duration(&m.body));
+        Ok(metadata)
+    }
+
+    fn write(&self, path: &Path, metadata: &TrackMetadata) -> Result<()> {
+        rewrite_ilst(path, |ilst| apply_metadata(ilst, metadata))
+    }
+
+    fn remove_fields(&self, path: &Path, fields: &[String]) -> Result<()> {
+        rewrite_ilst(path, |ilst| remove_fields(ilst, fields))
+    }
+
+    /// Replace the covr item with `art`, skipping the write if it's already embedded
+    ///
+    /// covr images carry no picture type, so the whole item is replaced.
+    fn embed_cover(&self, path: &Path, art: &CoverArt) -> Result<()> {
+        if self
+            .extract_pictures(path)?
+            .iter()
+            .any(|(_, data)| *data == art.data)
+        {
+            return Ok(());
+        }
+
+        let type_code = match art.mime_type.as_str() {
+            "image/jpeg" => TYPE_JPEG,
+            "image/png" => TYPE_PNG,
+            "image/gif" => TYPE_GIF,
+            other => {
+                return Err(AgentError::MetadataParse(format!(
+                    "Cannot embed {} cover art in MP4 tags",
+                    other
+                )))
+            }
+        };
+        rewrite_ilst(path, |ilst| set_item(ilst, b"covr", type_code, &art.data))
+    }
+
+    fn extract_pictures(&self, path: &Path) -> Result<Vec<(PictureInfo, Vec<u8>)>> {
+        let data = fs::read(path)?;
+        let moov = find_moov(&data)
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+        let moov = Atom::parse_container(b"moov", &data[moov.body.clone()])
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+        Ok(moov
+            .find_path(&[b"udta", b"meta", b"ilst"])
+            .map(cover_images)
+            .unwrap_or_default())
+    }
+
+    /// The bodies of the mdat atoms; moov (with the tags) may move but they don't change
+    fn audio_ranges(&self, data: &[u8]) -> std::result::Result<Vec<Range<usize>>, String> {
+        find_moov(data)?;
+        let mut ranges = Vec::new();
+        for location in top_level_atoms(data) {
+            let location = location?;
+            if &location.kind == b"mdat" {
+                ranges.push(location.body);
+            }
+        }
+        Ok(ranges)
+    }
+}
+
+/// Modify the ilst atom (creating it if needed) and write the file back
+fn rewrite_ilst(path: &Path, change: impl FnOnce(&mut Atom)) -> Result<()> {
+    let data = fs::read(path)?;
+    let location = find_moov(&data)
+        .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+    let mut moov = Atom::parse_container(b"moov", &data[location.body.clone()])
+        .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+
+    change(ilst_mut(&mut moov));
+
+    // Audio chunk offsets are absolute, so they move when moov grows or shrinks
+    // in front of mdat
+    let old_len = location.end - location.start;
+    let delta = moov.to_bytes().len() as i64 - old_len as i64;
+    if delta != 0 {
+        shift_chunk_offsets(&mut moov, location.start as u64, delta)?;
+    }
+
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..location.start]);
+    out.extend_from_slice(&moov.to_bytes());
+    out.extend_from_slice(&data[location.end..]);
+    fs::write(path, out)?;
+    Ok(())
+}
+
+/// An MP4 atom: leaf atoms keep their raw body, containers their children
+#[derive(Debug, Clone)]
+struct Atom {
+    kind: [u8; 4],
+    body: Vec<u8>,
+    children: Option<Vec<Atom>>,
+}
+
+impl Atom {
+    fn leaf(kind: &[u8; 4], body: Vec<u8>) -> Self {
+        Self {
+            kind: *kind,
+            body,
+            children: None,
+        }
+    }
+
+    fn container(kind: &[u8; 4], children: Vec<Atom>) -> Self {
+        Self {
+            kind: *kind,
+            body: Vec::new(),
+            children: Some(children),
+        }
+    }
+
+    /// Parse a container body; `meta` keeps its 4 version/flag bytes in `body`,
+    /// and ilst items are containers of `data`/`mean`/`name` atoms
+    fn parse_container(kind: &[u8; 4], data: &[u8]) -> std::result::Result<Self, String> {
+        let (prefix, data) = if kind == b"meta" {
+            let prefix = data.get(..4).ok_or("truncated meta atom")?;
+            (prefix.to_vec(), &data[4..])
+        } else {
+            (Vec::new(), data)
+        };
+
+        let mut children = Vec::new();
+        let mut offset = 0;
+        while offset + 8 <= data.len() {
+            let size = read_u32(data, offset) as usize;
+            let child_kind: [u8; 4] = data[offset + 4..offset + 8].try_into().unwrap();
+            if size < 8 || offset + size > data.len() {
+                return Err(format!(
+                    "invalid size for atom '{}'",
+                    String::from_utf8_lossy(&child_kind)
+                ));
+            }
+
+            let body = &data[offset + 8..offset + size];
+            let is_container =
+                CONTAINERS.contains(&&child_kind) || &child_kind == b"meta" || kind == b"ilst";
+            children.push(if is_container {
+                Self::parse_container(&child_kind, body)?
+            } else {
+                Self::leaf(&child_kind, body.to_vec())
+            });
+            offset += size;
+        }
+
+        Ok(Self {
+            kind: *kind,
+            body: prefix,
+            children: Some(children),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut body = self.body.clone();
+        for child in self.children.iter().flatten() {
+            body.extend_from_slice(&child.to_bytes());
+        }
+
+        let mut out = Vec::with_capacity(body.len() + 8);
+        out.extend_from_slice(&(body.len() as u32 + 8).to_be_bytes());
+        out.extend_from_slice(&self.kind);
+        out.extend_from_slice(&body);
+        out
+    }
+
+    fn child(&self, kind: &[u8; 4]) -> Option<&Atom> {
+        self.children.as_ref()?.iter().find(|c| &c.kind == kind)
+    }
+
+    fn find_path(&self, path: &[&[u8; 4]]) -> Option<&Atom> {
+        path.iter().try_fold(self, |atom, kind| atom.child(kind))
+    }
+
+    /// Child atom with this kind, created (empty) if missing
+    fn child_mut_or_insert(&mut self, kind: &[u8; 4], make: impl FnOnce() -> Atom) -> &mut Atom {
+        let children = self.children.get_or_insert_with(Vec::new);
+        let index = match children.iter().position(|c| &c.kind == kind) {
+            Some(index) => index,
+            None => {
+                children.push(make());
+                children.len() - 1
+            }
+        };
+        &mut children[index]
+    }
+}
+
+/// Byte range of a top-level atom and of its body
+struct AtomLocation {
+    kind: [u8; 4],
+    start: usize,
+    end: usize,
+    body: Range<usize>,
+}
+
+/// Walk the top-level atoms of an MP4 file
+fn top_level_atoms(
+    data: &[u8],
+) -> impl Iterator<Item = std::result::Result<AtomLocation, String>> + '_ {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        if offset + 8 > data.len() {
+            return None;
+        }
+        let mut size = read_u32(data, offset) as u64;
+        let mut header = 8;
+        if size == 1 {
+            // 64-bit size follows the type
+            let Some(bytes) = data.get(offset + 8..offset + 16) else {
+                offset = data.len();
+                return Some(Err("truncated atom".to_string()));
+            };
+            size = u64::from_be_bytes(bytes.try_into().unwrap());
+            header = 16;
+        } else if size == 0 {
+            size = (data.len() - offset) as u64;
+        }
+        let end = offset + size as usize;
+        if size < header as u64 || end > data.len() {
+            offset = data.len();
+            return Some(Err("invalid top-level atom size".to_string()));
+        }
+
+        let location = AtomLocation {
+            kind: data[offset + 4..offset + 8].try_into().unwrap(),
+            start: offset,
+            end,
+            body: offset + header..end,
+        };
+        offset = end;
+        Some(Ok(location))
+    })
+}
+
+fn find_moov(data: &[u8]) -> std::result::Result<AtomLocation, String> {
+    if data.get(4..8) != Some(b"ftyp") {
+        return Err("not an MP4 file (missing ftyp atom)".to_string());
+    }
+
+    for location in top_level_atoms(data) {
+        let location = location?;
+        if &location.kind == b"moov" {
+            return Ok(location);
+        }
+    }
+
+    Err("no moov atom found".to_string())
+}
+
+/// Parsed `data` atom of an ilst item: (type code, value bytes)
+fn item_data(item: &Atom) -> Option<(u32, &[u8])> {
+    let data = item.child(b"data")?;
+    if data.body.len() < 8 {
+        return None;
+    }
+    let type_code = read_u32(&data.body, 0) & 0x00ff_ffff;
+    Some((type_code, &data.body[8..]))
+}
+
+fn item_text(ilst: &Atom, kind: &[u8; 4]) -> Option<String> {
+    let (_, value) = item_data(ilst.child(kind)?)?;
+    let text = String::from_utf8_lossy(value).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Text of every data atom in an item (multi-value items hold one per value)
+fn item_texts(ilst: &Atom, kind: &[u8; 4]) -> Vec<String> {
+    let Some(item) = ilst.child(kind) else {
+        return Vec::new();
+    };
+    let texts: Vec<String> = item
+        .children
+        .iter()
+        .flatten()
+        .filter(|atom| &atom.kind == b"data" && atom.body.len() >= 8)
+        .map(|atom| String::from_utf8_lossy(&atom.body[8..]).to_string())
+        .collect();
+    split_values(&texts.join("\0"))
+}
+
+fn item_integer(ilst: &Atom, kind: &[u8; 4]) -> Option<u32> {
+    let (_, value) = item_data(ilst.child(kind)?)?;
+    value
+        .iter()
+        .take(4)
+        .try_fold(0u32, |acc, b| Some((acc << 8) | u32::from(*b)))
+        .filter(|_| !value.is_empty())
+}
+
+/// (number, total) from a trkn/disk item
+fn item_pair(ilst: &Atom, kind: &[u8; 4]) -> (Option<u32>, Option<u32>) {
+    let Some((_, value)) = ilst.child(kind).and_then(item_data) else {
+        return (None, None);
+    };
+    let get = |at: usize| {
+        value
+            .get(at..at + 2)
+            .map(|b| u32::from(u16::from_be_bytes([b[0], b[1]])))
+            .filter(|n| *n > 0)
+    };
+    (get(2), get(4))
+}
+
+/// Freeform `----` item value by name (e.g. "ISRC")
+fn freeform_text(ilst: &Atom, name: &str) -> Option<String> {
+    ilst.children.iter().flatten().find_map(|item| {
+        if &item.kind != b"----" || freeform_name(item)? != name {
+            return None;
+        }
+        let (_, value) = item_data(item)?;
+        Some(String::from_utf8_lossy(value).trim().to_string()).filter(|t| !t.is_empty())
+    })
+}
+
+fn freeform_name(item: &Atom) -> Option<String> {
+    let name = item.child(b"name")?;
+    name.body
+        .get(4..)
+        .map(|b| String::from_utf8_lossy(b).to_string())
+}
+
+/// Images in the covr item (one data atom each) with their summaries
+fn cover_images(ilst: &Atom) -> Vec<(PictureInfo, Vec<u8>)> {
+    let Some(covr) = ilst.child(b"covr") else {
+        return Vec::new();
+    };
+    covr.children
+        .iter()
+        .flatten()
+        .filter(|atom| &atom.kind == b"data" && atom.body.len() > 8)
+        .map(|atom| {
+            let image = atom.body[8..].to_vec();
+            let mime_type = match read_u32(&atom.body, 0) & 0x00ff_ffff {
+                TYPE_JPEG => "image/jpeg",
+                TYPE_PNG => "image/png",
+                TYPE_GIF => "image/gif",
+                _ => detect_mime(&image).unwrap_or("application/octet-stream"),
+            };
+            (
+                PictureInfo::new("Front cover", mime_type, "", &image),
+                image,
+            )
+        })
+        .collect()
+}
+
+/// Genre from a gnre item, which holds an ID3v1 genre index plus one
+fn standard_genre(ilst: &Atom) -> Option<String> {
+    let index = item_integer(ilst, b"gnre")?.checked_sub(1)?;
+    let tag = v1::Tag {
+        genre_id: u8::try_from(index).ok()?,
+        ..Default::default()
+    };
+    tag.genre().map(|g| g.to_string())
+}
+
+fn ilst_to_metadata(ilst: &Atom) -> TrackMetadata {
+    let text = |field: &str| {
+        TEXT_ITEMS
+            .iter()
+            .find(|(name, _)| *name == field)
+            .and_then(|(_, kind)| item_text(ilst, kind))
+    };
+    let freeform = |field: &str| {
+        FREEFORM_ITEMS
+            .iter()
+            .find(|(name, _)| *name == field)
+            .and_then(|(_, name)| freeform_text(ilst, name))
+    };
+    let (track_number, total_tracks) = item_pair(ilst, b"trkn");
+    let (disc_number, total_discs) = item_pair(ilst, b"disk");
+    let mut genres = item_texts(ilst, b"\xa9gen");
+    if genres.is_empty() {
+        genres.extend(standard_genre(ilst));
+    }
+
+    TrackMetadata {
+        artists: item_texts(ilst, b"\xa9ART"),
+        title: text("title"),
+        album: text("album"),
+        // ©day is usually a full timestamp ("1970-11-01T08:00:00Z")
+        year: text("year").and_then(|d| d.get(..4).and_then(|y| y.parse().ok())),
+        genres,
+        track_number,
+        album_artist: text("album_artist"),
+        total_tracks,
+        disc_number,
+        total_discs,
+        composer: text("composer"),
+        comment: text("comment"),
+        bpm: item_integer(ilst, b"tmpo").filter(|b| *b > 0),
+        publisher: freeform("publisher"),
+        isrc: freeform("isrc"),
+        compilation: item_integer(ilst, b"cpil").map(|c| c != 0),
+        artist_sort: text("artist_sort"),
+        album_sort: text("album_sort"),
+        pictures: cover_images(ilst)
+            .into_iter()
+            .map(|(info, _)| info)
+            .collect(),
+        // Other freeform items (iTunNORM, MusicBrainz IDs, ...) are custom tags
+        custom: ilst
+            .children
+            .iter()
+            .flatten()
+            .filter(|item| &item.kind == b"----")
+            .filter_map(freeform_name)
+            .filter(|name| !FREEFORM_ITEMS.iter().any(|(_, n)| n == name))
+            .filter_map(|name| freeform_text(ilst, &name).map(|value| (name, value)))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// The ilst atom, creating udta/meta/hdlr/ilst if the file has no tags yet
+fn ilst_mut(moov: &mut Atom) -> &mut Atom {
+    let udta = moov.child_mut_or_insert(b"udta", || Atom::container(b"udta", Vec::new()));
+    let meta = udta.child_mut_or_insert(b"meta", || {
+        let mut handler = vec![0u8; 8];
+        handler.extend_from_slice(b"mdirappl");
+        handler.extend_from_slice(&[0u8; 9]);
+        let mut meta = Atom::container(b"meta", vec![Atom::leaf(b"hdlr", handler)]);
+        meta.body = vec![0; 4];
+        meta
+    });
+    meta.child_mut_or_insert(b"ilst", || Atom::container(b"ilst", Vec::new()))
+}
+
+fn data_atom(type_code: u32, value: &[u8]) -> Atom {
+    let mut body = type_code.to_be_bytes().to_vec();
+    body.extend_from_slice(&[0; 4]);
+    body.extend_from_slice(value);
+    Atom::leaf(b"data", body)
+}
+
+/// Replace (or add) an ilst item holding a single data atom
+fn set_item(ilst: &mut Atom, kind: &[u8; 4], type_code: u32, value: &[u8]) {
+    set_item_values(ilst, kind, type_code, &[value]);
+}
+
+/// Replace (or add) an ilst item holding one data atom per value
+fn set_item_values(ilst: &mut Atom, kind: &[u8; 4], type_code: u32, values: &[&[u8]]) {
+    let data = values.iter().map(|v| data_atom(type_code, v)).collect();
+    let item = Atom::container(kind, data);
+    let children = ilst.children.get_or_insert_with(Vec::new);
+    match children.iter_mut().find(|c| &c.kind == kind) {
+        Some(existing) => *existing = item,
+        None => children.push(item),
+    }
+}
+
+fn remove_item(ilst: &mut Atom, kind: &[u8; 4]) {
+    if let Some(children) = ilst.children.as_mut() {
+        children.retain(|item| &item.kind != kind);
+    }
+}
+
+fn set_freeform(ilst: &mut Atom, name: &str, value: &str) {
+    let with_version = |text: &str| {
+        let mut body = vec![0; 4];
+        body.extend_from_slice(text.as_bytes());
+        body
+    };
+    let item = Atom::container(
+        b"----",
+        vec![
+            Atom::leaf(b"mean", with_version(FREEFORM_MEAN)),
+            Atom::leaf(b"name", with_version(name)),
+            data_atom(TYPE_UTF8, value.as_bytes()),
+        ],
+    );
+
+    let children = ilst.children.get_or_insert_with(Vec::new);
+    match children
+        .iter_mut()
+        .find(|c| &c.kind == b"----" && freeform_name(c).as_deref() == Some(name))
+    {
+        Some(existing) => *existing = item,
+        None => children.push(item),
+    }
+}
+
+fn pair_value(number: u32, total: Option<u32>, trailing: usize) -> Vec<u8> {
+    let mut value = vec![0; 2];
+    value.extend_from_slice(&(number.min(u16::MAX as u32) as u16).to_be_bytes());
+    value.extend_from_slice(&(total.unwrap_or(0).min(u16::MAX as u32) as u16).to_be_bytes());
+    value.extend(std::iter::repeat_n(0, trailing));
+    value
+}
+
+/// Write every field that is set in `metadata`; other items (e.g. covr) are kept
+fn apply_metadata(ilst: &mut Atom, metadata: &TrackMetadata) {
+    for (field, kind) in TEXT_ITEMS {
+        let values = metadata.field_values(field);
+        let Some(value) = values.first() else {
+            continue;
+        };
+        // Don't truncate a full ©day timestamp that already has this year
+        if *field == "year" && item_text(ilst, kind).is_some_and(|d| d.starts_with(value)) {
+            continue;
+        }
+        let values: Vec<&[u8]> = values.iter().map(|v| v.as_bytes()).collect();
+        set_item_values(ilst, kind, TYPE_UTF8, &values);
+        // A leftover gnre would still give players that read it the old genre
+        if *field == "genre" {
+            remove_item(ilst, b"gnre");
+        }
+    }
+
+    for (field, name) in FREEFORM_ITEMS {
+        if let Some(value) = metadata.field_value(field) {
+            set_freeform(ilst, name, &value);
+        }
+    }
+    for (name, value) in &metadata.custom {
+        if freeform_text(ilst, name).as_ref() != Some(value) {
+            set_freeform(ilst, name, value);
+        }
+    }
+
+    if let Some(track) = metadata.track_number {
+        set_item(
+            ilst,
+            b"trkn",
+            TYPE_IMPLICIT,
+            &pair_value(track, metadata.total_tracks, 2),
+        );
+    }
+    if let Some(disc) = metadata.disc_number {
+        set_item(
+            ilst,
+            b"disk",
+            TYPE_IMPLICIT,
+            &pair_value(disc, metadata.total_discs, 0),
+        );
+    }
+    if let Some(bpm) = metadata.bpm {
+        let bpm = bpm.min(u16::MAX as u32) as u16;
+        set_item(ilst, b"tmpo", TYPE_INTEGER, &bpm.to_be_bytes());
+    }
+    if let Some(compilation) = metadata.compilation {
+        set_item(ilst, b"cpil", TYPE_INTEGER, &[u8::from(compilation)]);
+    }
+}
+
+/// Delete the items for the given fields; totals are zeroed inside trkn/disk
+fn remove_fields(ilst: &mut Atom, fields: &[String]) {
+    let (track, _) = item_pair(ilst, b"trkn");
+    let (disc, _) = item_pair(ilst, b"disk");
+
+    for field in fields {
+        let field = field.as_str();
+        let kind = match field {
+            "track_number" => Some(b"trkn"),
+            "disc_number" => Some(b"disk"),
+            "bpm" => Some(b"tmpo"),
+            "compilation" => Some(b"cpil"),
+            _ => TEXT_ITEMS
+                .iter()
+                .find(|(name, _)| *name == field)
+                .map(|(_, kind)| *kind),
+        };
+        let freeform = FREEFORM_ITEMS
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, name)| *name)
+            .or(custom_key(field));
+        let is_freeform = |item: &Atom| {
+            freeform.is_some_and(|name| {
+                &item.kind == b"----"
+                    && freeform_name(item).is_some_and(|n| n.eq_ignore_ascii_case(name))
+            })
+        };
+
+        if let Some(children) = ilst.children.as_mut() {
+            children.retain(|item| Some(&item.kind) != kind && !is_freeform(item));
+        }
+        if field == "genre" {
+            remove_item(ilst, b"gnre");
+        }
+
+        match (field, track, disc) {
+            ("total_tracks", Some(track), _) => {
+                set_item(ilst, b"trkn", TYPE_IMPLICIT, &pair_value(track, None, 2))
+            }
+            ("total_discs", _, Some(disc)) => {
+                set_item(ilst, b"disk", TYPE_IMPLICIT, &pair_value(disc, None, 0))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Shift stco/co64 entries that point past the moov atom by `delta` bytes
+fn shift_chunk_offsets(atom: &mut Atom, moov_start: u64, delta: i64) -> Result<()> {
+    let shift = |offset: u64| -> Result<u64> {
+        if offset <= moov_start {
+            return Ok(offset);
+        }
+        offset
+            .checked_add_signed(delta)
+            .ok_or_else(|| AgentError::MetadataParse("Chunk offset out of range".to_string()))
+    };
+
+    match &atom.kind {
+        b"stco" if atom.body.len() >= 8 => {
+            let count = read_u32(&atom.body, 4) as usize;
+            for i in 0..count.min((atom.body.len() - 8) / 4) {
+                let at = 8 + i * 4;
+                let offset = shift(u64::from(read_u32(&atom.body, at)))?;
+                let offset = u32::try_from(offset).map_err(|_| {
+                    AgentError::MetadataParse("Chunk offset exceeds 32 bits".to_string())
+                })?;
+                atom.body[at..at + 4].copy_from_slice(&offset.to_be_bytes());
+            }
+        }
+        b"co64" if atom.body.len() >= 8 => {
+            let count = read_u32(&atom.body, 4) as usize;
+            for i in 0..count.min((atom.body.len() - 8) / 8) {
+                let at = 8 + i * 8;
+                let offset = u64::from_be_bytes(atom.body[at..at + 8].try_into().unwrap());
+                atom.body[at..at + 8].copy_from_slice(&shift(offset)?.to_be_bytes());
+            }
+        }
+        _ => {}
+    }
+
+    for child in atom.children.iter_mut().flatten() {
+        shift_chunk_offsets(child, moov_start, delta)?;
+    }
+    Ok(())
+}
+
+/// Duration from the movie header's timescale and duration
+fn mvhd_duration(body: &[u8]) -> Option<u32> {
+    let (timescale, duration) = match body.first()? {
+        0 if body.len() >= 20 => (read_u32(body, 12), u64::from(read_u32(body, 16))),
+        1 if body.len() >= 32 => (
+            read_u32(body, 20),
+            u64::from_be_bytes(body[24..32].try_into().ok()?),
+        ),
+        _ => return None,
+    };
+    if timescale == 0 || duration == 0 {
+        return None;
+    }
+    Some((duration / u64::from(timescale)) as u32)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atom_bytes(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = (body.len() as u32 + 8).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// ftyp + untagged moov (60 s mvhd, one stco entry) + mdat
+    fn sample_m4a() -> Vec<u8> {
+        let ftyp = atom_bytes(b"ftyp", b"M4A \0\0\0\0M4A mp42");
+
+        let mut mvhd = vec![0u8; 100];
+        mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&60_000u32.to_be_bytes());
+
+        let build_moov = |chunk_offset: u32| {
+            let mut stco = vec![0, 0, 0, 0, 0, 0, 0, 1];
+            stco.extend_from_slice(&chunk_offset.to_be_bytes());
+            let stbl = atom_bytes(b"stbl", &atom_bytes(b"stco", &stco));
+            let minf = atom_bytes(b"minf", &stbl);
+            let mdia = atom_bytes(b"mdia", &minf);
+            let trak = atom_bytes(b"trak", &mdia);
+            let mut body = atom_bytes(b"mvhd", &mvhd);
+            body.extend_from_slice(&trak);
+            atom_bytes(b"moov", &body)
+        };
+
+        // Audio starts after ftyp, moov and the mdat header
+        let moov_len = build_moov(0).len();
+        let moov = build_moov((ftyp.len() + moov_len + 8) as u32);
+
+        let mut data = ftyp;
+        data.extend_from_slice(&moov);
+        data.extend_from_slice(&atom_bytes(b"mdat", b"AUDIO"));
+        data
+    }
+
+    fn chunk_offset(data: &[u8]) -> usize {
+        let moov = find_moov(data).unwrap();
+        let moov = Atom::parse_container(b"moov", &data[moov.body]).unwrap();
+        let stco = moov
+            .find_path(&[b"trak", b"mdia", b"minf", b"stbl", b"stco"])
+            .unwrap();
+        read_u32(&stco.body, 8) as usize
+    }
+
+    #[test]
+    fn test_mp4_write_read_round_trip() {
+        let path = std::env::temp_dir().join("music-agent-mp4-test.m4a");
+        fs::write(&path, sample_m4a()).unwrap();
+
+        let mut metadata = Mp4Backend.read(&path).unwrap();
+        assert_eq!(metadata.duration_seconds, Some(60));
+        assert!(metadata.title.is_none());
+
+        metadata.title = Some("Rhinestone Cowboy".to_string());
+        metadata.set_field("artist", "Madvillain; MF DOOM");
+        metadata.track_number = Some(3);
+        metadata.total_tracks = Some(22);
+        metadata.isrc = Some("USSTO0400103".to_string());
+        metadata.compilation = Some(false);
+        Mp4Backend.write(&path, &metadata).unwrap();
+
+        let written = fs::read(&path).unwrap();
+        let reread = Mp4Backend.read(&path).unwrap();
+        let fields = ["isrc".to_string(), "total_tracks".to_string()];
+        Mp4Backend.remove_fields(&path, &fields).unwrap();
+        let removed = Mp4Backend.read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reread.title.as_deref(), Some("Rhinestone Cowboy"));
+        assert_eq!(reread.artists, vec!["Madvillain", "MF DOOM"]);
+        assert_eq!(reread.track_number, Some(3));
+        assert_eq!(reread.total_tracks, Some(22));
+        assert_eq!(reread.isrc.as_deref(), Some("USSTO0400103"));
+        assert_eq!(reread.compilation, Some(false));
+        assert_eq!(removed.isrc, None);
+        assert_eq!(removed.total_tracks, None);
+        assert_eq!(removed.track_number, Some(3));
+        assert_eq!(removed.title.as_deref(), Some("Rhinestone Cowboy"));
+        // The moov grew, so the chunk offset must follow the audio
+        let offset = chunk_offset(&written);
+        assert_eq!(&written[offset..offset + 5], b"AUDIO");
+        let original = sample_m4a();
+        let mdat = |data: &[u8]| Mp4Backend.audio_ranges(data).unwrap()[0].clone();
+        assert_eq!(written[mdat(&written)], original[mdat(&original)]);
+    }
+
+    #[test]
+    fn test_mp4_cover_and_standard_genre() {
+        let path = std::env::temp_dir().join("music-agent-mp4-covr-test.m4a");
+        fs::write(&path, sample_m4a()).unwrap();
+        // gnre 18 is ID3v1 genre 17
+        rewrite_ilst(&path, |ilst| {
+            set_item(ilst, b"gnre", TYPE_IMPLICIT, &[0, 18])
+        })
+        .unwrap();
+        let standard = Mp4Backend.read(&path).unwrap();
+
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 2, 0, 0, 0, 2, 0]);
+        let art = CoverArt {
+            mime_type: "image/png".to_string(),
+            data: png.clone(),
+        };
+        Mp4Backend.embed_cover(&path, &art).unwrap();
+        let mut metadata = Mp4Backend.read(&path).unwrap();
+        let pictures = Mp4Backend.extract_pictures(&path).unwrap();
+
+        metadata.set_field("genre", "Hip-Hop");
+        Mp4Backend.write(&path, &metadata).unwrap();
+        let written = fs::read(&path).unwrap();
+        let reread = Mp4Backend.read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(standard.genres, vec!["Rock"]);
+        assert_eq!(metadata.pictures, vec![art.info()]);
+        assert_eq!(pictures, vec![(art.info(), png)]);
+        assert_eq!(reread.genres, vec!["Hip-Hop"]);
+        assert_eq!(reread.pictures, vec![art.info()]);
+        let moov = find_moov(&written).unwrap();
+        let moov = Atom::parse_container(b"moov", &written[moov.body]).unwrap();
+        let ilst = moov.find_path(&[b"udta", b"meta", b"ilst"]).unwrap();
+        assert!(ilst.child(b"gnre").is_none());
+        let offset = chunk_offset(&written);
+        assert_eq!(&written[offset..offset + 5], b"AUDIO");
+    }
+}
@@ -0,0 +1,364 @@
+use crate::metadata::audio::{AudioProperties, ChannelMode};
+use crate::metadata::formats::ape::ApeTag;
+use crate::metadata::formats::id3v2_size;
+
+/// Bitrates in kbps by [table][index]; index 0 ("free") and 15 are invalid
+const BITRATES: [[u32; 15]; 5] = [
+    // MPEG-1 Layer I
+    [
+        0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448,
+    ],
+    // MPEG-1 Layer II
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384,
+    ],
+    // MPEG-1 Layer III
+    [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ],
+    // MPEG-2/2.5 Layer I
+    [
+        0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256,
+    ],
+    // MPEG-2/2.5 Layer II & III
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Version {
+    Mpeg1,
+    Mpeg2,
+    Mpeg25,
+}
+
+/// A decoded 4-byte frame header
+#[derive(Debug, Clone, Copy)]
+struct FrameHeader {
+    version: Version,
+    layer: u8,
+    bitrate_kbps: u32,
+    sample_rate: u32,
+    padding: bool,
+    channel_mode: ChannelMode,
+}
+
+impl FrameHeader {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 4 || bytes[0] != 0xff || bytes[1] & 0xe0 != 0xe0 {
+            return None;
+        }
+
+        let version = match (bytes[1] >> 3) & 0x03 {
+            0 => Version::Mpeg25,
+            2 => Version::Mpeg2,
+            3 => Version::Mpeg1,
+            _ => return None,
+        };
+        let layer = match (bytes[1] >> 1) & 0x03 {
+            1 => 3,
+            2 => 2,
+            3 => 1,
+            _ => return None,
+        };
+
+        let table = match (version, layer) {
+            (Version::Mpeg1, layer) => layer as usize - 1,
+            (_, 1) => 3,
+            _ => 4,
+        };
+        let bitrate_index = (bytes[2] >> 4) as usize;
+        let bitrate_kbps = *BITRATES[table].get(bitrate_index)?;
+        if bitrate_kbps == 0 {
+            return None;
+        }
+
+        let base_rate = match (bytes[2] >> 2) & 0x03 {
+            0 => 44_100,
+            1 => 48_000,
+            2 => 32_000,
+            _ => return None,
+        };
+        let sample_rate = match version {
+            Version::Mpeg1 => base_rate,
+            Version::Mpeg2 => base_rate / 2,
+            Version::Mpeg25 => base_rate / 4,
+        };
+
+        let channel_mode = match bytes[3] >> 6 {
+            0 => ChannelMode::Stereo,
+            1 => ChannelMode::JointStereo,
+            2 => ChannelMode::DualChannel,
+            _ => ChannelMode::Mono,
+        };
+
+        Some(Self {
+            version,
+            layer,
+            bitrate_kbps,
+            sample_rate,
+            padding: bytes[2] & 0x02 != 0,
+            channel_mode,
+        })
+    }
+
+    fn samples_per_frame(&self) -> u32 {
+        match (self.layer, self.version) {
+            (1, _) => 384,
+            (3, Version::Mpeg2 | Version::Mpeg25) => 576,
+            _ => 1152,
+        }
+    }
+
+    fn frame_len(&self) -> usize {
+        let padding = u32::from(self.padding);
+        let len = if self.layer == 1 {
+            (12 * self.bitrate_kbps * 1000 / self.sample_rate + padding) * 4
+        } else {
+            self.samples_per_frame() / 8 * self.bitrate_kbps * 1000 / self.sample_rate + padding
+        };
+        len as usize
+    }
+
+    /// Offset of a Xing/Info header from the start of the frame
+    fn xing_offset(&self) -> usize {
+        let side_info = match (self.version, self.channel_mode) {
+            (Version::Mpeg1, ChannelMode::Mono) => 17,
+            (Version::Mpeg1, _) => 32,
+            (_, ChannelMode::Mono) => 9,
+            _ => 17,
+        };
+        4 + side_info
+    }
+
+    fn codec(&self) -> String {
+        let version = match self.version {
+            Version::Mpeg1 => "MPEG-1",
+            Version::Mpeg2 => "MPEG-2",
+            Version::Mpeg25 => "MPEG-2.5",
+        };
+        let layer = match self.layer {
+            1 => "I",
+            2 => "II",
+            _ => "III",
+        };
+        format!("{} Layer {}", version, layer)
+    }
+}
+
+/// Frame count and stream size from a VBR header
+#[derive(Debug, Default)]
+struct VbrInfo {
+    frames: Option<u32>,
+    bytes: Option<u32>,
+    /// False for LAME's "Info" header, which marks a CBR stream
+    vbr: bool,
+    encoder: Option<String>,
+}
+
+/// Xing/Info header (optionally followed by a LAME tag) or Fraunhofer VBRI header
+fn parse_vbr_header(frame: &[u8], header: &FrameHeader) -> Option<VbrInfo> {
+    let be_u32 = |at: usize| {
+        frame
+            .get(at..at + 4)
+            .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+    };
+
+    let xing = header.xing_offset();
+    if let Some(tag @ (b"Xing" | b"Info")) = frame.get(xing..xing + 4) {
+        let flags = be_u32(xing + 4)?;
+        let mut at = xing + 8;
+        let mut info = VbrInfo {
+            vbr: tag == b"Xing",
+            ..Default::default()
+        };
+        if flags & 0x01 != 0 {
+            info.frames = be_u32(at);
+            at += 4;
+        }
+        if flags & 0x02 != 0 {
+            info.bytes = be_u32(at);
+            at += 4;
+        }
+        if flags & 0x04 != 0 {
+            at += 100; // seek table
+        }
+        if flags & 0x08 != 0 {
+            at += 4; // quality
+        }
+        info.encoder = frame
+            .get(at..at + 9)
+            .map(|b| {
+                String::from_utf8_lossy(b)
+                    .trim_end_matches('\0')
+                    .trim()
+                    .to_string()
+            })
+            .filter(|e| e.starts_with("LAME") || e.starts_with("Lavc") || e.starts_with("Lavf"));
+        return Some(info);
+    }
+
+    // VBRI sits 32 bytes after the frame header regardless of channel mode
+    if frame.get(36..40) == Some(b"VBRI") {
+        return Some(VbrInfo {
+            bytes: be_u32(46),
+            frames: be_u32(50),
+            vbr: true,
+            encoder: None,
+        });
+    }
+
+    None
+}
+
+/// Find the first frame header, requiring the following frame to line up too
+/// so stray 0xFF bytes in leftover tag data aren't mistaken for audio
+fn find_first_frame(data: &[u8], start: usize) -> Option<(usize, FrameHeader)> {
+    (start..data.len().saturating_sub(4)).find_map(|offset| {
+        let header = FrameHeader::parse(&data[offset..])?;
+        let next = offset + header.frame_len();
+        let confirmed = next + 4 > data.len()
+            || FrameHeader::parse(&data[next..])
+                .is_some_and(|h| h.version == header.version && h.layer == header.layer);
+        confirmed.then_some((offset, header))
+    })
+}
+
+/// True if `bytes` start with a valid MPEG audio frame header
+pub fn is_frame_header(bytes: &[u8]) -> bool {
+    FrameHeader::parse(bytes).is_some()
+}
+
+/// Offset of the first MPEG audio frame at or after `start`
+pub fn find_audio(data: &[u8], start: usize) -> Option<usize> {
+    find_first_frame(data, start).map(|(offset, _)| offset)
+}
+
+/// End of the audio data: before trailing APEv2 / ID3v1 tags, if any
+pub fn audio_end(data: &[u8]) -> usize {
+    if let Some(ape) = ApeTag::read(data) {
+        ape.range.start
+    } else if data.len() >= 128 && &data[data.len() - 128..data.len() - 125] == b"TAG" {
+        data.len() - 128
+    } else {
+        data.len()
+    }
+}
+
+/// Compute audio properties from the MPEG stream of an MP3 file
+///
+/// Uses the Xing/VBRI frame count when present; otherwise walks every frame,
+/// which is exact for both CBR and header-less VBR files.
+pub fn read_properties(data: &[u8]) -> Option<AudioProperties> {
+    let end = audio_end(data);
+    let data = &data[..end];
+    let (first, header) = find_first_frame(data, id3v2_size(data))?;
+    let vbr_info = parse_vbr_header(&data[first..], &header);
+
+    let (frames, bytes, vbr) = match vbr_info {
+        Some(VbrInfo {
+            frames: Some(frames),
+            ref bytes,
+            vbr,
+            ..
+        }) if frames > 0 => (
+            u64::from(frames),
+            bytes.map(u64::from).unwrap_or((end - first) as u64),
+            vbr,
+        ),
+        _ => {
+            // Count frames, skipping the Xing frame itself (it carries no audio)
+            let mut offset = first;
+            if vbr_info.is_some() {
+                offset += header.frame_len();
+            }
+            let audio_start = offset;
+            let mut frames = 0u64;
+            let mut bitrates = std::collections::HashSet::new();
+            while let Some(frame) = data.get(offset..).and_then(FrameHeader::parse) {
+                frames += 1;
+                bitrates.insert(frame.bitrate_kbps);
+                offset += frame.frame_len();
+            }
+            (
+                frames,
+                (offset.min(end) - audio_start) as u64,
+                bitrates.len() > 1,
+            )
+        }
+    };
+
+    if frames == 0 {
+        return None;
+    }
+
+    let duration_ms =
+        frames * u64::from(header.samples_per_frame()) * 1000 / u64::from(header.sample_rate);
+    let bitrate_kbps = if vbr && duration_ms > 0 {
+        (bytes * 8 / duration_ms) as u32
+    } else {
+        header.bitrate_kbps
+    };
+
+    Some(AudioProperties {
+        codec: header.codec(),
+        duration_ms,
+        bitrate_kbps,
+        sample_rate: header.sample_rate,
+        channel_mode: header.channel_mode,
+        vbr,
+        encoder: vbr_info.and_then(|v| v.encoder),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// MPEG-1 Layer III, 128 kbps, 44.1 kHz, joint stereo, no padding: 417 bytes
+    const HEADER: [u8; 4] = [0xff, 0xfb, 0x90, 0x44];
+
+    fn frame() -> Vec<u8> {
+        let mut frame = HEADER.to_vec();
+        frame.resize(417, 0);
+        frame
+    }
+
+    #[test]
+    fn test_cbr_frames_are_counted() {
+        // 10 s at 44.1 kHz is 382.8 frames of 1152 samples
+        let mut data = b"ID3\x04\x00\x00\x00\x00\x00\x0a".to_vec();
+        data.extend_from_slice(&[0; 10]);
+        for _ in 0..383 {
+            data.extend_from_slice(&frame());
+        }
+
+        let properties = read_properties(&data).unwrap();
+        assert_eq!(properties.codec, "MPEG-1 Layer III");
+        assert_eq!(properties.duration_seconds(), 10);
+        assert_eq!(properties.bitrate_kbps, 128);
+        assert_eq!(properties.sample_rate, 44_100);
+        assert_eq!(properties.channel_mode, ChannelMode::JointStereo);
+        assert!(!properties.vbr);
+    }
+
+    #[test]
+    fn test_xing_header_with_lame_tag() {
+        let mut xing = frame();
+        let at = 4 + 32;
+        xing[at..at + 4].copy_from_slice(b"Xing");
+        xing[at + 4..at + 8].copy_from_slice(&3u32.to_be_bytes());
+        xing[at + 8..at + 12].copy_from_slice(&9000u32.to_be_bytes());
+        xing[at + 12..at + 16].copy_from_slice(&(9000u32 * 300).to_be_bytes());
+        xing[at + 16..at + 25].copy_from_slice(b"LAME3.100");
+
+        let mut data = xing;
+        data.extend_from_slice(&frame());
+
+        let properties = read_properties(&data).unwrap();
+        // 9000 frames * 1152 / 44100 = 235.1 s
+        assert_eq!(properties.duration_seconds(), 235);
+        assert!(properties.vbr);
+        assert_eq!(properties.encoder.as_deref(), Some("LAME3.100"));
+        assert_eq!(properties.bitrate_kbps, 91);
+    }
+}
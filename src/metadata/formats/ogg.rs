@@ -0,0 +1,456 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::formats::vorbis::VorbisComments;
+use crate::metadata::formats::TagBackend;
+use crate::metadata::TrackMetadata;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+const CAPTURE_PATTERN: &[u8; 4] = b"OggS";
+const HEADER_LEN: usize = 27;
+const FLAG_CONTINUED: u8 = 0x01;
+/// Granule position for pages on which no packet ends
+const NO_GRANULE: u64 = u64::MAX;
+
+/// Vorbis comments in Ogg Vorbis and Ogg Opus files
+pub struct OggBackend;
+
+impl TagBackend for OggBackend {
+    fn name(&self) -> &str {
+        "Vorbis comment (Ogg)"
+    }
+
+    fn read(&self, path: &Path) -> Result<TrackMetadata> {
+        let data = fs::read(path)?;
+        let stream = OggStream::parse(&data)
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+
+        let mut metadata = stream.comments()?.to_metadata(&path.to_string_lossy());
+        metadata.tag_types.push("Vorbis comment".to_string());
+        metadata.duration_seconds = stream.duration_seconds(&data);
+        Ok(metadata)
+    }
+
+    fn write(&self, path: &Path, metadata: &TrackMetadata) -> Result<()> {
+        let data = fs::read(path)?;
+        let stream = OggStream::parse(&data)
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+
+        let mut comments = stream.comments()?;
+        comments.apply_metadata(metadata);
+
+        fs::write(path, stream.rewrite(&data, &comments))?;
+        Ok(())
+    }
+
+    fn remove_fields(&self, path: &Path, fields: &[String]) -> Result<()> {
+        let data = fs::read(path)?;
+        let stream = OggStream::parse(&data)
+            .map_err(|e| AgentError::MetadataParse(format!("{}: {}", path.display(), e)))?;
+
+        let mut comments = stream.comments()?;
+        comments.remove_fields(fields);
+
+        fs::write(path, stream.rewrite(&data, &comments))?;
+        Ok(())
+    }
+
+    /// The bodies of the audio pages; their headers are renumbered when the comment
+    /// header needs more or fewer pages
+    fn audio_ranges(&self, data: &[u8]) -> std::result::Result<Vec<Range<usize>>, String> {
+        let stream = OggStream::parse(data)?;
+        let mut ranges = Vec::new();
+        let mut offset = stream.headers_end;
+        while let Ok((page, len)) = Page::parse(data, offset) {
+            if page.serial == stream.serial {
+                ranges.push(offset + len - page.body.len()..offset + len);
+            }
+            offset += len;
+        }
+        // Trailing bytes that aren't pages are copied as they are
+        ranges.push(offset..data.len());
+        Ok(ranges)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Codec {
+    Vorbis,
+    Opus,
+}
+
+impl Codec {
+    fn detect(id_packet: &[u8]) -> Option<Self> {
+        if id_packet.starts_with(b"\x01vorbis") {
+            Some(Self::Vorbis)
+        } else if id_packet.starts_with(b"OpusHead") {
+            Some(Self::Opus)
+        } else {
+            None
+        }
+    }
+
+    /// Magic that starts the comment packet
+    fn comment_prefix(&self) -> &'static [u8] {
+        match self {
+            Self::Vorbis => b"\x03vorbis",
+            Self::Opus => b"OpusTags",
+        }
+    }
+
+    /// Identification + comment (+ setup for Vorbis)
+    fn header_packets(&self) -> usize {
+        match self {
+            Self::Vorbis => 3,
+            Self::Opus => 2,
+        }
+    }
+
+    /// Samples per second that granule positions count in
+    fn granule_rate(&self, id_packet: &[u8]) -> Option<u64> {
+        match self {
+            Self::Vorbis => id_packet
+                .get(12..16)
+                .map(|b| u64::from(u32::from_le_bytes(b.try_into().unwrap()))),
+            // Opus granules always count 48 kHz samples
+            Self::Opus => Some(48_000),
+        }
+    }
+}
+
+/// One Ogg page
+#[derive(Debug, Clone)]
+struct Page {
+    flags: u8,
+    granule: u64,
+    serial: u32,
+    sequence: u32,
+    lacing: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl Page {
+    /// Parse the page at `offset`, returning it and its total length
+    fn parse(data: &[u8], offset: usize) -> std::result::Result<(Self, usize), String> {
+        let header = data
+            .get(offset..offset + HEADER_LEN)
+            .ok_or("truncated Ogg page header")?;
+        if &header[..4] != CAPTURE_PATTERN {
+            return Err(format!("missing OggS capture pattern at byte {}", offset));
+        }
+
+        let segments = header[26] as usize;
+        let lacing = data
+            .get(offset + HEADER_LEN..offset + HEADER_LEN + segments)
+            .ok_or("truncated Ogg lacing table")?;
+        let body_len: usize = lacing.iter().map(|&l| l as usize).sum();
+        let body_start = offset + HEADER_LEN + segments;
+        let body = data
+            .get(body_start..body_start + body_len)
+            .ok_or("truncated Ogg page")?;
+
+        let page = Self {
+            flags: header[5],
+            granule: u64::from_le_bytes(header[6..14].try_into().unwrap()),
+            serial: u32::from_le_bytes(header[14..18].try_into().unwrap()),
+            sequence: u32::from_le_bytes(header[18..22].try_into().unwrap()),
+            lacing: lacing.to_vec(),
+            body: body.to_vec(),
+        };
+
+        let stored_crc = u32::from_le_bytes(header[22..26].try_into().unwrap());
+        if page.crc() != stored_crc {
+            return Err(format!("bad Ogg page checksum at byte {}", offset));
+        }
+
+        Ok((page, HEADER_LEN + segments + body_len))
+    }
+
+    fn to_bytes_with_crc(&self, crc: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.lacing.len() + self.body.len());
+        out.extend_from_slice(CAPTURE_PATTERN);
+        out.push(0); // stream structure version
+        out.push(self.flags);
+        out.extend_from_slice(&self.granule.to_le_bytes());
+        out.extend_from_slice(&self.serial.to_le_bytes());
+        out.extend_from_slice(&self.sequence.to_le_bytes());
+        out.extend_from_slice(&crc.to_le_bytes());
+        out.push(self.lacing.len() as u8);
+        out.extend_from_slice(&self.lacing);
+        out.extend_from_slice(&self.body);
+        out
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_crc(self.crc())
+    }
+
+    fn crc(&self) -> u32 {
+        ogg_crc(&self.to_bytes_with_crc(0))
+    }
+}
+
+/// Location of the header packets of the first logical stream
+#[derive(Debug)]
+struct OggStream {
+    codec: Codec,
+    serial: u32,
+    packets: Vec<Vec<u8>>,
+    first_page_len: usize,
+    /// Byte offset where the first audio page starts
+    headers_end: usize,
+    header_pages: usize,
+}
+
+impl OggStream {
+    fn parse(data: &[u8]) -> std::result::Result<Self, String> {
+        let mut offset = 0;
+        let mut packets: Vec<Vec<u8>> = Vec::new();
+        let mut partial: Vec<u8> = Vec::new();
+        let mut header_pages = 0;
+        let mut serial = None;
+        let mut codec = None;
+        let mut first_page_len = 0;
+
+        loop {
+            let (page, len) = Page::parse(data, offset)?;
+            if offset == 0 {
+                first_page_len = len;
+            }
+            offset += len;
+            if *serial.get_or_insert(page.serial) != page.serial {
+                // Interleaved streams (e.g. video) aren't supported
+                return Err("multiplexed Ogg streams are not supported".to_string());
+            }
+            header_pages += 1;
+
+            let mut position = 0;
+            for &segment in &page.lacing {
+                partial.extend_from_slice(&page.body[position..position + segment as usize]);
+                position += segment as usize;
+                if segment < 255 {
+                    packets.push(std::mem::take(&mut partial));
+                }
+            }
+
+            if codec.is_none() {
+                let id_packet = packets.first().ok_or("first Ogg page holds no packet")?;
+                codec = Some(Codec::detect(id_packet).ok_or("not an Ogg Vorbis or Opus stream")?);
+            }
+            let codec = codec.unwrap();
+
+            // Both specs require the last header packet to finish its page
+            if packets.len() >= codec.header_packets() && partial.is_empty() {
+                packets.truncate(codec.header_packets());
+                if !packets[1].starts_with(codec.comment_prefix()) {
+                    return Err("second Ogg packet is not a comment header".to_string());
+                }
+                return Ok(Self {
+                    codec,
+                    serial: page.serial,
+                    packets,
+                    first_page_len,
+                    headers_end: offset,
+                    header_pages,
+                });
+            }
+        }
+    }
+
+    fn comments(&self) -> Result<VorbisComments> {
+        VorbisComments::parse(&self.packets[1][self.codec.comment_prefix().len()..])
+    }
+
+    /// Duration from the last page's granule position
+    fn duration_seconds(&self, data: &[u8]) -> Option<u32> {
+        let mut offset = self.headers_end;
+        let mut last_granule = None;
+        while let Ok((page, len)) = Page::parse(data, offset) {
+            if page.serial == self.serial && page.granule != NO_GRANULE {
+                last_granule = Some(page.granule);
+            }
+            offset += len;
+        }
+
+        let id_packet = &self.packets[0];
+        let mut samples = last_granule?;
+        if self.codec == Codec::Opus {
+            let pre_skip = id_packet.get(10..12)?;
+            samples =
+                samples.saturating_sub(u64::from(u16::from_le_bytes([pre_skip[0], pre_skip[1]])));
+        }
+        let rate = self.codec.granule_rate(id_packet).filter(|r| *r > 0)?;
+        Some((samples / rate) as u32)
+    }
+
+    /// New file bytes with the comment packet replaced; audio pages keep their
+    /// payload and only get renumbered if the header page count changed
+    fn rewrite(&self, data: &[u8], comments: &VorbisComments) -> Vec<u8> {
+        let mut comment_packet = self.codec.comment_prefix().to_vec();
+        comment_packet.extend_from_slice(&comments.to_bytes());
+        if self.codec == Codec::Vorbis {
+            comment_packet.push(0x01); // framing bit
+        }
+
+        // The identification header always sits alone on the first page
+        let mut remaining = vec![comment_packet];
+        remaining.extend(self.packets[2..].iter().cloned());
+        let pages = paginate(&remaining, self.serial, 1);
+
+        let sequence_shift = 1 + pages.len() as i64 - self.header_pages as i64;
+
+        let mut out = Vec::with_capacity(data.len());
+        out.extend_from_slice(&data[..self.first_page_len]);
+        for page in &pages {
+            out.extend_from_slice(&page.to_bytes());
+        }
+
+        let mut offset = self.headers_end;
+        while offset < data.len() {
+            let Ok((mut page, len)) = Page::parse(data, offset) else {
+                // Keep any trailing bytes we can't interpret as they are
+                out.extend_from_slice(&data[offset..]);
+                break;
+            };
+            if page.serial == self.serial && sequence_shift != 0 {
+                page.sequence = (i64::from(page.sequence) + sequence_shift) as u32;
+                out.extend_from_slice(&page.to_bytes());
+            } else {
+                out.extend_from_slice(&data[offset..offset + len]);
+            }
+            offset += len;
+        }
+        out
+    }
+}
+
+/// Lay packets out on pages of at most 255 segments
+fn paginate(packets: &[Vec<u8>], serial: u32, first_sequence: u32) -> Vec<Page> {
+    let mut pages = Vec::new();
+    let mut page = new_page(serial, first_sequence, 0);
+
+    for packet in packets {
+        let mut chunks: Vec<&[u8]> = packet.chunks(255).collect();
+        // A packet whose length is a multiple of 255 ends with an empty segment
+        if packet.len() % 255 == 0 {
+            chunks.push(&[]);
+        }
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            if page.lacing.len() == 255 {
+                let sequence = page.sequence + 1;
+                let flags = if i > 0 { FLAG_CONTINUED } else { 0 };
+                pages.push(std::mem::replace(
+                    &mut page,
+                    new_page(serial, sequence, flags),
+                ));
+            }
+            page.lacing.push(chunk.len() as u8);
+            page.body.extend_from_slice(chunk);
+            if i == chunks.len() - 1 {
+                page.granule = 0;
+            }
+        }
+    }
+
+    if !page.lacing.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+fn new_page(serial: u32, sequence: u32, flags: u8) -> Page {
+    Page {
+        flags,
+        granule: NO_GRANULE,
+        serial,
+        sequence,
+        lacing: Vec::new(),
+        body: Vec::new(),
+    }
+}
+
+/// CRC-32 as used by Ogg: polynomial 0x04c11db7, no reflection, zero init
+fn ogg_crc(data: &[u8]) -> u32 {
+    data.iter().fold(0u32, |crc, &byte| {
+        let mut crc = crc ^ (u32::from(byte) << 24);
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04c1_1db7
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// OpusHead page, OpusTags page, one audio page ending at 5 s (after 312 pre-skip)
+    fn sample_opus() -> Vec<u8> {
+        let mut head = b"OpusHead\x01\x02".to_vec();
+        head.extend_from_slice(&312u16.to_le_bytes());
+        head.extend_from_slice(&48_000u32.to_le_bytes());
+        head.extend_from_slice(&[0, 0, 0]);
+
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(
+            &VorbisComments {
+                vendor: "libopus".to_string(),
+                comments: vec![("TITLE".to_string(), "Episode 1".to_string())],
+            }
+            .to_bytes(),
+        );
+
+        let mut data = Vec::new();
+        let mut first = paginate(&[head], 7, 0).remove(0);
+        first.flags = 0x02; // beginning of stream
+        data.extend_from_slice(&first.to_bytes());
+        data.extend_from_slice(&paginate(&[tags], 7, 1)[0].to_bytes());
+
+        let mut audio = paginate(&[b"AUDIOPACKET".to_vec()], 7, 2).remove(0);
+        audio.granule = 5 * 48_000 + 312;
+        audio.flags = 0x04; // end of stream
+        data.extend_from_slice(&audio.to_bytes());
+        data
+    }
+
+    #[test]
+    fn test_ogg_comment_rewrite() {
+        let path = std::env::temp_dir().join("music-agent-ogg-test.opus");
+        fs::write(&path, sample_opus()).unwrap();
+
+        let mut metadata = OggBackend.read(&path).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Episode 1"));
+        assert_eq!(metadata.duration_seconds, Some(5));
+
+        // Long enough to push the comment header across several pages
+        metadata.comment = Some("x".repeat(70_000));
+        metadata.artists = vec!["Radiolab".to_string()];
+        OggBackend.write(&path, &metadata).unwrap();
+
+        let written = fs::read(&path).unwrap();
+        let reread = OggBackend.read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reread.artists, vec!["Radiolab"]);
+        assert_eq!(reread.comment.map(|c| c.len()), Some(70_000));
+        assert_eq!(reread.duration_seconds, Some(5));
+
+        // Every page still checksums, and the audio page was renumbered after the headers
+        let stream = OggStream::parse(&written).unwrap();
+        let (audio, _) = Page::parse(&written, stream.headers_end).unwrap();
+        assert_eq!(audio.body, b"AUDIOPACKET");
+        assert_eq!(audio.sequence as usize, stream.header_pages);
+
+        // ...so the audio checksum skips page headers
+        let audio_of = |data: &[u8]| -> Vec<u8> {
+            let ranges = OggBackend.audio_ranges(data).unwrap();
+            ranges.into_iter().flat_map(|r| data[r].to_vec()).collect()
+        };
+        assert_eq!(audio_of(&written), audio_of(&sample_opus()));
+    }
+}
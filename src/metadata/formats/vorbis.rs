@@ -0,0 +1,330 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::{custom_key, parse_bool, split_values, TrackMetadata, MULTI_VALUE_FIELDS};
+
+/// Vorbis comment keys for each field; the first key is the one written
+const FIELD_KEYS: &[(&str, &[&str])] = &[
+    ("artist", &["ARTIST"]),
+    ("title", &["TITLE"]),
+    ("album", &["ALBUM"]),
+    ("year", &["DATE", "YEAR"]),
+    ("genre", &["GENRE"]),
+    ("track_number", &["TRACKNUMBER"]),
+    ("total_tracks", &["TRACKTOTAL", "TOTALTRACKS"]),
+    ("disc_number", &["DISCNUMBER"]),
+    ("total_discs", &["DISCTOTAL", "TOTALDISCS"]),
+    ("album_artist", &["ALBUMARTIST", "ALBUM ARTIST"]),
+    ("composer", &["COMPOSER"]),
+    ("comment", &["COMMENT", "DESCRIPTION"]),
+    ("bpm", &["BPM"]),
+    ("publisher", &["LABEL", "ORGANIZATION", "PUBLISHER"]),
+    ("isrc", &["ISRC"]),
+    ("compilation", &["COMPILATION"]),
+    ("artist_sort", &["ARTISTSORT"]),
+    ("album_sort", &["ALBUMSORT"]),
+];
+
+/// Unmapped keys that hold pictures or lyrics rather than custom tag values
+const NOT_CUSTOM: &[&str] = &[
+    "METADATA_BLOCK_PICTURE",
+    "COVERART",
+    "COVERARTMIME",
+    "LYRICS",
+    "UNSYNCEDLYRICS",
+];
+
+/// A Vorbis comment block (used by FLAC and Ogg): vendor string plus KEY=value pairs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VorbisComments {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>,
+}
+
+impl VorbisComments {
+    /// Parse the little-endian comment structure (without any framing bits)
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut cursor = 0;
+        let vendor = read_string(data, &mut cursor)?;
+        let count = read_u32(data, &mut cursor)?;
+
+        let mut comments = Vec::new();
+        for _ in 0..count {
+            let entry = read_string(data, &mut cursor)?;
+            if let Some((key, value)) = entry.split_once('=') {
+                comments.push((key.to_string(), value.to_string()));
+            }
+        }
+
+        Ok(Self { vendor, comments })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        write_string(&mut data, &self.vendor);
+        data.extend_from_slice(&(self.comments.len() as u32).to_le_bytes());
+        for (key, value) in &self.comments {
+            write_string(&mut data, &format!("{}={}", key, value));
+        }
+        data
+    }
+
+    /// First value for any of the keys (keys are case-insensitive)
+    pub fn get(&self, keys: &[&str]) -> Option<String> {
+        keys.iter().find_map(|key| {
+            self.comments
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.clone())
+        })
+    }
+
+    /// Every value stored under any of the keys, in file order
+    pub fn get_all(&self, keys: &[&str]) -> Vec<String> {
+        self.comments
+            .iter()
+            .filter(|(k, _)| keys.iter().any(|key| k.eq_ignore_ascii_case(key)))
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    /// Replace every value stored under the keys with a single value
+    pub fn set(&mut self, keys: &[&str], value: &str) {
+        self.set_all(keys, &[value.to_string()]);
+    }
+
+    /// Replace every value stored under the keys with one comment per value
+    pub fn set_all(&mut self, keys: &[&str], values: &[String]) {
+        let position = self
+            .comments
+            .iter()
+            .position(|(k, _)| keys.iter().any(|key| k.eq_ignore_ascii_case(key)));
+        self.comments
+            .retain(|(k, _)| !keys.iter().any(|key| k.eq_ignore_ascii_case(key)));
+
+        let at = position
+            .unwrap_or(self.comments.len())
+            .min(self.comments.len());
+        let entries = values.iter().map(|v| (keys[0].to_string(), v.clone()));
+        self.comments.splice(at..at, entries);
+    }
+
+    /// Drop every value stored under the keys
+    pub fn remove(&mut self, keys: &[&str]) {
+        self.comments
+            .retain(|(k, _)| !keys.iter().any(|key| k.eq_ignore_ascii_case(key)));
+    }
+
+    /// Delete fields by suggestion name
+    pub fn remove_fields(&mut self, fields: &[String]) {
+        for field in fields {
+            if let Some(key) = custom_key(field) {
+                self.remove(&[key]);
+                continue;
+            }
+            self.remove(keys_for(field));
+            // A total can also hide in "3/12" style numbers; keep just the number
+            let number_field = match field.as_str() {
+                "total_tracks" => "track_number",
+                "total_discs" => "disc_number",
+                _ => continue,
+            };
+            let keys = keys_for(number_field);
+            if let Some((number, _)) = self.get(keys).as_deref().and_then(|v| v.split_once('/')) {
+                self.set(keys, number.trim());
+            }
+        }
+    }
+
+    fn field(&self, field: &str) -> Option<String> {
+        self.get(keys_for(field))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Values of a multi-value field: repeated comments, or ";"-separated in one
+    fn values(&self, field: &str) -> Vec<String> {
+        split_values(&self.get_all(keys_for(field)).join("\0"))
+    }
+
+    /// Map the comments onto TrackMetadata
+    pub fn to_metadata(&self, file_path: &str) -> TrackMetadata {
+        // TRACKNUMBER / DISCNUMBER are sometimes written as "3/12"
+        let (track_number, track_total) = split_pair(self.field("track_number"));
+        let (disc_number, disc_total) = split_pair(self.field("disc_number"));
+
+        TrackMetadata {
+            file_path: file_path.to_string(),
+            artists: self.values("artist"),
+            title: self.field("title"),
+            album: self.field("album"),
+            // DATE may be a full date ("1970-11-01"); keep the year
+            year: self
+                .field("year")
+                .and_then(|d| d.get(..4).and_then(|y| y.parse().ok())),
+            genres: self.values("genre"),
+            track_number,
+            album_artist: self.field("album_artist"),
+            total_tracks: self
+                .field("total_tracks")
+                .and_then(|t| t.parse().ok())
+                .or(track_total),
+            disc_number,
+            total_discs: self
+                .field("total_discs")
+                .and_then(|t| t.parse().ok())
+                .or(disc_total),
+            composer: self.field("composer"),
+            comment: self.field("comment"),
+            bpm: self
+                .field("bpm")
+                .and_then(|b| b.parse::<f32>().ok())
+                .map(|b| b.round() as u32),
+            publisher: self.field("publisher"),
+            isrc: self.field("isrc"),
+            compilation: self.field("compilation").and_then(|c| parse_bool(&c)),
+            artist_sort: self.field("artist_sort"),
+            album_sort: self.field("album_sort"),
+            custom: self.custom(),
+            ..Default::default()
+        }
+    }
+
+    /// Comments that don't map to a TrackMetadata field (first value per key)
+    fn custom(&self) -> std::collections::BTreeMap<String, String> {
+        let mapped = |key: &str| {
+            FIELD_KEYS
+                .iter()
+                .flat_map(|(_, keys)| keys.iter())
+                .chain(NOT_CUSTOM)
+                .any(|k| k.eq_ignore_ascii_case(key))
+        };
+        let mut custom = std::collections::BTreeMap::new();
+        for (key, value) in &self.comments {
+            if !mapped(key) {
+                custom.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+        }
+        custom
+    }
+
+    /// Write every field that is set in `metadata`
+    pub fn apply_metadata(&mut self, metadata: &TrackMetadata) {
+        for (field, keys) in FIELD_KEYS {
+            if MULTI_VALUE_FIELDS.contains(field) {
+                let values = metadata.field_values(field);
+                if !values.is_empty() {
+                    self.set_all(keys, &values);
+                }
+                continue;
+            }
+            let value = match *field {
+                "compilation" => metadata
+                    .compilation
+                    .map(|c| if c { "1" } else { "0" }.to_string()),
+                _ => metadata.field_value(field),
+            };
+            // Don't truncate a full DATE ("1970-11-01") that already has this year
+            if *field == "year" {
+                if let (Some(ref year), Some(existing)) = (&value, self.get(keys)) {
+                    if existing.starts_with(year.as_str()) {
+                        continue;
+                    }
+                }
+            }
+            if let Some(value) = value {
+                self.set(keys, &value);
+            }
+        }
+
+        for (key, value) in &metadata.custom {
+            if self.get(&[key]).as_ref() != Some(value) {
+                self.set(&[key], value);
+            }
+        }
+    }
+}
+
+fn keys_for(field: &str) -> &'static [&'static str] {
+    FIELD_KEYS
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, keys)| *keys)
+        .unwrap_or(&[])
+}
+
+fn split_pair(value: Option<String>) -> (Option<u32>, Option<u32>) {
+    let Some(value) = value else {
+        return (None, None);
+    };
+    match value.split_once('/') {
+        Some((number, total)) => (number.trim().parse().ok(), total.trim().parse().ok()),
+        None => (value.parse().ok(), None),
+    }
+}
+
+fn read_u32(data: &[u8], cursor: &mut usize) -> Result<u32> {
+    let bytes = data
+        .get(*cursor..*cursor + 4)
+        .ok_or_else(|| AgentError::MetadataParse("Truncated Vorbis comment block".to_string()))?;
+    *cursor += 4;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_string(data: &[u8], cursor: &mut usize) -> Result<String> {
+    let len = read_u32(data, cursor)? as usize;
+    let bytes = data
+        .get(*cursor..*cursor + len)
+        .ok_or_else(|| AgentError::MetadataParse("Truncated Vorbis comment block".to_string()))?;
+    *cursor += len;
+    Ok(String::from_utf8_lossy(bytes).to_string())
+}
+
+fn write_string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_mapping() {
+        let mut comments = VorbisComments {
+            vendor: "reference libFLAC 1.4.3".to_string(),
+            comments: vec![
+                ("artist".to_string(), "Grateful Dead".to_string()),
+                ("TRACKNUMBER".to_string(), "2/10".to_string()),
+                ("DATE".to_string(), "1970-11-01".to_string()),
+            ],
+        };
+
+        let metadata = comments.to_metadata("song.flac");
+        assert_eq!(metadata.artists, vec!["Grateful Dead"]);
+        assert_eq!(metadata.track_number, Some(2));
+        assert_eq!(metadata.total_tracks, Some(10));
+        assert_eq!(metadata.year, Some(1970));
+
+        let mut updated = metadata.clone();
+        updated.artists = vec!["The Grateful Dead".to_string()];
+        updated.genres = vec!["Folk Rock".to_string(), "Psychedelic Rock".to_string()];
+        comments.apply_metadata(&updated);
+
+        let parsed = VorbisComments::parse(&comments.to_bytes()).unwrap();
+        assert_eq!(
+            parsed.get(&["ARTIST"]).as_deref(),
+            Some("The Grateful Dead")
+        );
+        assert_eq!(
+            parsed.get_all(&["GENRE"]),
+            vec!["Folk Rock", "Psychedelic Rock"]
+        );
+        assert_eq!(parsed.to_metadata("song.flac").genres, updated.genres);
+        assert_eq!(parsed.comments[0].0, "ARTIST");
+
+        comments.remove_fields(&["genre".to_string(), "total_tracks".to_string()]);
+        let metadata = comments.to_metadata("song.flac");
+        assert!(metadata.genres.is_empty());
+        assert_eq!(metadata.track_number, Some(2));
+        assert_eq!(metadata.total_tracks, None);
+    }
+}
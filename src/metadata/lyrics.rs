@@ -0,0 +1,281 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::TrackMetadata;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Lyric lines handed to the LLM as evidence of which song this is
+pub const PROMPT_LINES: usize = 4;
+
+/// Language used when a source doesn't name one (ISO 639-2)
+const DEFAULT_LANGUAGE: &str = "eng";
+
+/// One line of lyrics, with its start time if synchronized
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricLine {
+    pub time_ms: Option<u32>,
+    pub text: String,
+}
+
+/// Lyrics from a USLT/SYLT frame or an .lrc file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lyrics {
+    /// ISO 639-2 code, e.g. "eng"
+    pub language: String,
+    pub lines: Vec<LyricLine>,
+}
+
+/// Summary of lyrics found for a track (the full text is not kept)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LyricsInfo {
+    /// "USLT", "SYLT" or ".lrc sidecar"
+    pub source: String,
+    pub language: String,
+    pub synced: bool,
+    pub line_count: usize,
+    /// First non-empty lines, used as prompt evidence
+    pub first_lines: Vec<String>,
+}
+
+impl Lyrics {
+    /// Falls back to "eng" if `language` isn't a three-letter code
+    pub fn new(language: &str, lines: Vec<LyricLine>) -> Self {
+        Self {
+            language: language_or_default(language),
+            lines,
+        }
+    }
+
+    /// Unsynchronized lyrics from plain text
+    pub fn from_text(language: &str, text: &str) -> Self {
+        let lines = text
+            .lines()
+            .map(|line| LyricLine {
+                time_ms: None,
+                text: line.trim_end().to_string(),
+            })
+            .collect();
+        Self::new(language, lines)
+    }
+
+    /// Parse LRC text: `[mm:ss.xx]` timestamps (several per line allowed), `[offset:±ms]`
+    /// and `[la:xxx]` tags; other ID tags are skipped and untimed lines kept as plain text
+    pub fn parse_lrc(text: &str) -> Self {
+        let mut language = String::new();
+        let mut offset_ms = 0i64;
+        let mut lines = Vec::new();
+
+        for raw in text.trim_start_matches('\u{feff}').lines() {
+            let mut rest = raw.trim();
+            let mut times = Vec::new();
+            let mut is_tag = false;
+            while let Some(end) = rest.strip_prefix('[').and_then(|r| r.find(']')) {
+                let inner = &rest[1..end + 1];
+                rest = rest[end + 2..].trim_start();
+                if let Some(ms) = parse_timestamp(inner) {
+                    times.push(ms);
+                    continue;
+                }
+                is_tag = true;
+                if let Some((key, value)) = inner.split_once(':') {
+                    match key.trim().to_lowercase().as_str() {
+                        "offset" => offset_ms = value.trim().parse().unwrap_or(0),
+                        "la" | "lang" => language = value.trim().to_string(),
+                        _ => {}
+                    }
+                }
+            }
+
+            if times.is_empty() {
+                if !is_tag {
+                    lines.push(LyricLine {
+                        time_ms: None,
+                        text: rest.to_string(),
+                    });
+                }
+                continue;
+            }
+            for ms in times {
+                // A positive offset makes lyrics appear sooner
+                let time_ms = (i64::from(ms) - offset_ms).max(0) as u32;
+                lines.push(LyricLine {
+                    time_ms: Some(time_ms),
+                    text: rest.to_string(),
+                });
+            }
+        }
+
+        // Trim blank lines around the lyrics, then order repeated (multi-timestamp) lines
+        while lines
+            .last()
+            .is_some_and(|l| l.text.is_empty() && l.time_ms.is_none())
+        {
+            lines.pop();
+        }
+        let leading = lines
+            .iter()
+            .take_while(|l| l.text.is_empty() && l.time_ms.is_none())
+            .count();
+        lines.drain(..leading);
+        if lines.iter().all(|l| l.time_ms.is_some()) {
+            lines.sort_by_key(|l| l.time_ms);
+        }
+
+        Self::new(&language, lines)
+    }
+
+    /// Read an .lrc (or plain text) file
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).map_err(|e| {
+            AgentError::FileRead(format!("Failed to read lyrics {}: {}", path.display(), e))
+        })?;
+        let lyrics = Self::parse_lrc(&String::from_utf8_lossy(&data));
+        if lyrics.lines.is_empty() {
+            return Err(AgentError::FileRead(format!(
+                "No lyrics found in {}",
+                path.display()
+            )));
+        }
+        Ok(lyrics)
+    }
+
+    /// The .lrc file next to a track with the same name, if any
+    pub fn find_beside(track: &Path) -> Option<PathBuf> {
+        ["lrc", "LRC"]
+            .iter()
+            .map(|extension| track.with_extension(extension))
+            .find(|path| path.is_file())
+    }
+
+    /// True if every line has a timestamp
+    pub fn is_synced(&self) -> bool {
+        !self.lines.is_empty() && self.lines.iter().all(|l| l.time_ms.is_some())
+    }
+
+    /// Lyrics as plain text, one line per line
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|l| l.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Render as LRC with artist/title/album ID tags; untimed lines are written as is
+    pub fn to_lrc(&self, metadata: &TrackMetadata) -> String {
+        let mut out = String::new();
+        for (tag, value) in [
+            ("ar", metadata.artist()),
+            ("ti", metadata.title.clone()),
+            ("al", metadata.album.clone()),
+        ] {
+            if let Some(value) = value {
+                out.push_str(&format!("[{}:{}]\n", tag, value));
+            }
+        }
+        out.push_str(&format!("[la:{}]\n", self.language));
+
+        for line in &self.lines {
+            match line.time_ms {
+                Some(ms) => out.push_str(&format!(
+                    "[{:02}:{:02}.{:02}]{}\n",
+                    ms / 60_000,
+                    ms / 1000 % 60,
+                    ms % 1000 / 10,
+                    line.text
+                )),
+                None => out.push_str(&format!("{}\n", line.text)),
+            }
+        }
+        out
+    }
+
+    pub fn info(&self, source: &str) -> LyricsInfo {
+        LyricsInfo {
+            source: source.to_string(),
+            language: self.language.clone(),
+            synced: self.is_synced(),
+            line_count: self.lines.iter().filter(|l| !l.text.is_empty()).count(),
+            first_lines: self
+                .lines
+                .iter()
+                .map(|l| l.text.trim())
+                .filter(|t| !t.is_empty())
+                .take(PROMPT_LINES)
+                .map(|t| t.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for LyricsInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}), {} {}line(s)",
+            self.source,
+            self.language,
+            self.line_count,
+            if self.synced { "synced " } else { "" }
+        )
+    }
+}
+
+fn language_or_default(language: &str) -> String {
+    let language = language.trim();
+    if language.len() == 3 && language.chars().all(|c| c.is_ascii_alphabetic()) {
+        language.to_lowercase()
+    } else {
+        DEFAULT_LANGUAGE.to_string()
+    }
+}
+
+/// "mm:ss", "mm:ss.xx" or "mm:ss:xx" to milliseconds
+fn parse_timestamp(value: &str) -> Option<u32> {
+    let (minutes, rest) = value.split_once(':')?;
+    let minutes: u32 = minutes.trim().parse().ok()?;
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, fraction),
+        None => (rest, ""),
+    };
+    let seconds: u32 = seconds.trim().parse().ok()?;
+    if seconds >= 60 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    // ".5" is 500 ms, ".05" 50 ms, ".005" 5 ms
+    let fraction_ms = format!("{:0<3}", fraction)
+        .get(..3)
+        .and_then(|f| f.parse::<u32>().ok())
+        .unwrap_or(0);
+    Some(minutes * 60_000 + seconds * 1000 + fraction_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lrc_round_trip() {
+        let lrc = "\u{feff}[ar:Radiohead]\n[offset:+500]\n\n\
+                   [00:12.50][01:02.00]Chorus line\n[00:05.00]First line\n";
+        let lyrics = Lyrics::parse_lrc(lrc);
+        assert!(lyrics.is_synced());
+        assert_eq!(lyrics.language, "eng");
+        let times: Vec<_> = lyrics.lines.iter().map(|l| l.time_ms).collect();
+        assert_eq!(times, vec![Some(4500), Some(12_000), Some(61_500)]);
+        assert_eq!(lyrics.info("SYLT").first_lines[0], "First line");
+
+        let metadata = TrackMetadata {
+            title: Some("Creep".to_string()),
+            ..Default::default()
+        };
+        let exported = lyrics.to_lrc(&metadata);
+        assert!(exported.starts_with("[ti:Creep]\n[la:eng]\n[00:04.50]First line\n"));
+        assert_eq!(Lyrics::parse_lrc(&exported).lines, lyrics.lines);
+
+        let plain = Lyrics::parse_lrc("Verse one\n\nVerse two\n");
+        assert!(!plain.is_synced());
+        assert_eq!(plain.text(), "Verse one\n\nVerse two");
+    }
+}
@@ -1,32 +1,198 @@
+pub mod artwork;
+pub mod audio;
+pub mod encoding;
+pub mod formats;
+pub mod lyrics;
 pub mod reader;
+pub mod undo;
 pub mod writer;
 
-use serde::{Deserialize, Serialize};
+use artwork::PictureInfo;
+use audio::AudioProperties;
+use encoding::EncodingIssue;
+use lyrics::LyricsInfo;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Every tag field addressable through `field_value` / `set_field`
+pub const FIELDS: &[&str] = &[
+    "artist",
+    "title",
+    "album",
+    "year",
+    "genre",
+    "track_number",
+    "total_tracks",
+    "disc_number",
+    "total_discs",
+    "album_artist",
+    "composer",
+    "comment",
+    "bpm",
+    "publisher",
+    "isrc",
+    "compilation",
+    "artist_sort",
+    "album_sort",
+];
+
+/// Fields that hold a list of values, e.g. "MF DOOM; Madvillain"
+pub const MULTI_VALUE_FIELDS: &[&str] = &["artist", "genre"];
+
+/// Separator between the values of a multi-value field in suggestions and prompts
+pub const VALUE_SEPARATOR: &str = "; ";
+
+/// Prefix of fields that address a custom tag, e.g. "txxx:REPLAYGAIN_TRACK_GAIN"
+pub const CUSTOM_PREFIX: &str = "txxx:";
+
+/// The custom tag key of a "txxx:KEY" field name (prefix matched case-insensitively)
+pub fn custom_key(field: &str) -> Option<&str> {
+    let prefix = field.get(..CUSTOM_PREFIX.len())?;
+    let key = &field[CUSTOM_PREFIX.len()..];
+    (prefix.eq_ignore_ascii_case(CUSTOM_PREFIX) && !key.is_empty()).then_some(key)
+}
+
+/// Prefix of fields that address an involved-people role, e.g. "people:producer"
+pub const PEOPLE_PREFIX: &str = "people:";
+
+/// The lower-case role of a "people:ROLE" field name
+pub fn people_role(field: &str) -> Option<String> {
+    let prefix = field.get(..PEOPLE_PREFIX.len())?;
+    let role = field[PEOPLE_PREFIX.len()..].trim();
+    (prefix.eq_ignore_ascii_case(PEOPLE_PREFIX) && !role.is_empty()).then(|| role.to_lowercase())
+}
+
+/// True for the standard fields, "txxx:KEY" custom fields and "people:ROLE" credits
+pub fn is_known_field(field: &str) -> bool {
+    FIELDS.contains(&field) || custom_key(field).is_some() || people_role(field).is_some()
+}
+
+/// Values of a ";"- or NUL-separated list, trimmed, without blanks or repeats
+pub fn split_values(text: &str) -> Vec<String> {
+    let mut values: Vec<String> = Vec::new();
+    for value in text.split([';', '\0']).map(str::trim) {
+        if !value.is_empty() && !values.iter().any(|v| v.eq_ignore_ascii_case(value)) {
+            values.push(value.to_string());
+        }
+    }
+    values
+}
+
+/// Values joined with [`VALUE_SEPARATOR`], or None for an empty list
+pub fn join_values(values: &[String]) -> Option<String> {
+    (!values.is_empty()).then(|| values.join(VALUE_SEPARATOR))
+}
+
+/// Accept a list, a single (possibly "; "-separated) string or null
+///
+/// Suggestion files written before multi-value support store `artist` and `genre`
+/// as plain strings.
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Values {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<Values>::deserialize(deserializer)? {
+        Some(Values::One(text)) => split_values(&text),
+        Some(Values::Many(values)) => values,
+        None => Vec::new(),
+    })
+}
+
+// Fields added after v0.2.0 default to None so older suggestion files still load
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TrackMetadata {
     pub file_path: String,
-    pub artist: Option<String>,
+    /// Every performing artist, in tag order
+    #[serde(alias = "artist", deserialize_with = "one_or_many")]
+    pub artists: Vec<String>,
     pub title: Option<String>,
     pub album: Option<String>,
     pub year: Option<i32>,
-    pub genre: Option<String>,
+    #[serde(alias = "genre", deserialize_with = "one_or_many")]
+    pub genres: Vec<String>,
     pub track_number: Option<u32>,
     pub album_artist: Option<String>,
     pub duration_seconds: Option<u32>,
+    pub total_tracks: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub total_discs: Option<u32>,
+    pub composer: Option<String>,
+    pub comment: Option<String>,
+    pub bpm: Option<u32>,
+    pub publisher: Option<String>,
+    pub isrc: Option<String>,
+    pub compilation: Option<bool>,
+    pub artist_sort: Option<String>,
+    pub album_sort: Option<String>,
+    /// Properties of the audio stream itself, when the container could be parsed
+    pub audio: Option<AudioProperties>,
+    /// Tag formats found in the file (e.g. "ID3v2.3", "ID3v1", "APEv2")
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tag_types: Vec<String>,
+    /// Fields whose value differs between the tag formats present
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tag_conflicts: Vec<TagConflict>,
+    /// Embedded pictures (cover art etc.)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pictures: Vec<PictureInfo>,
+    /// Embedded lyrics frames, or the .lrc file beside the track
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub lyrics: Vec<LyricsInfo>,
+    /// Custom tags by key: ID3 TXXX frames, unmapped Vorbis comments, MP4 freeform items
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, String>,
+    /// Credits by lower-case role from ID3 TIPL/TMCL/IPLS, e.g. "producer" -> ["MF DOOM"]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub people: BTreeMap<String, Vec<String>>,
+    /// Why the file's extension doesn't match its contents, e.g. a FLAC named .mp3
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format_mismatch: Option<String>,
+    /// Fields whose text looks like another encoding read as ISO-8859-1
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub encoding_issues: Vec<EncodingIssue>,
+    /// Fields whose value was read from a legacy tag (ID3v1, APEv2) because the primary
+    /// tag lacks them; writes leave them out unless they are set again or consolidated
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub legacy_fields: Vec<String>,
+}
+
+/// One field stored with different values in different tag formats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagConflict {
+    pub field: String,
+    /// Value per tag format
+    pub values: BTreeMap<String, String>,
 }
 
 impl TrackMetadata {
     /// Returns true if critical metadata is missing
     pub fn has_missing_critical_fields(&self) -> bool {
-        self.artist.is_none() || self.title.is_none()
+        self.artists.is_empty() || self.title.is_none()
+    }
+
+    /// All artists as one "; "-separated value
+    pub fn artist(&self) -> Option<String> {
+        join_values(&self.artists)
+    }
+
+    /// All genres as one "; "-separated value
+    pub fn genre(&self) -> Option<String> {
+        join_values(&self.genres)
     }
 
     /// Returns a list of missing fields
+    ///
+    /// Composer, publisher and ISRC are often absent and can't be guessed, so they
+    /// aren't listed.
     pub fn missing_fields(&self) -> Vec<&str> {
         let mut missing = Vec::new();
-        if self.artist.is_none() {
+        if self.artists.is_empty() {
             missing.push("artist");
         }
         if self.title.is_none() {
@@ -38,12 +204,214 @@ impl TrackMetadata {
         if self.year.is_none() {
             missing.push("year");
         }
-        if self.genre.is_none() {
+        if self.genres.is_empty() {
             missing.push("genre");
         }
+        if self.track_number.is_none() {
+            missing.push("track_number");
+        }
+        if self.album_artist.is_none() {
+            missing.push("album_artist");
+        }
         missing
     }
 
+    /// Current value of a field by its suggestion name (e.g. "album_artist")
+    pub fn field_value(&self, field: &str) -> Option<String> {
+        match field {
+            "artist" => self.artist(),
+            "title" => self.title.clone(),
+            "album" => self.album.clone(),
+            "year" => self.year.map(|y| y.to_string()),
+            "genre" => self.genre(),
+            "track_number" => self.track_number.map(|t| t.to_string()),
+            "album_artist" => self.album_artist.clone(),
+            "total_tracks" => self.total_tracks.map(|t| t.to_string()),
+            "disc_number" => self.disc_number.map(|d| d.to_string()),
+            "total_discs" => self.total_discs.map(|d| d.to_string()),
+            "composer" => self.composer.clone(),
+            "comment" => self.comment.clone(),
+            "bpm" => self.bpm.map(|b| b.to_string()),
+            "publisher" => self.publisher.clone(),
+            "isrc" => self.isrc.clone(),
+            "compilation" => self.compilation.map(|c| c.to_string()),
+            "artist_sort" => self.artist_sort.clone(),
+            "album_sort" => self.album_sort.clone(),
+            _ => match people_role(field) {
+                Some(role) => self.people.get(&role).and_then(|names| join_values(names)),
+                None => {
+                    custom_key(field).and_then(|key| self.custom_entry(key).map(|(_, v)| v.clone()))
+                }
+            },
+        }
+    }
+
+    /// Values of a field by its suggestion name: the list for multi-value fields,
+    /// otherwise at most one
+    pub fn field_values(&self, field: &str) -> Vec<String> {
+        match field {
+            "artist" => self.artists.clone(),
+            "genre" => self.genres.clone(),
+            _ => match people_role(field) {
+                Some(role) => self.people.get(&role).cloned().unwrap_or_default(),
+                None => self.field_value(field).into_iter().collect(),
+            },
+        }
+    }
+
+    /// Custom tag entry by key, ignoring case (tools disagree on "MusicBrainz Album Id" casing)
+    pub fn custom_entry(&self, key: &str) -> Option<(&String, &String)> {
+        self.custom
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+    }
+
+    /// Set a field from its suggestion name and a string value
+    ///
+    /// Multi-value fields and credits take a "; "-separated list. Returns false if the field is unknown or the value can't be parsed for it.
+    pub fn set_field(&mut self, field: &str, value: &str) -> bool {
+        let set = self.assign_field(field, value);
+        if set {
+            self.legacy_fields
+                .retain(|f| !f.eq_ignore_ascii_case(field));
+        }
+        set
+    }
+
+    fn assign_field(&mut self, field: &str, value: &str) -> bool {
+        if let Some(role) = people_role(field) {
+            match split_values(value) {
+                names if names.is_empty() => self.people.remove(&role),
+                names => self.people.insert(role, names),
+            };
+            return true;
+        }
+
+        let text = Some(value.to_string());
+        match field {
+            "artist" => self.artists = split_values(value),
+            "title" => self.title = text,
+            "album" => self.album = text,
+            "year" => return set_parsed(&mut self.year, value),
+            "genre" => self.genres = split_values(value),
+            "track_number" => return set_parsed(&mut self.track_number, value),
+            "album_artist" => self.album_artist = text,
+            "total_tracks" => return set_parsed(&mut self.total_tracks, value),
+            "disc_number" => return set_parsed(&mut self.disc_number, value),
+            "total_discs" => return set_parsed(&mut self.total_discs, value),
+            "composer" => self.composer = text,
+            "comment" => self.comment = text,
+            "bpm" => return set_parsed(&mut self.bpm, value),
+            "publisher" => self.publisher = text,
+            "isrc" => self.isrc = text,
+            "compilation" => match parse_bool(value) {
+                Some(flag) => self.compilation = Some(flag),
+                None => return false,
+            },
+            "artist_sort" => self.artist_sort = text,
+            "album_sort" => self.album_sort = text,
+            _ => match custom_key(field) {
+                Some(key) => {
+                    // Keep the spelling of an existing key
+                    let key = self
+                        .custom_entry(key)
+                        .map(|(k, _)| k.clone())
+                        .unwrap_or_else(|| key.to_string());
+                    self.custom.insert(key, value.to_string());
+                }
+                None => return false,
+            },
+        }
+        true
+    }
+
+    /// Unset a field by its suggestion name; returns false if the field is unknown
+    pub fn clear_field(&mut self, field: &str) -> bool {
+        if let Some(role) = people_role(field) {
+            self.people.remove(&role);
+            return true;
+        }
+
+        match field {
+            "artist" => self.artists.clear(),
+            "title" => self.title = None,
+            "album" => self.album = None,
+            "year" => self.year = None,
+            "genre" => self.genres.clear(),
+            "track_number" => self.track_number = None,
+            "album_artist" => self.album_artist = None,
+            "total_tracks" => self.total_tracks = None,
+            "disc_number" => self.disc_number = None,
+            "total_discs" => self.total_discs = None,
+            "composer" => self.composer = None,
+            "comment" => self.comment = None,
+            "bpm" => self.bpm = None,
+            "publisher" => self.publisher = None,
+            "isrc" => self.isrc = None,
+            "compilation" => self.compilation = None,
+            "artist_sort" => self.artist_sort = None,
+            "album_sort" => self.album_sort = None,
+            _ => match custom_key(field).and_then(|key| self.custom_entry(key)) {
+                Some((key, _)) => {
+                    let key = key.clone();
+                    self.custom.remove(&key);
+                }
+                None => return custom_key(field).is_some(),
+            },
+        }
+        true
+    }
+
+    /// Compact description of the track used for embeddings
+    pub fn to_embedding_text(&self) -> String {
+        let mut parts = Vec::new();
+        for (label, value) in [
+            ("Artist", self.artist()),
+            ("Title", self.title.clone()),
+            ("Album", self.album.clone()),
+            ("Album Artist", self.album_artist.clone()),
+            ("Genre", self.genre()),
+            ("Year", self.year.map(|y| y.to_string())),
+        ] {
+            if let Some(value) = value {
+                parts.push(format!("{}: {}", label, value));
+            }
+        }
+
+        if parts.is_empty() {
+            // Fall back to the file name so untagged tracks still get a vector
+            let name = std::path::Path::new(&self.file_path)
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            parts.push(format!("File: {}", name));
+        }
+
+        parts.join("; ")
+    }
+
+    /// Fill fields that are unset here from `other`
+    pub fn fill_missing_from(&mut self, other: &TrackMetadata) {
+        for field in FIELDS {
+            if self.field_value(field).is_none() {
+                if let Some(value) = other.field_value(field) {
+                    self.set_field(field, &value);
+                }
+            }
+        }
+        for (key, value) in &other.custom {
+            if self.custom_entry(key).is_none() {
+                self.custom.insert(key.clone(), value.clone());
+            }
+        }
+        for (role, names) in &other.people {
+            self.people
+                .entry(role.clone())
+                .or_insert_with(|| names.clone());
+        }
+    }
+
     /// Format metadata for LLM prompt
     pub fn to_prompt_format(&self) -> String {
         format!(
@@ -57,24 +425,48 @@ Current Metadata:
 - Genre: {}
 - Track Number: {}
 - Album Artist: {}
-- Duration: {} seconds
-
+- Duration: {}
+- Audio: {}
+- Tags Present: {}
+- Cover Art: {}
+- Lyrics: {}
+{}{}{}
 Missing Fields: {}"#,
             self.file_path,
-            self.artist.as_deref().unwrap_or("(missing)"),
+            self.artist().as_deref().unwrap_or("(missing)"),
             self.title.as_deref().unwrap_or("(missing)"),
             self.album.as_deref().unwrap_or("(missing)"),
             self.year
                 .map(|y| y.to_string())
                 .unwrap_or_else(|| "(missing)".to_string()),
-            self.genre.as_deref().unwrap_or("(missing)"),
+            self.genre().as_deref().unwrap_or("(missing)"),
             self.track_number
                 .map(|t| t.to_string())
                 .unwrap_or_else(|| "(missing)".to_string()),
             self.album_artist.as_deref().unwrap_or("(missing)"),
-            self.duration_seconds
-                .map(|d| d.to_string())
+            self.duration_prompt(),
+            self.audio
+                .as_ref()
+                .map(|a| a.to_string())
                 .unwrap_or_else(|| "unknown".to_string()),
+            if self.tag_types.is_empty() {
+                "none".to_string()
+            } else {
+                self.tag_types.join(", ")
+            },
+            if self.pictures.is_empty() {
+                "(missing)".to_string()
+            } else {
+                self.pictures
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            },
+            self.lyrics_prompt(),
+            self.conflict_prompt_lines(),
+            self.encoding_prompt_lines(),
+            self.extended_prompt_lines(),
             if self.missing_fields().is_empty() {
                 "None".to_string()
             } else {
@@ -82,6 +474,122 @@ Missing Fields: {}"#,
             }
         )
     }
+
+    /// Duration as "m:ss (N seconds)", preferring the measured stream length
+    fn duration_prompt(&self) -> String {
+        let seconds = self
+            .audio
+            .as_ref()
+            .map(|a| a.duration_seconds())
+            .or(self.duration_seconds);
+        match seconds {
+            Some(s) => format!("{}:{:02} ({} seconds)", s / 60, s % 60, s),
+            None => "unknown".to_string(),
+        }
+    }
+
+    /// Opening lyric lines, which can identify a mislabeled track
+    fn lyrics_prompt(&self) -> String {
+        match self.lyrics.iter().find(|l| !l.first_lines.is_empty()) {
+            Some(lyrics) => {
+                let lines: Vec<String> = lyrics
+                    .first_lines
+                    .iter()
+                    .map(|line| format!("\"{}\"", line))
+                    .collect();
+                format!("{} ... (from {})", lines.join(" / "), lyrics.source)
+            }
+            None => "(none)".to_string(),
+        }
+    }
+
+    /// Prompt lines listing disagreeing tag values (each newline-terminated)
+    fn conflict_prompt_lines(&self) -> String {
+        self.tag_conflicts
+            .iter()
+            .map(|conflict| {
+                let values: Vec<String> = conflict
+                    .values
+                    .iter()
+                    .map(|(tag, value)| format!("{}: \"{}\"", tag, value))
+                    .collect();
+                format!(
+                    "- Tag Conflict ({}): {}\n",
+                    conflict.field,
+                    values.join(" vs ")
+                )
+            })
+            .collect()
+    }
+
+    /// Prompt lines naming a mislabeled file and mis-encoded fields (each newline-terminated)
+    fn encoding_prompt_lines(&self) -> String {
+        let mismatch = self
+            .format_mismatch
+            .iter()
+            .map(|mismatch| format!("- File Type: {}\n", mismatch));
+        let issues = self
+            .encoding_issues
+            .iter()
+            .map(|issue| format!("- Encoding: {}\n", issue));
+        mismatch.chain(issues).collect()
+    }
+
+    /// Prompt lines for the extended tag fields (each newline-terminated)
+    fn extended_prompt_lines(&self) -> String {
+        let mut lines = String::new();
+        for (label, field) in [
+            ("Total Tracks", "total_tracks"),
+            ("Disc Number", "disc_number"),
+            ("Total Discs", "total_discs"),
+            ("Composer", "composer"),
+            ("Comment", "comment"),
+            ("BPM", "bpm"),
+            ("Publisher/Label", "publisher"),
+            ("ISRC", "isrc"),
+            ("Compilation", "compilation"),
+            ("Artist Sort", "artist_sort"),
+            ("Album Sort", "album_sort"),
+        ] {
+            lines.push_str(&format!(
+                "- {}: {}\n",
+                label,
+                self.field_value(field)
+                    .unwrap_or_else(|| "(missing)".to_string())
+            ));
+        }
+        for (key, value) in &self.custom {
+            lines.push_str(&format!("- Custom ({}{}): {}\n", CUSTOM_PREFIX, key, value));
+        }
+        for (role, names) in &self.people {
+            lines.push_str(&format!(
+                "- Credit ({}{}): {}\n",
+                PEOPLE_PREFIX,
+                role,
+                names.join(VALUE_SEPARATOR)
+            ));
+        }
+        lines
+    }
+}
+
+fn set_parsed<T: std::str::FromStr>(slot: &mut Option<T>, value: &str) -> bool {
+    match value.trim().parse() {
+        Ok(parsed) => {
+            *slot = Some(parsed);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Parse yes/no style values used by the compilation flag
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
 }
 
 impl fmt::Display for TrackMetadata {
@@ -90,12 +598,87 @@ impl fmt::Display for TrackMetadata {
             f,
             "🎵 {}\n   Artist: {}\n   Album: {}\n   Year: {}\n   Genre: {}",
             self.title.as_deref().unwrap_or("Unknown Title"),
-            self.artist.as_deref().unwrap_or("Unknown Artist"),
+            self.artist().as_deref().unwrap_or("Unknown Artist"),
             self.album.as_deref().unwrap_or("Unknown Album"),
             self.year
                 .map(|y| y.to_string())
                 .unwrap_or_else(|| "Unknown".to_string()),
-            self.genre.as_deref().unwrap_or("Unknown"),
+            self.genre().as_deref().unwrap_or("Unknown"),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_field_round_trip() {
+        let mut metadata = TrackMetadata::default();
+        for (field, value) in [
+            ("disc_number", "2"),
+            ("bpm", "128"),
+            ("compilation", "true"),
+            ("isrc", "USUYG1202490"),
+            ("album_sort", "1999"),
+        ] {
+            assert!(metadata.set_field(field, value));
+            assert_eq!(metadata.field_value(field).as_deref(), Some(value));
+        }
+        assert!(!metadata.set_field("year", "nineteen"));
+        assert!(!metadata.set_field("mood", "happy"));
+
+        metadata
+            .custom
+            .insert("MusicBrainz Album Id".to_string(), "abc".to_string());
+        assert!(metadata.set_field("TXXX:musicbrainz album id", "def"));
+        assert_eq!(metadata.custom["MusicBrainz Album Id"], "def");
+        assert!(metadata.clear_field("txxx:MUSICBRAINZ ALBUM ID"));
+        assert!(metadata.custom.is_empty());
+        assert!(!metadata.set_field("txxx:", "empty key"));
+    }
+
+    #[test]
+    fn test_multi_value_fields() {
+        let mut metadata = TrackMetadata::default();
+        assert!(metadata.set_field("artist", "MF DOOM;Madvillain; mf doom"));
+        assert_eq!(metadata.artists, vec!["MF DOOM", "Madvillain"]);
+        assert_eq!(
+            metadata.field_value("artist").as_deref(),
+            Some("MF DOOM; Madvillain")
+        );
+
+        metadata.genres = split_values("Hip Hop\0Jazz Rap");
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert!(json.contains(r#""genres":["Hip Hop","Jazz Rap"]"#));
+        let reloaded: TrackMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded.genres, metadata.genres);
+
+        assert!(metadata.clear_field("artist"));
+        assert_eq!(metadata.field_value("artist"), None);
+    }
+
+    #[test]
+    fn test_fill_missing_keeps_credits() {
+        let mut metadata = TrackMetadata::default();
+        metadata.set_field("people:producer", "Madlib");
+        let mut other = TrackMetadata::default();
+        other.set_field("people:producer", "Lee Bannon");
+        other.set_field("people:mix", "Dave Cooley");
+
+        metadata.fill_missing_from(&other);
+        assert_eq!(metadata.people["producer"], vec!["Madlib"]);
+        assert_eq!(metadata.people["mix"], vec!["Dave Cooley"]);
+    }
+
+    #[test]
+    fn test_old_json_without_new_fields_loads() {
+        let json = r#"{"file_path":"a.mp3","artist":"X","title":null,"album":null,
+            "year":null,"genre":null,"track_number":1,"album_artist":null,"duration_seconds":null}"#;
+        let metadata: TrackMetadata = serde_json::from_str(json).unwrap();
+        assert_eq!(metadata.track_number, Some(1));
+        assert_eq!(metadata.artists, vec!["X"]);
+        assert!(metadata.genres.is_empty());
+        assert_eq!(metadata.composer, None);
+    }
+}
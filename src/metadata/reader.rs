@@ -1,9 +1,18 @@
 use crate::error::{AgentError, Result};
+use crate::metadata::encoding;
+use crate::metadata::formats::AudioFormat;
+use crate::metadata::lyrics::Lyrics;
+use crate::metadata::writer::UPDATED_DIR;
 use crate::metadata::TrackMetadata;
-use id3::{Tag, TagLike};
 use std::path::Path;
 
-/// Reads ID3 metadata from an MP3 file
+/// Files that sit beside tracks in a library and are never audio, so scans don't open them
+const SIDECAR_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "cue", "nfo", "lrc", "txt", "log", "m3u", "m3u8",
+    "pls", "sfv", "md5", "ffp", "accurip", "pdf", "json", "db", "ini",
+];
+
+/// Reads tag metadata from a supported audio file (MP3, FLAC, M4A, Ogg or Opus)
 pub fn read_metadata(file_path: &str) -> Result<TrackMetadata> {
     let path = Path::new(file_path);
 
@@ -15,36 +24,72 @@ pub fn read_metadata(file_path: &str) -> Result<TrackMetadata> {
         )));
     }
 
-    // Verify it's an MP3
-    if path.extension().and_then(|s| s.to_str()) != Some("mp3") {
+    // Pick the tag backend for what the file contains, whatever its name says
+    let format = AudioFormat::detect(path)?;
+
+    let mut metadata = format.backend().read(path)?;
+    metadata.file_path = file_path.to_string();
+    metadata.format_mismatch = format.extension_mismatch(path);
+    metadata.encoding_issues = encoding::find_issues(&metadata);
+
+    // Without embedded lyrics, an .lrc file beside the track still helps identify it
+    if metadata.lyrics.is_empty() {
+        if let Some(lyrics) = Lyrics::find_beside(path).and_then(|p| Lyrics::load(&p).ok()) {
+            metadata.lyrics.push(lyrics.info(".lrc sidecar"));
+        }
+    }
+    Ok(metadata)
+}
+
+/// Recursively lists the supported audio files under a directory, sorted by path
+///
+/// Files with an audio extension are listed even if their content turns out to be
+/// something else, so reading them reports the mismatch instead of skipping them. Only
+/// files with a missing or unfamiliar extension are opened to sniff their content.
+/// `updated/` directories hold this tool's own output copies and are skipped, so a
+/// rerun doesn't pick up (and copy again) what the last run wrote.
+pub fn find_audio_files(dir: &str) -> Result<Vec<String>> {
+    let root = Path::new(dir);
+    if !root.is_dir() {
         return Err(AgentError::FileRead(format!(
-            "Not an MP3 file: {}",
-            file_path
+            "Directory not found: {}",
+            dir
         )));
     }
 
-    // Read ID3 tags
-    let tag = Tag::read_from_path(path).map_err(|e| {
-        AgentError::MetadataParse(format!("Failed to read ID3 tags from {}: {}", file_path, e))
-    })?;
-
-    // Extract metadata
-    Ok(TrackMetadata {
-        file_path: file_path.to_string(),
-        artist: tag.artist().map(|s| s.to_string()),
-        title: tag.title().map(|s| s.to_string()),
-        album: tag.album().map(|s| s.to_string()),
-        year: tag.year(),
-        genre: tag.genre().map(|s| s.to_string()),
-        track_number: tag.track(),
-        album_artist: tag.album_artist().map(|s| s.to_string()),
-        duration_seconds: tag.duration(),
-    })
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if !path.ends_with(UPDATED_DIR) {
+                    pending.push(path);
+                }
+            } else if AudioFormat::from_extension(&path).is_some()
+                || (!is_sidecar(&path) && AudioFormat::from_path(&path).is_some())
+            {
+                files.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// True for cover art, cue sheets, logs and other non-audio files (by extension)
+fn is_sidecar(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| SIDECAR_EXTENSIONS.contains(&e.to_lowercase().as_str()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metadata::formats::test_mp3;
+    use std::fs;
 
     #[test]
     fn test_read_nonexistent_file() {
@@ -52,6 +97,30 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_find_audio_files_sniffs_unknown_extensions_only() {
+        let dir = std::env::temp_dir().join("music-agent-find-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mp3 = test_mp3(&[]);
+        // MP3 content behind a sidecar extension isn't opened; without one it's sniffed
+        for name in ["track", "cover.JPG", "rip.log", "song.mp3"] {
+            fs::write(dir.join(name), &mp3).unwrap();
+        }
+        fs::write(dir.join("notes.bin"), b"not audio").unwrap();
+        fs::create_dir_all(dir.join("updated")).unwrap();
+        fs::write(dir.join("updated").join("song.mp3"), &mp3).unwrap();
+
+        let files = find_audio_files(&dir.to_string_lossy()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<&str> = files
+            .iter()
+            .map(|f| Path::new(f).file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["song.mp3", "track"]);
+    }
+
     #[test]
     fn test_read_non_mp3() {
         let result = read_metadata("Cargo.toml");
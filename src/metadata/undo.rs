@@ -0,0 +1,447 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::formats::{audio_ranges, sha256_hex};
+use crate::metadata::writer::{move_file, replace_atomically};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Default location of the undo journal for in-place writes
+pub const DEFAULT_JOURNAL: &str = "public/undo/journal.jsonl";
+
+/// One file's state from before an in-place write or a rename
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UndoEntry {
+    pub run_id: String,
+    pub timestamp: String,
+    /// Absolute path of the changed file (where it was moved to, for renames)
+    pub file_path: String,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+/// What happened to the file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Change {
+    /// Tags rewritten in place
+    Tags {
+        /// SHA-256 of the audio data, which the write left unchanged
+        audio_hash: String,
+        /// SHA-256 of the whole file right after the write
+        written_hash: String,
+        /// Lengths of the non-audio segments stored in `backup`, in file order
+        segments: Vec<usize>,
+        /// File holding the original non-audio bytes, relative to the journal's directory
+        backup: String,
+    },
+    /// Moved by the rename command
+    Moved {
+        /// Absolute path the file had before
+        moved_from: String,
+    },
+}
+
+impl UndoEntry {
+    /// Save everything but the audio of `original` (the contents of `path` before the
+    /// write) beside the journal; `written` is what the file is about to become
+    pub fn backup(
+        journal: &str,
+        run_id: &str,
+        path: &Path,
+        original: &[u8],
+        written: &[u8],
+    ) -> Result<Self> {
+        let ranges = audio_ranges(path, original)?;
+        let segments = gaps(original.len(), &ranges);
+        let file_path = fs::canonicalize(path)?.to_string_lossy().to_string();
+
+        // Named after the path so runs can hold many files, numbered when a run writes
+        // the same file again so the first backup (the real original) is kept
+        let run_dir = journal_dir(journal).join(run_id);
+        fs::create_dir_all(&run_dir)
+            .map_err(|e| AgentError::FileRead(format!("Failed to create undo directory: {}", e)))?;
+        let stem = &sha256_hex([file_path.as_bytes()])[..16];
+        let mut name = format!("{}.tags", stem);
+        let mut n = 1;
+        while run_dir.join(&name).exists() {
+            n += 1;
+            name = format!("{}-{}.tags", stem, n);
+        }
+        let backup = format!("{}/{}", run_id, name);
+        let backup_path = run_dir.join(&name);
+        let saved: Vec<u8> = segments
+            .iter()
+            .flat_map(|r| original[r.clone()].iter().copied())
+            .collect();
+        fs::write(&backup_path, saved)
+            .map_err(|e| AgentError::FileRead(format!("Failed to write undo backup: {}", e)))?;
+
+        Ok(Self {
+            run_id: run_id.to_string(),
+            timestamp: chrono::Local::now().to_rfc3339(),
+            file_path,
+            change: Change::Tags {
+                audio_hash: sha256_hex(ranges.into_iter().map(|r| &original[r])),
+                written_hash: sha256_hex([written]),
+                segments: segments.iter().map(|r| r.len()).collect(),
+                backup,
+            },
+        })
+    }
+
+    /// Record that the file now at `to` was moved there from `from`
+    pub fn moved(run_id: &str, from: &Path, to: &Path) -> Result<Self> {
+        Ok(Self {
+            run_id: run_id.to_string(),
+            timestamp: chrono::Local::now().to_rfc3339(),
+            file_path: absolute(to)?,
+            change: Change::Moved {
+                moved_from: absolute(from)?,
+            },
+        })
+    }
+}
+
+/// Append-only log of in-place writes stored as JSON lines, with the old tag bytes
+/// kept in one directory per run beside it
+#[derive(Debug, Clone, Default)]
+pub struct UndoJournal {
+    path: String,
+    entries: Vec<UndoEntry>,
+}
+
+impl UndoJournal {
+    /// Load the journal, treating a missing file as empty
+    pub fn load(path: &str) -> Result<Self> {
+        let mut journal = Self {
+            path: path.to_string(),
+            entries: Vec::new(),
+        };
+
+        if !Path::new(path).exists() {
+            return Ok(journal);
+        }
+
+        let contents = fs::read_to_string(path)
+            .map_err(|e| AgentError::FileRead(format!("Failed to read undo journal: {}", e)))?;
+
+        for (i, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(line).map_err(|e| {
+                AgentError::MetadataParse(format!(
+                    "Invalid undo entry on line {} of {}: {}",
+                    i + 1,
+                    path,
+                    e
+                ))
+            })?;
+            journal.entries.push(entry);
+        }
+
+        Ok(journal)
+    }
+
+    /// Run ID for a new batch of in-place writes
+    pub fn new_run_id() -> String {
+        chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string()
+    }
+
+    /// Append an entry to the journal on disk without loading it
+    pub fn append(path: &str, entry: &UndoEntry) -> Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                AgentError::FileRead(format!("Failed to create undo directory: {}", e))
+            })?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| AgentError::FileRead(format!("Failed to open undo journal: {}", e)))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &[UndoEntry] {
+        &self.entries
+    }
+
+    /// Entries of a run ("last" for the most recent run)
+    pub fn run(&self, run_id: &str) -> Vec<UndoEntry> {
+        let run_id = match run_id {
+            "last" => match self.entries.last() {
+                Some(entry) => entry.run_id.as_str(),
+                None => return Vec::new(),
+            },
+            id => id,
+        };
+        self.entries
+            .iter()
+            .filter(|e| e.run_id == run_id)
+            .cloned()
+            .collect()
+    }
+
+    /// The latest entry for a file
+    pub fn latest_for(&self, file: &str) -> Result<Option<UndoEntry>> {
+        let file_path = fs::canonicalize(file)?.to_string_lossy().to_string();
+        Ok(self
+            .entries
+            .iter()
+            .rev()
+            .find(|e| e.file_path == file_path)
+            .cloned())
+    }
+
+    /// Undo the change recorded in `entry`
+    ///
+    /// Tags are put back around the file's current audio; this refuses if the audio
+    /// changed, or (unless `force`) if anything else touched the file after the write.
+    /// Moved files go back to their old path unless something else took it meanwhile.
+    pub fn restore(&self, entry: &UndoEntry, force: bool) -> Result<()> {
+        match &entry.change {
+            Change::Tags {
+                audio_hash,
+                written_hash,
+                segments,
+                backup,
+            } => self.restore_tags(entry, audio_hash, written_hash, segments, backup, force),
+            Change::Moved { moved_from } => restore_move(entry, moved_from),
+        }
+    }
+
+    fn restore_tags(
+        &self,
+        entry: &UndoEntry,
+        audio_hash: &str,
+        written_hash: &str,
+        segments: &[usize],
+        backup: &str,
+        force: bool,
+    ) -> Result<()> {
+        let path = Path::new(&entry.file_path);
+        let data = fs::read(path)?;
+        let ranges = audio_ranges(path, &data)?;
+
+        if sha256_hex(ranges.iter().map(|r| &data[r.clone()])) != audio_hash {
+            return Err(AgentError::AudioChanged(format!(
+                "{} no longer has the audio recorded in the undo journal; not restoring",
+                entry.file_path
+            )));
+        }
+        if !force && sha256_hex([data.as_slice()]) != written_hash {
+            return Err(AgentError::FileRead(format!(
+                "{} was changed after run {}; use --force to restore the old tags anyway",
+                entry.file_path, entry.run_id
+            )));
+        }
+
+        let backup_path = journal_dir(&self.path).join(backup);
+        let backup = fs::read(&backup_path).map_err(|e| {
+            AgentError::FileRead(format!(
+                "Failed to read undo backup {}: {}",
+                backup_path.display(),
+                e
+            ))
+        })?;
+        if segments.len() != ranges.len() + 1 || segments.iter().sum::<usize>() != backup.len() {
+            return Err(AgentError::MetadataParse(format!(
+                "Undo backup {} doesn't fit the layout of {}",
+                backup_path.display(),
+                entry.file_path
+            )));
+        }
+
+        // Saved segment, audio, saved segment, ..., saved segment
+        let mut restored = Vec::with_capacity(backup.len() + data.len());
+        let mut offset = 0;
+        for (i, len) in segments.iter().enumerate() {
+            restored.extend_from_slice(&backup[offset..offset + len]);
+            offset += len;
+            if let Some(audio) = ranges.get(i) {
+                restored.extend_from_slice(&data[audio.clone()]);
+            }
+        }
+        replace_atomically(path, &restored)
+    }
+
+    /// Drop restored entries from the journal and delete their backups
+    pub fn remove(&mut self, restored: &[UndoEntry]) -> Result<()> {
+        let dir = journal_dir(&self.path);
+        for entry in restored {
+            if let Change::Tags { backup, .. } = &entry.change {
+                let _ = fs::remove_file(dir.join(backup));
+                let _ = fs::remove_dir(dir.join(&entry.run_id));
+            }
+        }
+        self.entries.retain(|e| !restored.contains(e));
+
+        let mut contents = String::new();
+        for entry in &self.entries {
+            contents.push_str(&serde_json::to_string(entry)?);
+            contents.push('\n');
+        }
+        fs::write(&self.path, contents)
+            .map_err(|e| AgentError::FileRead(format!("Failed to update undo journal: {}", e)))
+    }
+}
+
+/// Move a renamed file back, then drop the directories the rename left empty
+fn restore_move(entry: &UndoEntry, moved_from: &str) -> Result<()> {
+    let path = Path::new(&entry.file_path);
+    let original = Path::new(moved_from);
+    if !path.exists() {
+        return Err(AgentError::FileRead(format!(
+            "{} no longer exists; not moving it back",
+            entry.file_path
+        )));
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    move_file(path, original)?;
+    remove_empty_dirs(path, original);
+    Ok(())
+}
+
+/// Remove the now-empty directories above `moved`, stopping at the first one that
+/// isn't empty or that also holds `kept`
+pub fn remove_empty_dirs(moved: &Path, kept: &Path) {
+    for dir in moved.ancestors().skip(1) {
+        if kept.starts_with(dir) || fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+/// Absolute path of a file that may not exist yet, resolving its directory
+fn absolute(path: &Path) -> Result<String> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::canonicalize(parent)?,
+        _ => std::env::current_dir()?,
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| AgentError::FileRead(format!("Not a file path: {}", path.display())))?;
+    Ok(parent.join(name).to_string_lossy().to_string())
+}
+
+fn journal_dir(journal: &str) -> PathBuf {
+    Path::new(journal)
+        .parent()
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
+
+/// The byte ranges before, between and after the audio ranges
+fn gaps(len: usize, audio: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut gaps = Vec::new();
+    let mut start = 0;
+    for range in audio {
+        gaps.push(start..range.start);
+        start = range.end;
+    }
+    gaps.push(start..len);
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::formats::test_mp3;
+    use crate::metadata::writer::{write_metadata_safely, InPlace, WriteOptions};
+    use crate::metadata::TrackMetadata;
+
+    #[test]
+    fn test_in_place_write_and_restore() {
+        let dir = std::env::temp_dir().join("music-agent-undo-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // ID3v2.3 tag, one MPEG frame, ID3v1 tag
+        let mut original = test_mp3(&[]);
+        original.extend_from_slice(b"TAG");
+        original.resize(original.len() + 125, 0);
+        let track = dir.join("song.mp3");
+        fs::write(&track, &original).unwrap();
+
+        let journal_file = dir.join("undo").join("journal.jsonl");
+        let journal_file = journal_file.to_string_lossy().to_string();
+        let options = WriteOptions {
+            consolidate: true,
+            in_place: Some(InPlace {
+                journal: journal_file.clone(),
+                run_id: "run-1".to_string(),
+            }),
+            ..Default::default()
+        };
+        let metadata = TrackMetadata {
+            artists: vec!["Joey Bada$$".to_string()],
+            ..Default::default()
+        };
+        write_metadata_safely(&track.to_string_lossy(), &metadata, &options).unwrap();
+        let written = fs::read(&track).unwrap();
+
+        let mut journal = UndoJournal::load(&journal_file).unwrap();
+        let entries = journal.run("last");
+        assert_eq!(entries.len(), 1);
+        journal.restore(&entries[0], false).unwrap();
+        journal.remove(&entries).unwrap();
+        let restored = fs::read(&track).unwrap();
+        let remaining = UndoJournal::load(&journal_file).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_ne!(written, original);
+        assert_eq!(restored, original);
+        assert!(remaining.entries().is_empty());
+    }
+
+    #[test]
+    fn test_same_file_twice_in_one_run() {
+        let dir = std::env::temp_dir().join("music-agent-undo-twice-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let original = test_mp3(&[]);
+        let track = dir.join("song.mp3");
+        fs::write(&track, &original).unwrap();
+
+        let journal_file = dir.join("undo").join("journal.jsonl");
+        let journal_file = journal_file.to_string_lossy().to_string();
+        let options = WriteOptions {
+            in_place: Some(InPlace {
+                journal: journal_file.clone(),
+                run_id: "run-1".to_string(),
+            }),
+            ..Default::default()
+        };
+        for artist in ["Joey Bada$$", "Capital STEEZ"] {
+            let metadata = TrackMetadata {
+                artists: vec![artist.to_string()],
+                ..Default::default()
+            };
+            write_metadata_safely(&track.to_string_lossy(), &metadata, &options).unwrap();
+        }
+
+        let mut journal = UndoJournal::load(&journal_file).unwrap();
+        let entries = journal.run("run-1");
+        for entry in entries.iter().rev() {
+            journal.restore(entry, false).unwrap();
+        }
+        journal.remove(&entries).unwrap();
+        let restored = fs::read(&track).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(restored, original);
+    }
+}
@@ -1,82 +1,315 @@
 use crate::error::{AgentError, Result};
-use crate::metadata::TrackMetadata;
-use id3::{Tag, TagLike, Version};
+use crate::metadata::artwork::CoverArt;
+use crate::metadata::formats::{audio_hash, AudioFormat, Id3Version, TagBackend};
+use crate::metadata::lyrics::Lyrics;
+use crate::metadata::undo::{UndoEntry, UndoJournal};
+use crate::metadata::{is_known_field, TrackMetadata};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-/// Writes ID3 metadata to a NEW copy of an MP3 file (never overwrites original)
-pub fn write_metadata_safely(original_file: &str, metadata: &TrackMetadata) -> Result<String> {
-    let original_path = Path::new(original_file);
+/// Directory that copies with applied changes are written to
+pub const UPDATED_DIR: &str = "updated";
 
-    // Verify original file exists
-    if !original_path.exists() {
-        return Err(AgentError::FileRead(format!(
-            "File not found: {}",
-            original_file
+/// Extra changes made to the copy besides the tag fields
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Strip legacy tags (ID3v1, APEv2) once their values are merged into the primary tag
+    pub consolidate: bool,
+    /// Image to embed as the front cover
+    pub cover_art: Option<CoverArt>,
+    /// Lyrics to embed, replacing any already in the file
+    pub lyrics: Option<Lyrics>,
+    /// Fields to delete from the file (fields that are just unset are left alone)
+    pub remove_fields: Vec<String>,
+    /// ID3v2 version to write (ID3v2.4 if unset); ignored for other tag formats
+    pub id3_version: Option<Id3Version>,
+    /// Change the original file instead of writing a copy
+    pub in_place: Option<InPlace>,
+}
+
+/// Where in-place writes save the old tags so `undo` can put them back
+#[derive(Debug, Clone)]
+pub struct InPlace {
+    pub journal: String,
+    pub run_id: String,
+}
+
+/// A file written by the writer
+#[derive(Debug, Clone)]
+pub struct WrittenFile {
+    pub path: String,
+    /// SHA-256 of the audio data, checked to be the same before and after the write
+    pub audio_hash: String,
+}
+
+/// Writes tag metadata to a NEW copy of an audio file (never overwrites original),
+/// or into the original with `options.in_place`
+pub fn write_metadata_safely(
+    original_file: &str,
+    metadata: &TrackMetadata,
+    options: &WriteOptions,
+) -> Result<WrittenFile> {
+    if let Some(field) = options.remove_fields.iter().find(|f| !is_known_field(f)) {
+        return Err(AgentError::MetadataParse(format!(
+            "Cannot remove unknown field: {}",
+            field
         )));
     }
 
-    // Create output path with .updated.mp3 suffix
-    let output_path = create_output_path(original_path);
+    // Values only a legacy tag holds stay there unless consolidating, so ID3v1's
+    // 30-character truncations aren't copied into ID3v2 behind the user's back
+    let mut primary = metadata.clone();
+    if !options.consolidate {
+        for field in &metadata.legacy_fields {
+            primary.clear_field(field);
+        }
+    }
 
-    // Copy original to new file
-    fs::copy(original_path, &output_path)
-        .map_err(|e| AgentError::FileRead(format!("Failed to create output file: {}", e)))?;
+    let change = |backend: &dyn TagBackend, output_path: &Path| {
+        backend.write(output_path, &primary)?;
+        if !options.remove_fields.is_empty() {
+            backend.remove_fields(output_path, &options.remove_fields)?;
+        }
+        if let Some(ref art) = options.cover_art {
+            backend.embed_cover(output_path, art)?;
+        }
+        if let Some(ref lyrics) = options.lyrics {
+            backend.embed_lyrics(output_path, lyrics)?;
+        }
+        if options.consolidate {
+            backend.strip_legacy_tags(output_path)?;
+        }
+        if let Some(version) = options.id3_version {
+            backend.convert_id3_version(output_path, version)?;
+        }
+        Ok(())
+    };
+    match options.in_place {
+        Some(ref in_place) => write_in_place(original_file, in_place, change),
+        None => write_copy(original_file, change),
+    }
+}
 
-    // Read existing tag or create new one
-    let mut tag = Tag::read_from_path(&output_path).unwrap_or_else(|_| Tag::new());
+/// Converts the ID3v2 tag of an audio file to `version`, in a NEW copy or (with
+/// `in_place`) in the original
+///
+/// Returns None (and writes nothing) if the file has no ID3v2 tag or already uses `version`.
+pub fn convert_tags_safely(
+    original_file: &str,
+    version: Id3Version,
+    in_place: Option<&InPlace>,
+) -> Result<Option<WrittenFile>> {
+    let mut converted = false;
+    let change = |backend: &dyn TagBackend, output_path: &Path| {
+        converted = backend.convert_id3_version(output_path, version)?;
+        Ok(())
+    };
+    let written = match in_place {
+        Some(in_place) => write_in_place(original_file, in_place, change)?,
+        None => write_copy(original_file, change)?,
+    };
 
-    // Update fields if provided
-    if let Some(ref artist) = metadata.artist {
-        tag.set_artist(artist);
+    if !converted {
+        if in_place.is_none() {
+            let _ = fs::remove_file(&written.path);
+        }
+        return Ok(None);
     }
+    Ok(Some(written))
+}
 
-    if let Some(ref title) = metadata.title {
-        tag.set_title(title);
+/// Replace a file's contents via a temporary file and a rename, so it is never left
+/// half-written
+pub fn replace_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    let temp = temp_path(path);
+    let written = fs::copy(path, &temp)
+        .and_then(|_| fs::write(&temp, data))
+        .and_then(|_| fs::rename(&temp, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(AgentError::FileRead(format!(
+            "Failed to replace {}: {}",
+            path.display(),
+            e
+        )));
     }
+    Ok(())
+}
 
-    if let Some(ref album) = metadata.album {
-        tag.set_album(album);
+/// Move a file without replacing anything at `to`, copying it when `to` is on
+/// another filesystem
+pub fn move_file(from: &Path, to: &Path) -> Result<()> {
+    let failed = |e: std::io::Error| {
+        AgentError::FileRead(format!(
+            "Failed to move {} to {}: {}",
+            from.display(),
+            to.display(),
+            e
+        ))
+    };
+    if to.exists() {
+        return Err(AgentError::FileRead(format!(
+            "Not moving {}: {} already exists",
+            from.display(),
+            to.display()
+        )));
     }
 
-    if let Some(year) = metadata.year {
-        tag.set_year(year);
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            let temp = temp_path(to);
+            let copied = fs::copy(from, &temp).and_then(|_| fs::rename(&temp, to));
+            if let Err(e) = copied {
+                let _ = fs::remove_file(&temp);
+                return Err(failed(e));
+            }
+            fs::remove_file(from).map_err(failed)
+        }
+        moved => moved.map_err(failed),
     }
+}
+
+/// Copy the original under updated/ and let `change` edit the copy with the container's
+/// backend; the copy is deleted if `change` fails or touches the audio data
+fn write_copy(
+    original_file: &str,
+    change: impl FnOnce(&dyn TagBackend, &Path) -> Result<()>,
+) -> Result<WrittenFile> {
+    let original_path = Path::new(original_file);
+    let backend = backend_for(original_path)?;
+    let original_hash = audio_hash(original_path)?;
+
+    // Create output path under updated/ keeping the original extension
+    let output_path = create_output_path(original_path);
 
-    if let Some(ref genre) = metadata.genre {
-        tag.set_genre(genre);
+    // Copy original to new file
+    fs::copy(original_path, &output_path)
+        .map_err(|e| AgentError::FileRead(format!("Failed to create output file: {}", e)))?;
+
+    // Change the NEW file with the container's backend, then make sure only tags changed
+    if let Err(e) = change_verified(backend, &output_path, &original_hash, change) {
+        let _ = fs::remove_file(&output_path);
+        return Err(e);
     }
 
-    if let Some(track) = metadata.track_number {
-        tag.set_track(track);
+    Ok(WrittenFile {
+        path: output_path.to_string_lossy().to_string(),
+        audio_hash: original_hash,
+    })
+}
+
+/// Let `change` edit a temporary copy of the original, journal the original's tags and
+/// rename the copy over the original; nothing is journaled if the bytes didn't change
+fn write_in_place(
+    original_file: &str,
+    in_place: &InPlace,
+    change: impl FnOnce(&dyn TagBackend, &Path) -> Result<()>,
+) -> Result<WrittenFile> {
+    let original_path = Path::new(original_file);
+    let backend = backend_for(original_path)?;
+    let original = fs::read(original_path)?;
+    let original_hash = audio_hash(original_path)?;
+
+    // Same directory, so the rename can't cross file systems
+    let temp = temp_path(original_path);
+    fs::copy(original_path, &temp)
+        .map_err(|e| AgentError::FileRead(format!("Failed to create temporary file: {}", e)))?;
+
+    let replaced = change_verified(backend, &temp, &original_hash, change).and_then(|_| {
+        let written = fs::read(&temp)?;
+        if written == original {
+            return Ok(false);
+        }
+        let entry = UndoEntry::backup(
+            &in_place.journal,
+            &in_place.run_id,
+            original_path,
+            &original,
+            &written,
+        )?;
+        // Journaled first so a replaced file always has its undo entry; the entry is
+        // dropped again if the file can't be replaced
+        UndoJournal::append(&in_place.journal, &entry)?;
+        if let Err(e) = fs::rename(&temp, original_path) {
+            UndoJournal::load(&in_place.journal)?.remove(&[entry])?;
+            return Err(AgentError::FileRead(format!(
+                "Failed to replace {}: {}",
+                original_file, e
+            )));
+        }
+        Ok(true)
+    });
+    if !matches!(replaced, Ok(true)) {
+        let _ = fs::remove_file(&temp);
     }
+    replaced?;
 
-    if let Some(ref album_artist) = metadata.album_artist {
-        tag.set_album_artist(album_artist);
+    Ok(WrittenFile {
+        path: original_file.to_string(),
+        audio_hash: original_hash,
+    })
+}
+
+/// The backend for an existing file of a supported type
+fn backend_for(path: &Path) -> Result<&'static dyn TagBackend> {
+    // Verify original file exists
+    if !path.exists() {
+        return Err(AgentError::FileRead(format!(
+            "File not found: {}",
+            path.display()
+        )));
     }
 
-    // Write to the NEW file with ID3v2.4
-    tag.write_to_path(&output_path, Version::Id3v24)
-        .map_err(|e| AgentError::MetadataParse(format!("Failed to write ID3 tags: {}", e)))?;
+    AudioFormat::detect(path).map(|format| format.backend())
+}
+
+/// Run `change` on `path`, then check its audio still hashes to `original_hash`
+fn change_verified(
+    backend: &dyn TagBackend,
+    path: &Path,
+    original_hash: &str,
+    change: impl FnOnce(&dyn TagBackend, &Path) -> Result<()>,
+) -> Result<()> {
+    change(backend, path)?;
+    let output_hash = audio_hash(path)?;
+    if output_hash != original_hash {
+        return Err(AgentError::AudioChanged(format!(
+            "writing tags altered the audio data (SHA-256 {} vs {}); the change was discarded",
+            output_hash, original_hash
+        )));
+    }
+    Ok(())
+}
 
-    Ok(output_path.to_string_lossy().to_string())
+/// Hidden sibling of `path` keeping its extension, e.g. ".song.tmp.mp3"
+fn temp_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!(".{}.tmp.{}", stem, extension.to_string_lossy()),
+        None => format!(".{}.tmp", stem),
+    };
+    path.with_file_name(name)
 }
 
 /// Create a safe output path that doesn't overwrite the original
 /// Saves to public/updated/ directory
 fn create_output_path(original: &Path) -> PathBuf {
     let stem = original.file_stem().unwrap_or_default();
+    let extension = original
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| "mp3".to_string());
 
     // Determine the public/updated directory
     let updated_dir = if let Some(parent) = original.parent() {
         // If file is in public/originals/, use public/updated/
         if parent.ends_with("originals") {
-            parent.parent().unwrap_or(parent).join("updated")
+            parent.parent().unwrap_or(parent).join(UPDATED_DIR)
         } else if parent.ends_with("public") {
-            parent.join("updated")
+            parent.join(UPDATED_DIR)
         } else {
             // For other locations, create updated/ subdirectory
-            parent.join("updated")
+            parent.join(UPDATED_DIR)
         }
     } else {
         PathBuf::from("public/updated")
@@ -86,12 +319,17 @@ fn create_output_path(original: &Path) -> PathBuf {
     let _ = fs::create_dir_all(&updated_dir);
 
     // Create filename like "02 Friend of the Devil.mp3" (no .updated suffix needed since it's in updated/)
-    let mut output_path = updated_dir.join(format!("{}.mp3", stem.to_string_lossy()));
+    let mut output_path = updated_dir.join(format!("{}.{}", stem.to_string_lossy(), extension));
 
     // If file already exists, add number suffix
     let mut counter = 1;
     while output_path.exists() {
-        output_path = updated_dir.join(format!("{}-{}.mp3", stem.to_string_lossy(), counter));
+        output_path = updated_dir.join(format!(
+            "{}-{}.{}",
+            stem.to_string_lossy(),
+            counter,
+            extension
+        ));
         counter += 1;
     }
 
@@ -101,11 +339,156 @@ fn create_output_path(original: &Path) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metadata::formats::{id3_frame, test_mp3};
+    use crate::metadata::reader;
+    use id3::{Tag, TagLike};
+
+    /// An ID3v2.3 tag with a title and an English comment, one MPEG frame, and an
+    /// ID3v1 tag naming `v1_album` if given
+    fn write_mp3(dir: &Path, v1_album: Option<&str>) -> PathBuf {
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir).unwrap();
+
+        let mut data = test_mp3(&id3_frame("COMM", b"\x00eng\x00Pro Era!"));
+        if let Some(album) = v1_album {
+            let mut v1 = b"TAG".to_vec();
+            v1.resize(63, 0);
+            v1.extend_from_slice(album.as_bytes());
+            v1.resize(127, 0);
+            v1.push(255);
+            data.extend_from_slice(&v1);
+        }
+
+        let path = dir.join("song.mp3");
+        fs::write(&path, &data).unwrap();
+        path
+    }
 
     #[test]
     fn test_output_path_creation() {
-        let path = Path::new("test/song.mp3");
-        let output = create_output_path(path);
-        assert!(output.to_string_lossy().contains(".updated.mp3"));
+        let dir = std::env::temp_dir()
+            .join("music-agent-writer-test")
+            .join("originals");
+        let output = create_output_path(&dir.join("song.mp3"));
+        assert!(output.ends_with("updated/song.mp3"));
+
+        let output = create_output_path(&dir.join("song.flac"));
+        assert!(output.ends_with("updated/song.flac"));
+    }
+
+    #[test]
+    fn test_legacy_values_stay_out_of_id3v2() {
+        let dir = std::env::temp_dir().join("music-agent-writer-legacy-test");
+        let track = write_mp3(&dir, Some("Madvillainy"));
+        let track = track.to_string_lossy().to_string();
+
+        let mut metadata = reader::read_metadata(&track).unwrap();
+        assert_eq!(metadata.album.as_deref(), Some("Madvillainy"));
+        assert_eq!(metadata.legacy_fields, vec!["album"]);
+        metadata.set_field("genre", "Hip-Hop");
+
+        let copy = write_metadata_safely(&track, &metadata, &WriteOptions::default()).unwrap();
+        let kept = Tag::read_from_path(&copy.path).unwrap();
+        let consolidate = WriteOptions {
+            consolidate: true,
+            ..Default::default()
+        };
+        let merged = write_metadata_safely(&track, &metadata, &consolidate).unwrap();
+        let merged = Tag::read_from_path(&merged.path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(kept.genre(), Some("Hip-Hop"));
+        assert_eq!(kept.album(), None);
+        assert_eq!(merged.album(), Some("Madvillainy"));
+    }
+
+    #[test]
+    fn test_removed_field_is_gone_after_write() {
+        let dir = std::env::temp_dir().join("music-agent-writer-remove-test");
+        let track = write_mp3(&dir, None);
+        let track = track.to_string_lossy().to_string();
+
+        let mut metadata = reader::read_metadata(&track).unwrap();
+        assert_eq!(metadata.comment.as_deref(), Some("Pro Era!"));
+        metadata.clear_field("comment");
+        let options = WriteOptions {
+            remove_fields: vec!["comment".to_string()],
+            ..Default::default()
+        };
+        let copy = write_metadata_safely(&track, &metadata, &options).unwrap();
+        let reread = reader::read_metadata(&copy.path).unwrap();
+        let tag = Tag::read_from_path(&copy.path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(reread.comment, None);
+        assert_eq!(reread.title.as_deref(), Some("Pennyroyal"));
+        assert_eq!(tag.comments().count(), 0);
+    }
+
+    #[test]
+    fn test_changed_audio_is_discarded() {
+        let dir = std::env::temp_dir().join("music-agent-writer-verify-test");
+        let track = write_mp3(&dir, None);
+        let original = fs::read(&track).unwrap();
+        let journal = dir.join("undo").join("journal.jsonl");
+        let in_place = InPlace {
+            journal: journal.to_string_lossy().to_string(),
+            run_id: "run-1".to_string(),
+        };
+
+        // Flip the last byte of the audio
+        let corrupt = |_: &dyn TagBackend, path: &Path| {
+            let mut data = fs::read(path)?;
+            let last = data.len() - 1;
+            data[last] ^= 0xff;
+            fs::write(path, data)?;
+            Ok(())
+        };
+        let copy = write_copy(&track.to_string_lossy(), corrupt);
+        let replaced = write_in_place(&track.to_string_lossy(), &in_place, corrupt);
+        let unchanged = fs::read(&track).unwrap();
+        let left_over: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        let updated = fs::read_dir(dir.join("updated")).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(copy, Err(AgentError::AudioChanged(_))));
+        assert!(matches!(replaced, Err(AgentError::AudioChanged(_))));
+        assert_eq!(unchanged, original);
+        assert_eq!(updated, 0);
+        assert_eq!(left_over.len(), 2, "{:?}", left_over);
+        assert!(!journal.exists());
+    }
+
+    #[test]
+    fn test_failed_replace_leaves_no_undo_entry() {
+        let dir = std::env::temp_dir().join("music-agent-writer-replace-test");
+        let track = write_mp3(&dir, None);
+        let journal = dir.join("undo").join("journal.jsonl");
+        let in_place = InPlace {
+            journal: journal.to_string_lossy().to_string(),
+            run_id: "run-1".to_string(),
+        };
+
+        let metadata = TrackMetadata {
+            title: Some("Accordion".to_string()),
+            ..Default::default()
+        };
+        // Something puts a directory where the track was, so the rename must fail
+        let replaced = write_in_place(&track.to_string_lossy(), &in_place, |backend, path| {
+            backend.write(path, &metadata)?;
+            fs::remove_file(&track)?;
+            fs::create_dir_all(track.join("blocker"))?;
+            Ok(())
+        });
+        let remaining = UndoJournal::load(&in_place.journal).unwrap();
+        let backups = fs::read_dir(dir.join("undo")).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(replaced.is_err());
+        assert!(remaining.entries().is_empty());
+        assert_eq!(backups, 1, "only the journal file should be left");
     }
 }
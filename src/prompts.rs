@@ -0,0 +1,233 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::TrackMetadata;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+const DEFAULT_ANALYSIS: &str = include_str!("../prompts/default/analysis.txt");
+const DEFAULT_SUGGESTIONS: &str = include_str!("../prompts/default/suggestions.txt");
+
+/// File names looked up inside a template directory
+const ANALYSIS_FILE: &str = "analysis.txt";
+const SUGGESTIONS_FILE: &str = "suggestions.txt";
+
+/// Identifies which template produced a prompt (recorded in suggestion reports)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub hash: String,
+}
+
+/// A single prompt template with `{{placeholder}}` markers for metadata fields
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub body: String,
+}
+
+impl PromptTemplate {
+    pub fn new(name: &str, body: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    /// Short content hash so reports can tell template revisions apart
+    pub fn hash(&self) -> String {
+        let digest = Sha256::digest(self.body.as_bytes());
+        digest
+            .iter()
+            .take(6)
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    pub fn info(&self) -> TemplateInfo {
+        TemplateInfo {
+            name: self.name.clone(),
+            hash: self.hash(),
+        }
+    }
+
+    /// True if the body contains `{{key}}`
+    pub fn has_placeholder(&self, key: &str) -> bool {
+        self.body.contains(&format!("{{{{{}}}}}", key))
+    }
+
+    /// Substitute metadata placeholders into the template
+    ///
+    /// Supported placeholders: `{{metadata}}` (the full prompt block), `{{file_path}}`,
+    /// `{{artist}}`, `{{title}}`, `{{album}}`, `{{year}}`, `{{genre}}`,
+    /// `{{track_number}}`, `{{album_artist}}`, `{{duration}}` and `{{missing_fields}}`.
+    /// `{{examples}}` renders empty; see [`PromptTemplate::render_with_examples`].
+    pub fn render(&self, metadata: &TrackMetadata) -> String {
+        self.render_with_examples(metadata, "")
+    }
+
+    /// Render the template with a block of few-shot examples for `{{examples}}`
+    pub fn render_with_examples(&self, metadata: &TrackMetadata, examples: &str) -> String {
+        let missing = metadata.missing_fields();
+        let values = [
+            ("examples", examples.to_string()),
+            ("metadata", metadata.to_prompt_format()),
+            ("file_path", metadata.file_path.clone()),
+            ("artist", or_missing(metadata.artist())),
+            ("title", or_missing(metadata.title.clone())),
+            ("album", or_missing(metadata.album.clone())),
+            ("year", or_missing(metadata.year.map(|y| y.to_string()))),
+            ("genre", or_missing(metadata.genre())),
+            (
+                "track_number",
+                or_missing(metadata.track_number.map(|t| t.to_string())),
+            ),
+            ("album_artist", or_missing(metadata.album_artist.clone())),
+            (
+                "duration",
+                metadata
+                    .duration_seconds
+                    .map(|d| d.to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+            ),
+            (
+                "missing_fields",
+                if missing.is_empty() {
+                    "None".to_string()
+                } else {
+                    missing.join(", ")
+                },
+            ),
+        ];
+
+        let mut rendered = self.body.clone();
+        for (key, value) in values {
+            rendered = rendered.replace(&format!("{{{{{}}}}}", key), &value);
+        }
+        rendered.trim_end().to_string()
+    }
+}
+
+fn or_missing(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "(missing)".to_string())
+}
+
+/// The pair of templates used by the agent's two prompting modes
+#[derive(Debug, Clone)]
+pub struct PromptTemplates {
+    pub analysis: PromptTemplate,
+    pub suggestions: PromptTemplate,
+}
+
+impl Default for PromptTemplates {
+    fn default() -> Self {
+        Self {
+            analysis: PromptTemplate::new("default", DEFAULT_ANALYSIS),
+            suggestions: PromptTemplate::new("default", DEFAULT_SUGGESTIONS),
+        }
+    }
+}
+
+impl PromptTemplates {
+    /// Load templates from a directory containing `analysis.txt` and/or `suggestions.txt`
+    ///
+    /// Any file that is absent falls back to the built-in default. The directory name
+    /// becomes the template name recorded in reports.
+    pub fn load_from_dir(dir: &str) -> Result<Self> {
+        let path = Path::new(dir);
+        if !path.is_dir() {
+            return Err(AgentError::FileRead(format!(
+                "Template directory not found: {}",
+                dir
+            )));
+        }
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| dir.to_string());
+
+        let mut templates = Self::default();
+        for (file, template) in [
+            (ANALYSIS_FILE, &mut templates.analysis),
+            (SUGGESTIONS_FILE, &mut templates.suggestions),
+        ] {
+            let file_path = path.join(file);
+            if file_path.exists() {
+                let body = fs::read_to_string(&file_path).map_err(|e| {
+                    AgentError::FileRead(format!(
+                        "Failed to read template {}: {}",
+                        file_path.display(),
+                        e
+                    ))
+                })?;
+                *template = PromptTemplate::new(&name, &body);
+            }
+        }
+
+        Ok(templates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_metadata() -> TrackMetadata {
+        TrackMetadata {
+            file_path: "song.mp3".to_string(),
+            artists: vec!["Grateful Dead".to_string()],
+            title: Some("Friend of the Devil".to_string()),
+            album: None,
+            year: Some(1970),
+            genres: Vec::new(),
+            track_number: Some(2),
+            album_artist: None,
+            duration_seconds: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_placeholders() {
+        let template = PromptTemplate::new("t", "{{artist}} - {{title}} ({{year}}) [{{album}}]");
+        assert_eq!(
+            template.render(&sample_metadata()),
+            "Grateful Dead - Friend of the Devil (1970) [(missing)]"
+        );
+    }
+
+    #[test]
+    fn test_default_template_embeds_metadata_block() {
+        let metadata = sample_metadata();
+        let rendered = PromptTemplates::default().suggestions.render(&metadata);
+        assert!(rendered.starts_with("You are a music metadata expert."));
+        assert!(rendered.ends_with(&metadata.to_prompt_format()));
+    }
+
+    #[test]
+    fn test_examples_placeholder() {
+        let template = PromptTemplate::new("t", "{{examples}}{{title}}");
+        let metadata = sample_metadata();
+        assert_eq!(template.render(&metadata), "Friend of the Devil");
+        assert_eq!(
+            template.render_with_examples(&metadata, "EXAMPLE\n"),
+            "EXAMPLE\nFriend of the Devil"
+        );
+        assert!(template.has_placeholder("examples"));
+        assert!(!PromptTemplate::new("t", "{{title}}").has_placeholder("examples"));
+    }
+
+    #[test]
+    fn test_hash_changes_with_body() {
+        let a = PromptTemplate::new("a", "one");
+        let b = PromptTemplate::new("a", "two");
+        assert_ne!(a.hash(), b.hash());
+        assert_eq!(a.hash().len(), 12);
+    }
+
+    #[test]
+    fn test_load_missing_dir() {
+        assert!(PromptTemplates::load_from_dir("no/such/templates").is_err());
+    }
+}
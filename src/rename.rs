@@ -0,0 +1,527 @@
+use crate::error::{AgentError, Result};
+use crate::metadata::formats::AudioFormat;
+use crate::metadata::lyrics::Lyrics;
+use crate::metadata::undo::{remove_empty_dirs, UndoEntry, UndoJournal};
+use crate::metadata::writer::move_file;
+use crate::metadata::{reader, TrackMetadata};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Album artist folder, album folder, then the disc (multi-disc albums only) and track
+pub const DEFAULT_PATTERN: &str = "{album_artist}/{year} - {album}/{disc}-{track:02} {title}";
+/// Compilations also name each track's artist
+pub const DEFAULT_COMPILATION_PATTERN: &str =
+    "{album_artist}/{year} - {album}/{disc}-{track:02} {artist} - {title}";
+
+/// Placeholders a pattern may use; numbers take a zero-padded width, e.g. `{track:02}`
+pub const PLACEHOLDERS: &[&str] = &[
+    "artist",
+    "album_artist",
+    "album",
+    "title",
+    "year",
+    "track",
+    "total_tracks",
+    "disc",
+    "total_discs",
+    "genre",
+    "composer",
+    "publisher",
+];
+
+/// Album artist of compilations that don't name one
+const VARIOUS_ARTISTS: &str = "Various Artists";
+/// Characters Windows, macOS or Linux don't allow in file names
+const ILLEGAL_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+/// Left over at the ends of a name when a placeholder is empty, e.g. "{year} - {album}"
+const SEPARATORS: &[char] = &[' ', '-', '_', ',', '.'];
+/// Longest file or directory name most filesystems allow, in bytes
+const MAX_NAME_LEN: usize = 255;
+
+/// A parsed path pattern such as `{album_artist}/{album}/{track:02} {title}`
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field { name: String, width: usize },
+}
+
+impl Template {
+    /// Parse a pattern; a trailing ".mp3" or ".{ext}" is dropped since every file keeps
+    /// the extension of its format
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let invalid = |why: String| {
+            AgentError::MetadataParse(format!("Invalid rename pattern \"{}\": {}", pattern, why))
+        };
+
+        let mut parts = Vec::new();
+        let mut rest = strip_extension(pattern);
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                parts.push(Part::Text(rest[..open].to_string()));
+            }
+            let close = rest[open..]
+                .find('}')
+                .ok_or_else(|| invalid("unclosed \"{\"".to_string()))?
+                + open;
+            let (name, width) = match rest[open + 1..close].split_once(':') {
+                Some((name, width)) => (
+                    name,
+                    width
+                        .parse()
+                        .map_err(|_| invalid(format!("bad width \"{}\"", width)))?,
+                ),
+                None => (&rest[open + 1..close], 0),
+            };
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(invalid(format!(
+                    "unknown placeholder {{{}}} (expected one of {})",
+                    name,
+                    PLACEHOLDERS.join(", ")
+                )));
+            }
+            parts.push(Part::Field {
+                name: name.to_string(),
+                width,
+            });
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        if !parts.iter().any(|p| matches!(p, Part::Field { .. })) {
+            return Err(invalid("no placeholders".to_string()));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Relative path for `track`, ending in `extension`
+    ///
+    /// Values can't add directories ("AC/DC" becomes "AC_DC"); a name left with stray
+    /// separators by an empty value ("{disc}-" on a single-disc album) is tidied up.
+    pub fn render(&self, track: &Track, extension: &str) -> PathBuf {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => rendered.push_str(text),
+                Part::Field { name, width } => {
+                    let value = track.value(name);
+                    let value = match value.parse::<u32>() {
+                        Ok(number) if *width > 0 => format!("{:0width$}", number, width = width),
+                        _ => value,
+                    };
+                    rendered.push_str(&value.replace(ILLEGAL_CHARS, "_"));
+                }
+            }
+        }
+
+        let names: Vec<String> = rendered.split('/').filter_map(clean_name).collect();
+        let mut path: PathBuf = names.iter().collect();
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| track.fallback_title.clone());
+        if extension.is_empty() {
+            path.set_file_name(file_name);
+        } else {
+            let stem = truncate(&file_name, MAX_NAME_LEN - extension.len() - 1);
+            path.set_file_name(format!("{}.{}", stem, extension));
+        }
+        path
+    }
+}
+
+/// A pattern without a trailing ".{ext}" or audio file extension such as ".mp3"
+pub fn strip_extension(pattern: &str) -> &str {
+    match pattern.strip_suffix(".{ext}") {
+        Some(rest) => rest,
+        None if AudioFormat::from_extension(Path::new(pattern)).is_some() => {
+            &pattern[..pattern.rfind('.').unwrap_or(pattern.len())]
+        }
+        None => pattern,
+    }
+}
+
+/// A file name with illegal characters replaced and stray separators trimmed, or None if
+/// nothing is left
+fn clean_name(name: &str) -> Option<String> {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() { '_' } else { c })
+        .collect();
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = name.trim_matches(SEPARATORS);
+    if name.is_empty() {
+        return None;
+    }
+
+    // Windows reserves device names even with an extension
+    let device = name.split('.').next().unwrap_or(name).to_uppercase();
+    let reserved = matches!(device.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || ((device.starts_with("COM") || device.starts_with("LPT"))
+            && device.len() == 4
+            && device.ends_with(|c: char| c.is_ascii_digit()));
+    Some(match reserved {
+        true => format!("{}_", name),
+        false => truncate(name, MAX_NAME_LEN).to_string(),
+    })
+}
+
+/// The longest prefix of `name` that fits in `max` bytes
+fn truncate(name: &str, max: usize) -> &str {
+    let mut end = name.len().min(max);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    name[..end].trim_end_matches(SEPARATORS)
+}
+
+/// A track's tags together with what the rest of the library says about its album
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub metadata: TrackMetadata,
+    /// True if any track of the album is on a disc other than the first
+    pub multi_disc: bool,
+    /// Title to use when the tags have none: the current file name
+    pub fallback_title: String,
+}
+
+impl Track {
+    /// True for tracks flagged as part of a compilation or credited to various artists
+    pub fn is_compilation(&self) -> bool {
+        self.metadata.compilation == Some(true)
+            || self
+                .metadata
+                .album_artist
+                .as_deref()
+                .is_some_and(|a| a.eq_ignore_ascii_case(VARIOUS_ARTISTS))
+    }
+
+    fn album_artist(&self) -> String {
+        let metadata = &self.metadata;
+        match (&metadata.album_artist, metadata.artists.first()) {
+            (Some(album_artist), _) => album_artist.clone(),
+            (None, _) if self.is_compilation() => VARIOUS_ARTISTS.to_string(),
+            (None, Some(artist)) => artist.clone(),
+            (None, None) => "Unknown Artist".to_string(),
+        }
+    }
+
+    /// The text a placeholder stands for ("" if unknown)
+    fn value(&self, name: &str) -> String {
+        let metadata = &self.metadata;
+        let number = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_default();
+        match name {
+            "artist" if metadata.artists.is_empty() => "Unknown Artist".to_string(),
+            "artist" => metadata.artists.join(", "),
+            "album_artist" => self.album_artist(),
+            "album" => metadata
+                .album
+                .clone()
+                .unwrap_or_else(|| "Unknown Album".to_string()),
+            "title" => metadata
+                .title
+                .clone()
+                .unwrap_or_else(|| self.fallback_title.clone()),
+            "year" => metadata.year.map(|y| y.to_string()).unwrap_or_default(),
+            "track" => number(metadata.track_number),
+            "total_tracks" => number(metadata.total_tracks),
+            "disc" if self.multi_disc => number(metadata.disc_number.or(Some(1))),
+            "disc" => String::new(),
+            "total_discs" => number(metadata.total_discs),
+            "genre" => metadata.genres.first().cloned().unwrap_or_default(),
+            "composer" => metadata.composer.clone().unwrap_or_default(),
+            "publisher" => metadata.publisher.clone().unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// One file to move
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Moves computed from the tags of a set of files, before anything is touched
+#[derive(Debug, Default)]
+pub struct RenamePlan {
+    /// Output directory the new paths are under
+    pub root: PathBuf,
+    pub moves: Vec<Move>,
+    /// Files already where the pattern puts them
+    pub unchanged: usize,
+    /// Files left alone, with the reason
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+impl RenamePlan {
+    /// Plan new paths under `root` for `files`, using `compilation` for compilations
+    ///
+    /// Tracks whose target is taken (by an existing file or another track) are skipped,
+    /// and an .lrc file beside a track moves with it.
+    pub fn build(
+        files: &[String],
+        root: &Path,
+        pattern: &Template,
+        compilation: &Template,
+    ) -> Self {
+        let mut plan = RenamePlan {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+
+        let mut tracks = Vec::new();
+        for file in files {
+            match reader::read_metadata(file) {
+                Ok(metadata) => tracks.push(metadata),
+                Err(e) => plan.skipped.push((PathBuf::from(file), e.to_string())),
+            }
+        }
+
+        // An album is multi-disc if any of its tracks is past disc 1
+        let album_key = |m: &TrackMetadata| {
+            let artist = m
+                .album_artist
+                .clone()
+                .or_else(|| m.artists.first().cloned());
+            (
+                artist.map(|a| a.to_lowercase()),
+                m.album.as_ref().map(|a| a.to_lowercase()),
+            )
+        };
+        let multi_disc: HashSet<_> = tracks
+            .iter()
+            .filter(|m| m.total_discs.unwrap_or(1) > 1 || m.disc_number.unwrap_or(1) > 1)
+            .map(album_key)
+            .collect();
+
+        let mut targets: HashMap<String, PathBuf> = HashMap::new();
+        for metadata in tracks {
+            let from = PathBuf::from(&metadata.file_path);
+            let format = AudioFormat::detect(&from).ok();
+            // Mislabeled files get the extension of their content
+            let extension = match (format, from.extension()) {
+                (Some(format), _) if format.extension_mismatch(&from).is_some() => {
+                    format.extension().to_string()
+                }
+                (_, Some(extension)) => extension.to_string_lossy().to_lowercase(),
+                (_, None) => String::new(),
+            };
+            let track = Track {
+                multi_disc: multi_disc.contains(&album_key(&metadata)),
+                fallback_title: from
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                metadata,
+            };
+            let template = if track.is_compilation() {
+                compilation
+            } else {
+                pattern
+            };
+            let to = root.join(template.render(&track, &extension));
+
+            let mut moves = vec![Move { from, to }];
+            if let Some(lyrics) = Lyrics::find_beside(&moves[0].from) {
+                let to = moves[0].to.with_extension("lrc");
+                moves.push(Move { from: lyrics, to });
+            }
+            for Move { from, to } in moves {
+                if from == to {
+                    plan.unchanged += 1;
+                    continue;
+                }
+                // Case-insensitive, since two names differing in case collide on macOS
+                let key = to.to_string_lossy().to_lowercase();
+                let reason = match targets.get(&key) {
+                    Some(other) => Some(format!("{} is also moving there", other.display())),
+                    None if to.exists() => Some(format!("{} already exists", to.display())),
+                    None => None,
+                };
+                match reason {
+                    Some(reason) => plan.skipped.push((from, reason)),
+                    None => {
+                        targets.insert(key, from.clone());
+                        plan.moves.push(Move { from, to });
+                    }
+                }
+            }
+        }
+        plan
+    }
+
+    /// Carry out the moves, recording each in the undo journal under `run_id`
+    ///
+    /// All or nothing: if any move fails, the ones already made are moved back and
+    /// dropped from the journal. Directories left empty are removed afterwards.
+    pub fn execute(&self, journal: &str, run_id: &str) -> Result<()> {
+        let mut done: Vec<&Move> = Vec::new();
+        let mut entries = Vec::new();
+
+        for step in &self.moves {
+            let moved = step
+                .to
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .map_err(AgentError::from)
+                .and_then(|_| move_file(&step.from, &step.to));
+            let recorded = moved.and_then(|_| {
+                done.push(step);
+                let entry = UndoEntry::moved(run_id, &step.from, &step.to)?;
+                UndoJournal::append(journal, &entry)?;
+                entries.push(entry);
+                Ok(())
+            });
+
+            if let Err(e) = recorded {
+                for step in done.iter().rev() {
+                    if move_file(&step.to, &step.from).is_ok() {
+                        remove_empty_dirs(&step.to, &self.root);
+                    }
+                }
+                if !entries.is_empty() {
+                    UndoJournal::load(journal)?.remove(&entries)?;
+                }
+                return Err(AgentError::FileRead(format!(
+                    "Renaming stopped at {} and the {} earlier move(s) were undone: {}",
+                    step.from.display(),
+                    done.len(),
+                    e
+                )));
+            }
+        }
+
+        for step in &self.moves {
+            remove_empty_dirs(&step.from, &self.root);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(artist: &str, album: &str, title: &str, track: u32) -> Track {
+        let mut metadata = TrackMetadata::default();
+        metadata.set_field("artist", artist);
+        metadata.set_field("album", album);
+        metadata.set_field("title", title);
+        metadata.track_number = Some(track);
+        metadata.year = Some(1990);
+        Track {
+            metadata,
+            multi_disc: false,
+            fallback_title: "01 untitled".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_pattern() {
+        let pattern = Template::parse(&format!("{}.mp3", DEFAULT_PATTERN)).unwrap();
+        let compilation = Template::parse(DEFAULT_COMPILATION_PATTERN).unwrap();
+
+        let mut song = track("AC/DC", "Back in Black", "Hells Bells", 1);
+        assert_eq!(
+            pattern.render(&song, "flac"),
+            PathBuf::from("AC_DC/1990 - Back in Black/01 Hells Bells.flac")
+        );
+
+        song.multi_disc = true;
+        song.metadata.disc_number = Some(2);
+        song.metadata.year = None;
+        song.metadata.title = Some("What? Why: <Now>".to_string());
+        assert_eq!(
+            pattern.render(&song, "mp3"),
+            PathBuf::from("AC_DC/Back in Black/2-01 What_ Why_ _Now.mp3")
+        );
+
+        let mut various = track("Orbital", "Trance Classics", "Chime", 7);
+        various.metadata.compilation = Some(true);
+        assert!(various.is_compilation());
+        assert_eq!(
+            compilation.render(&various, "mp3"),
+            PathBuf::from("Various Artists/1990 - Trance Classics/07 Orbital - Chime.mp3")
+        );
+
+        let mut untitled = track("CON", "Nul", "", 1);
+        untitled.metadata.title = None;
+        assert_eq!(
+            pattern.render(&untitled, "mp3"),
+            PathBuf::from("CON_/1990 - Nul/01 01 untitled.mp3")
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for pattern in ["{artist", "{artst}/{title}", "{track:xx}", "flat.mp3"] {
+            assert!(Template::parse(pattern).is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_execute_and_undo() {
+        let dir = std::env::temp_dir().join("music-agent-rename-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("incoming")).unwrap();
+        let from = dir.join("incoming").join("track01.mp3");
+        fs::write(&from, b"audio").unwrap();
+        let journal = dir.join("undo.jsonl").to_string_lossy().to_string();
+
+        let plan = RenamePlan {
+            root: dir.clone(),
+            moves: vec![Move {
+                from: from.clone(),
+                to: dir.join("Artist").join("Album").join("01 Song.mp3"),
+            }],
+            ..Default::default()
+        };
+        plan.execute(&journal, "run-1").unwrap();
+        let moved = plan.moves[0].to.exists() && !dir.join("incoming").exists();
+
+        let mut undo = UndoJournal::load(&journal).unwrap();
+        let entries = undo.run("last");
+        undo.restore(&entries[0], false).unwrap();
+        undo.remove(&entries).unwrap();
+        let restored = fs::read(&from).unwrap();
+        let cleaned = !dir.join("Artist").exists();
+
+        // A move into a taken path fails and undoes the earlier ones
+        fs::write(dir.join("taken.mp3"), b"other").unwrap();
+        let failing = RenamePlan {
+            root: dir.clone(),
+            moves: vec![
+                Move {
+                    from: from.clone(),
+                    to: dir.join("first.mp3"),
+                },
+                Move {
+                    from: dir.join("missing.mp3"),
+                    to: dir.join("taken.mp3"),
+                },
+            ],
+            ..Default::default()
+        };
+        let failed = failing.execute(&journal, "run-2").is_err();
+        let rolled_back = from.exists() && !dir.join("first.mp3").exists();
+        let remaining = UndoJournal::load(&journal).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(moved);
+        assert_eq!(restored, b"audio");
+        assert!(cleaned);
+        assert!(failed);
+        assert!(rolled_back);
+        assert!(remaining.entries().is_empty());
+    }
+}
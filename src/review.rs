@@ -0,0 +1,187 @@
+use crate::error::Result;
+use crate::feedback::FeedbackDecision;
+use crate::suggestions::{SuggestionAction, SuggestionStatus, SuggestionsReport};
+use std::io::{BufRead, Write};
+
+/// What the reviewer chose for one suggestion
+#[derive(Debug, PartialEq)]
+enum ReviewAction {
+    Accept,
+    Reject,
+    Edit,
+    Skip,
+    Quit,
+}
+
+impl ReviewAction {
+    fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "a" | "accept" | "y" | "yes" => Some(Self::Accept),
+            "r" | "reject" | "n" | "no" => Some(Self::Reject),
+            "e" | "edit" => Some(Self::Edit),
+            "s" | "skip" | "" => Some(Self::Skip),
+            "q" | "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Walk each suggestion and record the reviewer's decision on the report
+///
+/// Returns the decisions made in this session so they can be logged as feedback.
+/// Skipped suggestions keep their previous status; end of input behaves like quit.
+pub fn review_suggestions<R: BufRead, W: Write>(
+    report: &mut SuggestionsReport,
+    input: &mut R,
+    output: &mut W,
+) -> Result<Vec<FeedbackDecision>> {
+    let mut decisions = Vec::new();
+    let total = report.suggestions.len();
+
+    for i in 0..total {
+        let suggestion = &report.suggestions[i];
+        writeln!(output, "\n{}", "-".repeat(62))?;
+        writeln!(
+            output,
+            "{}/{}. {} (Confidence: {}, Status: {:?})",
+            i + 1,
+            total,
+            suggestion.field.to_uppercase(),
+            suggestion.confidence,
+            suggestion.status
+        )?;
+        writeln!(
+            output,
+            "   Current:   {}",
+            suggestion.current_value.as_deref().unwrap_or("(none)")
+        )?;
+        writeln!(output, "   Suggested: {}", suggestion.display_value())?;
+        writeln!(output, "   Reason:    {}", suggestion.reason)?;
+
+        let action = loop {
+            write!(output, "   [a]ccept  [r]eject  [e]dit  [s]kip  [q]uit > ")?;
+            output.flush()?;
+
+            let Some(line) = read_line(input)? else {
+                break ReviewAction::Quit;
+            };
+            match ReviewAction::parse(&line) {
+                Some(ReviewAction::Edit) => {
+                    write!(output, "   New value: ")?;
+                    output.flush()?;
+                    match read_line(input)? {
+                        Some(value) if !value.trim().is_empty() => {
+                            let suggestion = &mut report.suggestions[i];
+                            let value = value.trim().to_string();
+                            if suggestion.edited_from.is_none()
+                                && value != suggestion.suggested_value
+                            {
+                                suggestion.edited_from = Some(suggestion.suggested_value.clone());
+                            }
+                            suggestion.suggested_value = value;
+                            // Typing a value turns a removal into a regular change
+                            suggestion.action = SuggestionAction::Set;
+                            break ReviewAction::Edit;
+                        }
+                        Some(_) => writeln!(output, "   (empty value, edit cancelled)")?,
+                        None => break ReviewAction::Quit,
+                    }
+                }
+                Some(action) => break action,
+                None => writeln!(output, "   Please answer a, r, e, s or q")?,
+            }
+        };
+
+        let suggestion = &mut report.suggestions[i];
+        match action {
+            ReviewAction::Accept | ReviewAction::Edit => {
+                suggestion.status = SuggestionStatus::Accepted;
+                decisions.push(FeedbackDecision::new(
+                    &report.current_metadata,
+                    suggestion,
+                    true,
+                ));
+            }
+            ReviewAction::Reject => {
+                suggestion.status = SuggestionStatus::Rejected;
+                decisions.push(FeedbackDecision::new(
+                    &report.current_metadata,
+                    suggestion,
+                    false,
+                ));
+            }
+            ReviewAction::Skip => {}
+            ReviewAction::Quit => break,
+        }
+    }
+
+    report.should_apply = report
+        .suggestions
+        .iter()
+        .any(|s| s.status == SuggestionStatus::Accepted);
+
+    Ok(decisions)
+}
+
+fn read_line<R: BufRead>(input: &mut R) -> Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::TrackMetadata;
+    use crate::suggestions::MetadataSuggestion;
+    use std::io::Cursor;
+
+    fn report() -> SuggestionsReport {
+        let metadata = TrackMetadata {
+            file_path: "song.mp3".to_string(),
+            artists: vec!["MF DOOM".to_string()],
+            title: None,
+            album: None,
+            year: None,
+            genres: Vec::new(),
+            track_number: None,
+            album_artist: None,
+            duration_seconds: None,
+            ..Default::default()
+        };
+        let suggestions = ["genre", "year", "album"]
+            .iter()
+            .map(|field| MetadataSuggestion {
+                field: field.to_string(),
+                current_value: None,
+                suggested_value: "value".to_string(),
+                confidence: "High".to_string(),
+                reason: String::new(),
+                action: SuggestionAction::Set,
+                status: SuggestionStatus::Pending,
+                edited_from: None,
+            })
+            .collect();
+        SuggestionsReport::new("song.mp3".to_string(), metadata, suggestions, String::new())
+    }
+
+    #[test]
+    fn test_review_accept_edit_reject() {
+        let mut report = report();
+        let mut input = Cursor::new("x\ne\nHip-Hop\nr\ns\n");
+        let mut output = Vec::new();
+
+        let decisions = review_suggestions(&mut report, &mut input, &mut output).unwrap();
+
+        assert_eq!(decisions.len(), 2);
+        assert_eq!(report.suggestions[0].status, SuggestionStatus::Accepted);
+        assert_eq!(report.suggestions[0].suggested_value, "Hip-Hop");
+        assert_eq!(report.suggestions[0].edited_from.as_deref(), Some("value"));
+        assert_eq!(report.suggestions[1].status, SuggestionStatus::Rejected);
+        assert_eq!(report.suggestions[2].status, SuggestionStatus::Pending);
+        assert!(report.should_apply);
+        assert_eq!(report.applicable_suggestions().len(), 1);
+    }
+}
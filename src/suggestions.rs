@@ -1,5 +1,7 @@
 use crate::error::{AgentError, Result};
+use crate::feedback::FeedbackDecision;
 use crate::metadata::TrackMetadata;
+use crate::prompts::TemplateInfo;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -9,9 +11,49 @@ use std::path::{Path, PathBuf};
 pub struct MetadataSuggestion {
     pub field: String,
     pub current_value: Option<String>,
+    /// Empty for `Remove` suggestions
     pub suggested_value: String,
     pub confidence: String, // "High", "Medium", "Low"
     pub reason: String,
+    #[serde(default)]
+    pub action: SuggestionAction,
+    #[serde(default)]
+    pub status: SuggestionStatus,
+    /// The LLM's original value when a reviewer edited `suggested_value`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_from: Option<String>,
+}
+
+/// Whether a suggestion sets a new value or deletes the field from the file
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionAction {
+    #[default]
+    Set,
+    Remove,
+}
+
+impl MetadataSuggestion {
+    /// Marker the LLM writes as the SUGGESTED value to delete a field
+    pub const REMOVE_MARKER: &'static str = "REMOVE_FIELD";
+
+    /// Suggested value for display ("(remove)" for deletions)
+    pub fn display_value(&self) -> String {
+        match self.action {
+            SuggestionAction::Set => self.suggested_value.clone(),
+            SuggestionAction::Remove => "(remove)".to_string(),
+        }
+    }
+}
+
+/// Reviewer decision on a single suggestion
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionStatus {
+    #[default]
+    Pending,
+    Accepted,
+    Rejected,
 }
 
 /// Collection of suggestions for a track
@@ -23,6 +65,25 @@ pub struct SuggestionsReport {
     pub suggestions: Vec<MetadataSuggestion>,
     pub llm_analysis: String,
     pub should_apply: bool, // Whether user should apply changes
+    #[serde(default)]
+    pub prompt_template: Option<TemplateInfo>,
+    #[serde(default)]
+    pub few_shot_examples: Vec<FeedbackDecision>,
+    /// SHA-256 of the original's audio data when the suggestions were made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_hash: Option<String>,
+    /// Copies written from this report
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applied: Vec<AppliedCopy>,
+}
+
+/// A copy written by --apply/--review
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppliedCopy {
+    pub file_path: String,
+    pub timestamp: String,
+    /// SHA-256 of the audio data, verified equal in the original and the copy
+    pub audio_hash: String,
 }
 
 impl SuggestionsReport {
@@ -39,7 +100,84 @@ impl SuggestionsReport {
             suggestions,
             llm_analysis,
             should_apply: false,
+            prompt_template: None,
+            few_shot_examples: Vec::new(),
+            audio_hash: None,
+            applied: Vec::new(),
+        }
+    }
+
+    /// Record which prompt template produced these suggestions
+    pub fn with_prompt_template(mut self, template: TemplateInfo) -> Self {
+        self.prompt_template = Some(template);
+        self
+    }
+
+    /// Record the past decisions that were injected into the prompt
+    pub fn with_few_shot_examples(mut self, examples: Vec<FeedbackDecision>) -> Self {
+        self.few_shot_examples = examples;
+        self
+    }
+
+    /// Record the hash of the original's audio data
+    pub fn with_audio_hash(mut self, audio_hash: Option<String>) -> Self {
+        self.audio_hash = audio_hash;
+        self
+    }
+
+    /// Record a copy written from these suggestions
+    pub fn record_applied(&mut self, file_path: &str, audio_hash: &str) {
+        self.applied.push(AppliedCopy {
+            file_path: file_path.to_string(),
+            timestamp: chrono::Local::now().to_rfc3339(),
+            audio_hash: audio_hash.to_string(),
+        });
+    }
+
+    /// Fold generated suggestions into the report, returning how many fields changed
+    ///
+    /// A pending `Set` suggestion for the same field takes the new value and both
+    /// reasons; a `Remove` suggestion for it stays the reviewer's call.
+    pub fn merge(&mut self, generated: Vec<MetadataSuggestion>) -> usize {
+        let mut changed = 0;
+        for suggestion in generated {
+            match self
+                .suggestions
+                .iter_mut()
+                .find(|s| s.field.eq_ignore_ascii_case(&suggestion.field))
+            {
+                Some(existing) if existing.action == SuggestionAction::Set => {
+                    existing.suggested_value = suggestion.suggested_value;
+                    existing.reason = format!("{} ({})", existing.reason, suggestion.reason);
+                }
+                Some(_) => continue,
+                None => self.suggestions.push(MetadataSuggestion {
+                    current_value: self.current_metadata.field_value(&suggestion.field),
+                    ..suggestion
+                }),
+            }
+            changed += 1;
         }
+        changed
+    }
+
+    /// True once any suggestion has been accepted or rejected
+    pub fn is_reviewed(&self) -> bool {
+        self.suggestions
+            .iter()
+            .any(|s| s.status != SuggestionStatus::Pending)
+    }
+
+    /// Suggestions that `--apply` should write
+    ///
+    /// Reviewed reports apply only accepted suggestions; unreviewed (hand-edited) files
+    /// apply everything they contain.
+    pub fn applicable_suggestions(&self) -> Vec<&MetadataSuggestion> {
+        let reviewed = self.is_reviewed();
+        self.suggestions
+            .iter()
+            .filter(|s| !reviewed || s.status == SuggestionStatus::Accepted)
+            .collect()
     }
 
     /// Save suggestions to a JSON file in public/suggestions/ directory
@@ -69,16 +207,21 @@ impl SuggestionsReport {
 
         let suggestions_path =
             suggestions_dir.join(format!("{}.suggestions.json", file_stem.to_string_lossy()));
+        let suggestions_path = suggestions_path.to_string_lossy().to_string();
 
+        self.save_to_path(&suggestions_path)?;
+
+        Ok(suggestions_path)
+    }
+
+    /// Save suggestions to an explicit JSON path (e.g. back to the file they were loaded from)
+    pub fn save_to_path(&self, file_path: &str) -> Result<()> {
         let json = serde_json::to_string_pretty(self).map_err(|e| {
             AgentError::MetadataParse(format!("Failed to serialize suggestions: {}", e))
         })?;
 
-        fs::write(&suggestions_path, json).map_err(|e| {
-            AgentError::FileRead(format!("Failed to write suggestions file: {}", e))
-        })?;
-
-        Ok(suggestions_path.to_string_lossy().to_string())
+        fs::write(file_path, json)
+            .map_err(|e| AgentError::FileRead(format!("Failed to write suggestions file: {}", e)))
     }
 
     /// Load suggestions from a JSON file
@@ -90,34 +233,33 @@ impl SuggestionsReport {
             .map_err(|e| AgentError::MetadataParse(format!("Failed to parse suggestions: {}", e)))
     }
 
-    /// Apply suggestions to create updated metadata
+    /// Apply suggestions to create updated metadata (removed fields become None)
     pub fn apply_suggestions(&self) -> TrackMetadata {
         let mut updated = self.current_metadata.clone();
 
-        for suggestion in &self.suggestions {
-            match suggestion.field.as_str() {
-                "artist" => updated.artist = Some(suggestion.suggested_value.clone()),
-                "title" => updated.title = Some(suggestion.suggested_value.clone()),
-                "album" => updated.album = Some(suggestion.suggested_value.clone()),
-                "year" => {
-                    if let Ok(year) = suggestion.suggested_value.parse::<i32>() {
-                        updated.year = Some(year);
-                    }
+        for suggestion in self.applicable_suggestions() {
+            match suggestion.action {
+                SuggestionAction::Set => {
+                    updated.set_field(&suggestion.field, &suggestion.suggested_value);
                 }
-                "genre" => updated.genre = Some(suggestion.suggested_value.clone()),
-                "album_artist" => updated.album_artist = Some(suggestion.suggested_value.clone()),
-                "track_number" => {
-                    if let Ok(track) = suggestion.suggested_value.parse::<u32>() {
-                        updated.track_number = Some(track);
-                    }
+                SuggestionAction::Remove => {
+                    updated.clear_field(&suggestion.field);
                 }
-                _ => {}
             }
         }
 
         updated
     }
 
+    /// Fields the applicable suggestions delete from the file
+    pub fn removed_fields(&self) -> Vec<String> {
+        self.applicable_suggestions()
+            .into_iter()
+            .filter(|s| s.action == SuggestionAction::Remove)
+            .map(|s| s.field.clone())
+            .collect()
+    }
+
     /// Display suggestions in a user-friendly format
     pub fn display(&self) {
         println!("\n{}", "=".repeat(62));
@@ -137,11 +279,20 @@ impl SuggestionsReport {
                 suggestion.confidence
             );
             println!("   Current:  {:?}", suggestion.current_value);
-            println!("   Suggested: {}", suggestion.suggested_value);
+            println!("   Suggested: {}", suggestion.display_value());
             println!("   Reason: {}", suggestion.reason);
         }
 
         println!("\n{}", "-".repeat(62));
+        if let Some(ref template) = self.prompt_template {
+            println!("🧩 Prompt template: {} ({})", template.name, template.hash);
+        }
+        if !self.few_shot_examples.is_empty() {
+            println!("📚 Few-shot examples used:");
+            for example in &self.few_shot_examples {
+                println!("   {}", example.to_prompt_line());
+            }
+        }
         println!("📝 LLM Analysis Summary:");
         println!(
             "{}",